@@ -0,0 +1,76 @@
+//! Compare hashing plain old data field by field with `#[hash_by]` and as raw bytes with `#[hash_by(bytes)]`.
+use bytemuck::{Pod, Zeroable};
+use cmp_by_derive::HashBy;
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::hash::{BuildHasher, BuildHasherDefault, DefaultHasher};
+use std::hint::black_box;
+
+#[derive(Clone, Copy, Hash, Pod, Zeroable)]
+#[repr(C)]
+struct Pixel {
+    r: u8,
+    g: u8,
+    b: u8,
+    a: u8,
+}
+
+#[derive(HashBy)]
+struct Image {
+    #[hash_by]
+    pixels: [Pixel; 4096],
+}
+
+#[derive(HashBy)]
+struct ImageBytes {
+    #[hash_by(bytes)]
+    pixels: [Pixel; 4096],
+}
+
+#[derive(HashBy)]
+struct Samples {
+    #[hash_by]
+    values: [u32; 4096],
+}
+
+#[derive(HashBy)]
+struct SamplesBytes {
+    #[hash_by(bytes)]
+    values: [u32; 4096],
+}
+
+fn hash_bytes(c: &mut Criterion) {
+    let state = BuildHasherDefault::<DefaultHasher>::default();
+    let pixel = Pixel {
+        r: 1,
+        g: 2,
+        b: 3,
+        a: 4,
+    };
+
+    let mut group = c.benchmark_group("struct array");
+    let image = Image {
+        pixels: [pixel; 4096],
+    };
+    let image_bytes = ImageBytes {
+        pixels: [pixel; 4096],
+    };
+    group.bench_function("hash_by", |b| b.iter(|| state.hash_one(black_box(&image))));
+    group.bench_function("hash_by(bytes)", |b| {
+        b.iter(|| state.hash_one(black_box(&image_bytes)))
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("integer array");
+    let samples = Samples { values: [7; 4096] };
+    let samples_bytes = SamplesBytes { values: [7; 4096] };
+    group.bench_function("hash_by", |b| {
+        b.iter(|| state.hash_one(black_box(&samples)))
+    });
+    group.bench_function("hash_by(bytes)", |b| {
+        b.iter(|| state.hash_one(black_box(&samples_bytes)))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, hash_bytes);
+criterion_main!(benches);
@@ -1,23 +1,33 @@
-use crate::parsing::{parse_input, ParsedFields, ParsedInput, ParsingError};
-use proc_macro2::TokenStream;
+use crate::eq_by::{gen_eq_expression, gen_other_partial_eq};
+use crate::parsing::{
+    add_bounds, assert_bounds, fold_token_errors, parse_input, Flags, Key, OtherComparison,
+    ParsedFields, ParsedInput, ParsingError,
+};
+use proc_macro2::{Ident, Span, TokenStream};
 use quote::{quote, quote_spanned, ToTokens};
-use syn::{parse2, parse_quote_spanned, spanned::Spanned, DeriveInput, Error, Expr, Index};
+use syn::{
+    parse2, parse_quote, parse_quote_spanned, spanned::Spanned, DeriveInput, Error, Expr, Generics,
+    Index, Lifetime, LitStr, Type, TypePath, Visibility,
+};
 
 pub fn impl_cmp_by_derive(input: DeriveInput) -> TokenStream {
     // println!("Entered impl_cmp_by_derive");
     let input_span = input.span();
     let struct_name = input.ident.clone();
+    let vis = input.vis.clone();
 
     let ParsedInput {
+        flags,
         expressions: sortable_expressions,
         fields: sortable_fields,
-        generics,
-        generic_arguments: generics_params,
+        others,
+        mut generics,
     } = match parse_input(input, "cmp_by") {
         Ok(value) => value,
         Err(err) => {
             return match err {
                 ParsingError::Error(err) => err,
+                ParsingError::Union(span) => Error::new(span, "CmpBy: unions are not supported"),
                 ParsingError::NoField(span) => Error::new(
                     span,
                     "CmpBy: no field to compare on. Mark fields to compare on with #[cmp_by]",
@@ -28,201 +38,4641 @@ pub fn impl_cmp_by_derive(input: DeriveInput) -> TokenStream {
     };
     // println!("Successfully parsed input");
 
-    let field_ord_statement = match &sortable_fields {
-        ParsedFields::Struct(sortable_expr) => gen_cmp_exprs(sortable_expr),
+    // Checked on every field, `eq` fields are left out of the ordering below.
+    if let Some((span, name)) = sortable_fields
+        .keys()
+        .find_map(|key| key.modifiers.hash_by_only().next())
+    {
+        return Error::new(span, format!("CmpBy: `{name}` only applies to HashBy"))
+            .into_compile_error();
+    }
+
+    if let Err(err) = check_trait_selection(&flags, &sortable_expressions, &sortable_fields) {
+        return err.into_compile_error();
+    }
+
+    // Fields marked with `eq` are only compared for equality.
+    let ordered_fields = sortable_fields.filter(|key| key.modifiers.eq.is_none());
+    let eq = sortable_fields.keys().find_map(|key| key.modifiers.eq);
+    if let (ParsedFields::Struct(keys), Some(eq)) = (&ordered_fields, eq) {
+        let ordered_expressions = sortable_expressions
+            .iter()
+            .any(|key| key.expr.to_token_stream().to_string() != "_fields");
+        if keys.is_empty() && !ordered_expressions {
+            return Error::new(
+                eq,
+                "CmpBy: no field to order by, fields marked with `eq` are only compared for equality",
+            )
+            .into_compile_error();
+        }
+    }
+
+    let ord_expression = match flags.tuple_cmp {
+        // Both sides build the same tuple, the arity always matches.
+        Some(tuple_cmp) => [quote!(self), quote!(other)]
+            .iter()
+            .map(|this| {
+                gen_cmp_key(
+                    (tuple_cmp, "tuple_cmp"),
+                    &flags,
+                    &sortable_expressions,
+                    &ordered_fields,
+                    this,
+                )
+            })
+            .collect::<Result<Vec<_>, Error>>()
+            .map(|keys| {
+                let (this, other) = (&keys[0], &keys[1]);
+                quote!(#this.cmp(&#other))
+            }),
+        None => gen_ord_expression(
+            "CmpBy",
+            input_span,
+            &flags,
+            &sortable_expressions,
+            &ordered_fields,
+            Comparison::Total,
+        )
+        .map(ToTokens::into_token_stream),
+    };
+    let ord_expression = match ord_expression {
+        Ok(ord_expression) => ord_expression,
+        Err(err) => return err.into_compile_error(),
+    };
+
+    let eq_expression = gen_eq_expression(input_span, &sortable_expressions, &sortable_fields);
+    let inline = match flags.inline() {
+        Ok(inline) => inline,
+        Err(err) => return err.into_compile_error(),
+    };
+    let other_partial_eq = gen_other_partial_eq(&struct_name, &generics, &others, &inline);
+    let other_partial_ord = flags
+        .eq_only
+        .is_none()
+        .then(|| gen_other_partial_ord(&struct_name, &generics, &others, &inline));
+
+    // The wrapper is declared like the type, before the bounds of the comparisons are added.
+    let declared_generics = generics.clone();
+
+    // Without `Ord`, every field only needs to be compared for equality.
+    let bounded_keys = |eq: bool| {
+        sortable_fields.keys().filter(move |key| {
+            needs_bound(key) && (key.modifiers.eq.is_some() || flags.eq_only.is_some()) == eq
+        })
+    };
+    add_bounds(&mut generics, bounded_keys(false), quote!(::core::cmp::Ord));
+    add_bounds(&mut generics, bounded_keys(true), quote!(::core::cmp::Eq));
+    let bound_assertions = [
+        assert_bounds(
+            &struct_name,
+            &generics,
+            bounded_keys(false),
+            quote!(::core::cmp::Ord),
+        ),
+        assert_bounds(
+            &struct_name,
+            &generics,
+            bounded_keys(true),
+            quote!(::core::cmp::PartialEq),
+        ),
+    ];
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    let reverse_wrapper = match &flags.gen_reverse {
+        Some(name) => {
+            match gen_reverse_wrapper(
+                name,
+                &vis,
+                &struct_name,
+                &declared_generics,
+                &generics,
+                &inline,
+            ) {
+                Ok(wrapper) => Some(wrapper),
+                Err(err) => return err.into_compile_error(),
+            }
+        }
+        None => None,
+    };
+
+    let key_methods = match flags.key_methods().next() {
+        Some(flag) => match gen_cmp_key(
+            flag,
+            &flags,
+            &sortable_expressions,
+            &ordered_fields,
+            &quote!(self),
+        ) {
+            Ok(cmp_key) => {
+                let ParsedFields::Struct(fields) = &ordered_fields else {
+                    unreachable!("gen_cmp_key() rejects enums");
+                };
+                // Top-level items and projected fields have no type that can be named, their key is opaque.
+                let (lifetime, receiver, key_type) =
+                    match gen_key_type("gen_key", &flags, &sortable_expressions, fields) {
+                        Ok(key_type) => (quote!(<'key>), quote!(&'key self), key_type),
+                        Err(_) => (quote!(), quote!(&self), quote!(impl ::core::cmp::Ord + '_)),
+                    };
+                let cmp_key_method = flags.gen_key.map(|_| {
+                    quote! {
+                        /// The compared items, in the order they are compared.
+                        #inline
+                        pub fn cmp_key #lifetime(#receiver) -> #key_type {
+                            #cmp_key
+                        }
+                    }
+                });
+                let min_heap_key_method = flags.gen_min_heap_key.map(|_| {
+                    quote! {
+                        /// The compared items, reversed so that a `BinaryHeap` pops the least value first.
+                        #inline
+                        pub fn min_heap_key #lifetime(#receiver) -> ::core::cmp::Reverse<#key_type> {
+                            ::core::cmp::Reverse(#cmp_key)
+                        }
+                    }
+                });
+                Some(quote! {
+                    #[automatically_derived]
+                    impl #impl_generics #struct_name #ty_generics #where_clause {
+                        #cmp_key_method
+                        #min_heap_key_method
+                    }
+                })
+            }
+            Err(err) => return err.into_compile_error(),
+        },
+        None => None,
+    };
+
+    let sort_key_impl = match flags.impl_sort_key {
+        Some(impl_sort_key) => {
+            match gen_sort_key(
+                impl_sort_key,
+                &flags,
+                &sortable_expressions,
+                &ordered_fields,
+            ) {
+                Ok((key_type, sort_key)) => Some(quote! {
+                    #[automatically_derived]
+                    impl #impl_generics SortKey for #struct_name #ty_generics #where_clause {
+                        type Key<'key> = #key_type where Self: 'key;
+
+                        #inline
+                        fn sort_key(&self) -> Self::Key<'_> {
+                            #sort_key
+                        }
+                    }
+                }),
+                Err(err) => return err.into_compile_error(),
+            }
+        }
+        None => None,
+    };
+
+    let const_cmp = match flags.const_fn {
+        Some(const_fn) => {
+            match gen_const_cmp(const_fn, &flags, &sortable_expressions, &ordered_fields) {
+                Ok(const_cmp) => Some(quote! {
+                    #[automatically_derived]
+                    impl #impl_generics #struct_name #ty_generics #where_clause {
+                        /// Compare like `Ord::cmp`, in const contexts.
+                        #inline
+                        pub const fn const_cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+                            #const_cmp
+                        }
+                    }
+                }),
+                Err(err) => return err.into_compile_error(),
+            }
+        }
+        None => None,
+    };
+
+    let eq_impls = flags.ord_only.is_none().then(|| {
+        quote_spanned! {input_span =>
+            #[automatically_derived]
+            impl #impl_generics ::core::cmp::Eq for #struct_name #ty_generics #where_clause {}
+
+            #[automatically_derived]
+            impl #impl_generics ::core::cmp::PartialEq<Self> for #struct_name #ty_generics #where_clause {
+                #inline
+                fn eq(&self, other: &Self) -> bool {
+                    #eq_expression
+                }
+            }
+        }
+    });
+    let ord_impls = flags.eq_only.is_none().then(|| {
+        quote_spanned! {input_span =>
+            #[automatically_derived]
+            impl #impl_generics ::core::cmp::PartialOrd<Self> for #struct_name #ty_generics #where_clause {
+                #inline
+                fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+                    ::core::option::Option::Some(self.cmp(other))
+                }
+            }
+
+            #[automatically_derived]
+            impl #impl_generics ::core::cmp::Ord for #struct_name #ty_generics #where_clause {
+                #inline
+                fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+                    #ord_expression
+                }
+            }
+        }
+    });
+
+    quote_spanned! {input_span =>
+        #eq_impls
+        #ord_impls
+        #other_partial_eq
+        #other_partial_ord
+        #key_methods
+        #sort_key_impl
+        #const_cmp
+        #reverse_wrapper
+        #(#bound_assertions)*
+    }
+}
+
+/// Reject the flags and modifiers that don't apply to the traits selected by `ord_only` or `eq_only`.
+fn check_trait_selection(
+    flags: &Flags,
+    expressions: &[Key],
+    fields: &ParsedFields,
+) -> Result<(), Error> {
+    match (flags.ord_only, flags.eq_only) {
+        (None, None) => Ok(()),
+        (Some(_), Some(eq_only)) => Err(Error::new(
+            eq_only,
+            "CmpBy: `eq_only` can't be combined with `ord_only`, nothing would be implemented",
+        )),
+        // `PartialEq` is implemented separately, so no field can be left out of the ordering.
+        (Some(_), None) => fields
+            .keys()
+            .filter_map(|key| key.modifiers.eq)
+            .map(|span| {
+                Error::new(
+                    span,
+                    "CmpBy: `eq` has no effect with `ord_only`, `PartialEq` is implemented separately",
+                )
+            })
+            .reduce(|mut acc, err| {
+                acc.combine(err);
+                acc
+            })
+            .map_or(Ok(()), Err),
+        (None, Some(_)) => {
+            let modifiers = expressions
+                .iter()
+                .chain(fields.keys())
+                .flat_map(|Key { modifiers, .. }| {
+                    [
+                        modifiers.reverse.map(|span| (span, "reverse")),
+                        modifiers.none_last.map(|span| (span, "none_last")),
+                        modifiers.eq.map(|span| (span, "eq")),
+                    ]
+                })
+                .flatten();
+            flags
+                .descending
+                .map(|span| (span, "descending"))
+                .into_iter()
+                .chain(flags.key_methods())
+                .chain(flags.tuple_cmp.map(|span| (span, "tuple_cmp")))
+                .chain(flags.const_fn.map(|span| (span, "const_fn")))
+                .chain(flags.impl_sort_key.map(|span| (span, "impl_sort_key")))
+                .chain(flags.gen_reverse.as_ref().map(|name| (name.span(), "gen_reverse")))
+                .chain(modifiers)
+                .map(|(span, name)| {
+                    Error::new(
+                        span,
+                        format!("CmpBy: `{name}` has no effect with `eq_only`, `Ord` isn't implemented"),
+                    )
+                })
+                .reduce(|mut acc, err| {
+                    acc.combine(err);
+                    acc
+                })
+                .map_or(Ok(()), Err)
+        }
+    }
+}
+
+/// The items of a single key, with the fields in place of `_fields`, or after the top-level items, paired with whether
+/// they are ordered in reverse, by `reverse`, a reversed `_fields` or `descending`.
+///
+/// Each item is reversed on its own rather than the whole key, so that `none_last` items keep `None` last.
+fn key_items<'a>(
+    flags: &Flags,
+    sortable_expressions: &'a [Key],
+    fields: &'a [Key],
+) -> Vec<(&'a Key, bool)> {
+    let is_fields = |key: &Key| key.expr.to_token_stream().to_string() == "_fields";
+    let items: Vec<(&Key, bool)> = if sortable_expressions.iter().any(is_fields) {
+        sortable_expressions
+            .iter()
+            .flat_map(|key| {
+                if is_fields(key) {
+                    let fields_reversed = key.modifiers.reverse.is_some();
+                    fields
+                        .iter()
+                        .map(|field| (field, fields_reversed))
+                        .collect()
+                } else {
+                    vec![(key, false)]
+                }
+            })
+            .collect()
+    } else {
+        sortable_expressions
+            .iter()
+            .chain(fields)
+            .map(|key| (key, false))
+            .collect()
+    };
+    items
+        .into_iter()
+        .map(|(key, fields_reversed)| {
+            let reversed =
+                key.modifiers.reverse.is_some() ^ fields_reversed ^ flags.descending.is_some();
+            (key, reversed)
+        })
+        .collect()
+}
+
+/// Generate the `Key` type and the body of `sort_key`, references to the compared fields in the order they are compared.
+fn gen_sort_key(
+    impl_sort_key: Span,
+    flags: &Flags,
+    sortable_expressions: &[Key],
+    sortable_fields: &ParsedFields,
+) -> Result<(TokenStream, TokenStream), Error> {
+    let sort_key = gen_cmp_key(
+        (impl_sort_key, "impl_sort_key"),
+        flags,
+        sortable_expressions,
+        sortable_fields,
+        &quote!(self),
+    )?;
+    let ParsedFields::Struct(fields) = sortable_fields else {
+        unreachable!("gen_cmp_key() rejects enums");
+    };
+    let key_type = gen_key_type("impl_sort_key", flags, sortable_expressions, fields)?;
+    Ok((key_type, sort_key))
+}
+
+/// Generate the type of the key built by `gen_cmp_key`, borrowing the value for `'key`.
+///
+/// The type has to be named, so only fields compared as they are, possibly reversed or with `none_last`,
+/// can be included.
+fn gen_key_type(
+    flag_name: &str,
+    flags: &Flags,
+    sortable_expressions: &[Key],
+    fields: &[Key],
+) -> Result<TokenStream, Error> {
+    let element_types = key_items(flags, sortable_expressions, fields)
+        .into_iter()
+        .map(|(key, reversed)| {
+            let modifiers = &key.modifiers;
+            let projection = [
+                (modifiers.cache.as_ref().map(Spanned::span), "cache"),
+                (modifiers.index.as_ref().map(Spanned::span), "index"),
+                (modifiers.cast.as_ref().map(Spanned::span), "as"),
+                (modifiers.key.as_ref().map(Spanned::span), "key"),
+                (modifiers.map.as_ref().map(Spanned::span), "map"),
+                (modifiers.deref, "deref"),
+                (modifiers.by_len, "by_len"),
+                (modifiers.by_display, "by_display"),
+                (modifiers.abs, "abs"),
+                (modifiers.ordered_float, "ordered_float"),
+                (modifiers.as_sorted, "as_sorted"),
+                (modifiers.as_pairs, "as_pairs"),
+            ]
+            .into_iter()
+            .find_map(|(span, name)| span.map(|span| (span, name)));
+            if let Some((span, name)) = projection {
+                return Err(Error::new(
+                    span,
+                    format!("CmpBy: `{flag_name}` can't include a field compared with `{name}`, the type of its key can't be named"),
+                ));
+            }
+            let Some(ty) = &key.ty else {
+                return Err(Error::new(
+                    key.expr.span(),
+                    format!(
+                        "CmpBy: `{flag_name}` can't include `{}`, only the keys of fields marked with #[cmp_by] have a type that can be named",
+                        key.expr.to_token_stream()
+                    ),
+                ));
+            };
+            let mut element = quote!(&'key #ty);
+            if reversed {
+                element = quote!(::core::cmp::Reverse<#element>);
+            }
+            if modifiers.none_last.is_some() {
+                element = quote!((bool, #element));
+            }
+            Ok(element)
+        })
+        .fold(Ok(vec![]), fold_token_errors)?;
+    Ok(quote!((#(#element_types,)*)))
+}
+
+/// Generate the body of `const_cmp`, comparing the fields with the operators of primitive types, which are usable in
+/// const contexts unlike `Ord::cmp`.
+fn gen_const_cmp(
+    const_fn: Span,
+    flags: &Flags,
+    sortable_expressions: &[Key],
+    sortable_fields: &ParsedFields,
+) -> Result<TokenStream, Error> {
+    let ParsedFields::Struct(fields) = sortable_fields else {
+        return Err(Error::new(
+            const_fn,
+            "CmpBy: `const_fn` only applies to structs",
+        ));
+    };
+    if let Some(item) = sortable_expressions
+        .iter()
+        .find(|key| key.expr.to_token_stream().to_string() != "_fields")
+    {
+        return Err(Error::new(
+            item.expr.span(),
+            "CmpBy: `const_fn` can't include top-level items, their type isn't known to be comparable in const contexts",
+        ));
+    }
+    let statements = fields
+        .iter()
+        .map(|key| {
+            let modifiers = &key.modifiers;
+            let unsupported = [
+                (modifiers.with.as_ref().map(Spanned::span), "with"),
+                (
+                    modifiers.collate_with.as_ref().map(Spanned::span),
+                    "collate_with",
+                ),
+                (modifiers.key.as_ref().map(Spanned::span), "key"),
+                (modifiers.map.as_ref().map(Spanned::span), "map"),
+                (modifiers.cache.as_ref().map(Spanned::span), "cache"),
+                (modifiers.index.as_ref().map(Spanned::span), "index"),
+                (modifiers.on_err.as_ref().map(Spanned::span), "on_err"),
+                (modifiers.epsilon.as_ref().map(Spanned::span), "epsilon"),
+                (
+                    modifiers.assume_total.as_ref().map(Spanned::span),
+                    "assume_total",
+                ),
+                (modifiers.none_last, "none_last"),
+                (modifiers.both_some_only, "both_some_only"),
+                (modifiers.total, "total"),
+                (modifiers.deref, "deref"),
+                (modifiers.case_insensitive, "case_insensitive"),
+                (modifiers.by_len, "by_len"),
+                (modifiers.by_display, "by_display"),
+                (modifiers.as_sorted, "as_sorted"),
+                (modifiers.as_pairs, "as_pairs"),
+                (modifiers.ordered_float, "ordered_float"),
+                (modifiers.lex.as_ref().map(Spanned::span), "lex"),
+            ]
+            .into_iter()
+            .find_map(|(span, name)| span.map(|span| (span, name)));
+            if let Some((span, name)) = unsupported {
+                return Err(Error::new(
+                    span,
+                    format!("CmpBy: `const_fn` can't include a field compared with `{name}`, it isn't usable in const contexts"),
+                ));
+            }
+            // The converted type is compared rather than the type of the field.
+            match modifiers.cast.as_ref().or(key.ty.as_ref()) {
+                Some(ty) if is_primitive(ty) => {}
+                ty => {
+                    return Err(Error::new(
+                        ty.map_or_else(|| key.expr.span(), Spanned::span),
+                        "CmpBy: `const_fn` only compares integers, `bool` and `char`, whose operators are usable in const contexts",
+                    ))
+                }
+            }
+            let (this, other) = if modifiers.reverse.is_some() == flags.descending.is_some() {
+                (key.value(&quote!(self)), key.value(&quote!(other)))
+            } else {
+                (key.value(&quote!(other)), key.value(&quote!(self)))
+            };
+            Ok(quote! {
+                if #this != #other {
+                    return if #this < #other {
+                        ::core::cmp::Ordering::Less
+                    } else {
+                        ::core::cmp::Ordering::Greater
+                    };
+                }
+            })
+        })
+        .fold(Ok(vec![]), fold_token_errors)?;
+    Ok(quote! {
+        #(#statements)*
+        ::core::cmp::Ordering::Equal
+    })
+}
+
+/// Whether `ty` is an integer, `bool` or `char`, which are compared by their operators.
+fn is_primitive(ty: &Type) -> bool {
+    const PRIMITIVES: [&str; 14] = [
+        "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize",
+        "bool", "char",
+    ];
+    matches!(ty, Type::Path(TypePath { qself: None, path }) if path
+        .get_ident()
+        .is_some_and(|ident| PRIMITIVES.contains(&ident.to_string().as_str())))
+}
+
+/// Generate the wrapper named by `gen_reverse`, which orders the type in reverse.
+///
+/// The wrapper is declared with the `declared_generics` of the type, and its impls are bounded by `generics`
+/// like the impls of the type.
+fn gen_reverse_wrapper(
+    name: &LitStr,
+    vis: &Visibility,
+    struct_name: &Ident,
+    declared_generics: &Generics,
+    generics: &Generics,
+    inline: &TokenStream,
+) -> Result<TokenStream, Error> {
+    let wrapper: Ident = name.parse()?;
+    let doc = format!(
+        " `{struct_name}` ordered in reverse, e.g. to pop the least value first from a `BinaryHeap`."
+    );
+    let (declared_params, _, declared_where_clause) = declared_generics.split_for_impl();
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    Ok(quote_spanned! {name.span() =>
+        #[doc = #doc]
+        #vis struct #wrapper #declared_params (pub #struct_name #ty_generics) #declared_where_clause;
+
+        #[automatically_derived]
+        impl #impl_generics ::core::cmp::Eq for #wrapper #ty_generics #where_clause {}
+
+        #[automatically_derived]
+        impl #impl_generics ::core::cmp::PartialEq<Self> for #wrapper #ty_generics #where_clause {
+            #inline
+            fn eq(&self, other: &Self) -> bool {
+                self.0 == other.0
+            }
+        }
+
+        #[automatically_derived]
+        impl #impl_generics ::core::cmp::PartialOrd<Self> for #wrapper #ty_generics #where_clause {
+            #inline
+            fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+                ::core::option::Option::Some(self.cmp(other))
+            }
+        }
+
+        #[automatically_derived]
+        impl #impl_generics ::core::cmp::Ord for #wrapper #ty_generics #where_clause {
+            #inline
+            fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+                other.0.cmp(&self.0)
+            }
+        }
+    })
+}
+
+/// Generate the tuple of the compared items of `this`, returned by the key methods, which orders like `Ord::cmp`.
+///
+/// `flag` is the flag requesting a key method or `tuple_cmp`, to report errors.
+fn gen_cmp_key(
+    (flag, flag_name): (Span, &str),
+    flags: &Flags,
+    sortable_expressions: &[Key],
+    sortable_fields: &ParsedFields,
+    this: &TokenStream,
+) -> Result<TokenStream, Error> {
+    let ParsedFields::Struct(fields) = sortable_fields else {
+        return Err(Error::new(
+            flag,
+            format!("CmpBy: `{flag_name}` only applies to structs, variants can't be ordered by a single key"),
+        ));
+    };
+    let elements = key_items(flags, sortable_expressions, fields)
+        .into_iter()
+        .map(|(key, reversed)| {
+            let modifiers = &key.modifiers;
+            let unordered = [
+                (modifiers.with.as_ref().map(Spanned::span), "with"),
+                (
+                    modifiers.collate_with.as_ref().map(Spanned::span),
+                    "collate_with",
+                ),
+                (modifiers.total, "total"),
+                (modifiers.epsilon.as_ref().map(Spanned::span), "epsilon"),
+                (
+                    modifiers.assume_total.as_ref().map(Spanned::span),
+                    "assume_total",
+                ),
+                (
+                    modifiers.comparator.as_ref().map(Spanned::span),
+                    "use_comparator",
+                ),
+                (modifiers.case_insensitive, "case_insensitive"),
+                (modifiers.both_some_only, "both_some_only"),
+                (modifiers.on_err.as_ref().map(Spanned::span), "on_err"),
+                (modifiers.lex.as_ref().map(Spanned::span), "lex"),
+            ]
+            .into_iter()
+            .find_map(|(span, name)| span.map(|span| (span, name)));
+            if let Some((span, name)) = unordered {
+                return Err(Error::new(
+                    span,
+                    format!("CmpBy: `{flag_name}` can't include an item compared with `{name}`, its value isn't ordered by `Ord`"),
+                ));
+            }
+            // Temporaries are returned by value, fields by reference.
+            let owned = matches!(key.expr, Expr::Call(_) | Expr::MethodCall(_))
+                || modifiers.key.is_some()
+                || modifiers.map.is_some()
+                || modifiers.by_len.is_some()
+                || modifiers.by_display.is_some()
+                || modifiers.cast.is_some()
+                || modifiers.abs.is_some()
+                || modifiers.ordered_float.is_some()
+                || modifiers.as_sorted.is_some()
+                || modifiers.as_pairs.is_some()
+                || modifiers.cache.is_some();
+            let element = if owned {
+                key.projected(this)
+            } else {
+                key.reference(this)
+            };
+            let reverse = |element: TokenStream| {
+                if reversed {
+                    quote!(::core::cmp::Reverse(#element))
+                } else {
+                    element
+                }
+            };
+            // Only the value is reversed, `None` stays last.
+            Ok(if modifiers.none_last.is_none() {
+                reverse(element)
+            } else if matches!(key.expr, Expr::Call(_) | Expr::MethodCall(_)) {
+                // The call is only evaluated once.
+                let reversed = reverse(quote!(key));
+                quote!({
+                    let key = #element;
+                    (key.is_none(), #reversed)
+                })
+            } else {
+                let value = key.value(this);
+                let reversed = reverse(element);
+                quote!((#value.is_none(), #reversed))
+            })
+        })
+        .fold(Ok(vec![]), fold_token_errors)?;
+    Ok(quote!((#(#elements,)*)))
+}
+
+/// Generate the `PartialOrd` impls comparing `Self` to other types.
+pub(crate) fn gen_other_partial_ord(
+    struct_name: &Ident,
+    generics: &Generics,
+    others: &[OtherComparison],
+    inline: &TokenStream,
+) -> TokenStream {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let impls = others.iter().map(|other| {
+        let OtherComparison { ty, key, .. } = other;
+        let value = other.value();
+        let mut ord_expr: Expr = parse_quote_spanned! { key.expr.span() =>
+            #value.partial_cmp(other)
+        };
+        if key.modifiers.reverse.is_some() {
+            ord_expr = Comparison::Partial.reverse(ord_expr);
+        }
+        quote_spanned! {ty.span() =>
+            #[automatically_derived]
+            impl #impl_generics ::core::cmp::PartialOrd<#ty> for #struct_name #ty_generics #where_clause {
+                #inline
+                fn partial_cmp(&self, other: &#ty) -> ::core::option::Option<::core::cmp::Ordering> {
+                    #ord_expr
+                }
+            }
+        }
+    });
+    quote!(#(#impls)*)
+}
+
+/// Whether the type of the field must implement the compared trait.
+///
+/// Fields compared with a custom function, with `total_cmp` or through a cached key don't need to.
+pub(crate) fn needs_bound(key: &Key) -> bool {
+    key.modifiers.cache.is_none()
+        && key.modifiers.with.is_none()
+        && key.modifiers.collate_with.is_none()
+        && key.modifiers.key.is_none()
+        && key.modifiers.map.is_none()
+        && key.modifiers.total.is_none()
+        && key.modifiers.epsilon.is_none()
+        && key.modifiers.assume_total.is_none()
+        && key.modifiers.case_insensitive.is_none()
+        && key.modifiers.by_len.is_none()
+        && key.modifiers.by_display.is_none()
+        && key.modifiers.cast.is_none()
+        && key.modifiers.abs.is_none()
+        && key.modifiers.ordered_float.is_none()
+        && key.modifiers.index.is_none()
+        && key.modifiers.as_sorted.is_none()
+        && key.modifiers.as_pairs.is_none()
+        && key.modifiers.on_err.is_none()
+}
+
+/// The kind of ordering expressions are generated for.
+#[derive(Clone, Copy)]
+pub(crate) enum Comparison {
+    /// `Ord::cmp`, expressions evaluate to an `Ordering`.
+    Total,
+    /// `PartialOrd::partial_cmp`, expressions evaluate to an `Option<Ordering>`.
+    Partial,
+}
+
+/// Number of compared items above which they are compared in a flat sequence of matches.
+const FLAT_CHAIN_LEN: usize = 12;
+
+impl Comparison {
+    /// Evaluate `next` only if `ord_expr` is equal.
+    fn then(self, ord_expr: Expr, next: Expr) -> Expr {
+        match self {
+            Comparison::Total => parse_quote_spanned! {next.span() =>
+                #ord_expr.then_with(|| #next)
+            },
+            Comparison::Partial => parse_quote_spanned! {next.span() =>
+                match #ord_expr {
+                    ::core::option::Option::Some(::core::cmp::Ordering::Equal) => #next,
+                    ord => ord,
+                }
+            },
+        }
+    }
+
+    /// Evaluate the expressions in order until one isn't equal.
+    ///
+    /// Past `FLAT_CHAIN_LEN` expressions, they are compared in a block labeled `label` breaking out on
+    /// the first unequal one rather than in nested calls, whose depth slows down compilation.
+    fn chain(
+        self,
+        ord_exprs: impl DoubleEndedIterator<Item = Expr>,
+        label: Lifetime,
+    ) -> Option<Expr> {
+        let mut ord_exprs: Vec<Expr> = ord_exprs.collect();
+        if ord_exprs.len() > FLAT_CHAIN_LEN {
+            let last = ord_exprs.pop()?;
+            let equal = self.wrap(parse_quote!(::core::cmp::Ordering::Equal));
+            return Some(parse_quote_spanned! {last.span() =>
+                #label: {
+                    #(
+                        match #ord_exprs {
+                            #equal => {}
+                            ord => break #label ord,
+                        }
+                    )*
+                    #last
+                }
+            });
+        }
+        match self {
+            Comparison::Total => ord_exprs
+                .into_iter()
+                .reduce(|ord_expr, expr| self.then(ord_expr, expr)),
+            // Nest the matches in the equal arm rather than in the scrutinee, to keep them readable.
+            Comparison::Partial => ord_exprs
+                .into_iter()
+                .rev()
+                .reduce(|expr, ord_expr| self.then(ord_expr, expr)),
+        }
+    }
+
+    /// Convert an expression evaluating to an `Ordering` to this kind of ordering.
+    fn wrap(self, ord_expr: Expr) -> Expr {
+        match self {
+            Comparison::Total => ord_expr,
+            Comparison::Partial => parse_quote_spanned! {ord_expr.span() =>
+                ::core::option::Option::Some(#ord_expr)
+            },
+        }
+    }
+
+    fn reverse(self, ord_expr: Expr) -> Expr {
+        match self {
+            Comparison::Total => parse_quote_spanned! {ord_expr.span() =>
+                (#ord_expr).reverse()
+            },
+            Comparison::Partial => parse_quote_spanned! {ord_expr.span() =>
+                (#ord_expr).map(::core::cmp::Ordering::reverse)
+            },
+        }
+    }
+}
+
+/// Generate the expression comparing `self` to `other`.
+pub(crate) fn gen_ord_expression(
+    derive: &str,
+    input_span: Span,
+    flags: &Flags,
+    sortable_expressions: &[Key],
+    sortable_fields: &ParsedFields,
+    comparison: Comparison,
+) -> Result<Expr, Error> {
+    let cmp_by_flag = flags.cmp_by_only().next();
+    if let (Some((span, name)), Comparison::Partial) = (cmp_by_flag, comparison) {
+        return Err(Error::new(
+            span,
+            format!("{derive}: `{name}` only applies to CmpBy"),
+        ));
+    }
+    let hash_flag = flags.hash_by_only().next();
+    let hash_modifier = sortable_expressions
+        .iter()
+        .chain(sortable_fields.keys())
+        .find_map(|key| key.modifiers.hash_by_only().next());
+    if let Some((span, name)) = hash_flag.or(hash_modifier) {
+        return Err(Error::new(
+            span,
+            format!("{derive}: `{name}` only applies to HashBy"),
+        ));
+    }
+    // CmpBy leaves the fields marked with `eq` out of the ordering.
+    if let Some(eq) = sortable_fields.keys().find_map(|key| key.modifiers.eq) {
+        return Err(Error::new(
+            eq,
+            format!("{derive}: `eq` only applies to CmpBy, `PartialEq` is implemented separately"),
+        ));
+    }
+    if let Some(descending) = flags.descending {
+        let reversed = sortable_expressions
+            .iter()
+            .chain(sortable_fields.keys())
+            .filter_map(|key| key.modifiers.reverse)
+            .map(|span| {
+                Error::new(
+                    span,
+                    format!("{derive}: `reverse` can't be combined with `descending`, which already reverses the whole ordering"),
+                )
+            })
+            .reduce(|mut acc, err| {
+                acc.combine(err);
+                acc
+            });
+        if let Some(mut err) = reversed {
+            err.combine(Error::new(
+                descending,
+                format!("{derive}: `descending` declared here"),
+            ));
+            return Err(err);
+        }
+    }
+
+    // `none_last` items that are reversed afterwards order `None` first, so that it ends up last.
+    let descending = flags.descending.is_some();
+    let fields_reversed = descending
+        != sortable_expressions.iter().any(|key| {
+            key.expr.to_token_stream().to_string() == "_fields" && key.modifiers.reverse.is_some()
+        });
+    let field_ord_statement = match sortable_fields {
+        ParsedFields::Struct(sortable_expr) => {
+            gen_cmp_exprs(sortable_expr, comparison, fields_reversed)
+        }
         ParsedFields::Enum(sortable_variants) => {
             // dbg!(sortable_variants.len());
             let ord_statements = sortable_variants
                 .iter()
-                .filter(|(_, sortable_expr)| !sortable_expr.is_empty())
-                .map(|(variant, sortable_expr)| {
-                    let ord_pattern =
-                        quote_spanned! {variant.span() => (this @ #variant, other @ #variant)};
-                    let ord_statement = gen_cmp_exprs(sortable_expr);
+                .filter(|variant| !variant.keys.is_empty())
+                .map(|variant| {
+                    let (this, other) = (variant.bind(&quote!(self)), variant.bind(&quote!(other)));
+                    let ord_pattern = quote_spanned! {variant.pattern.span() => (#this, #other)};
+                    let ord_statement = gen_cmp_exprs(&variant.keys, comparison, fields_reversed);
                     quote! {#ord_pattern => #ord_statement}
-                });
+                })
+                .collect::<Vec<_>>();
 
-            let idx_statements =
-                sortable_variants
-                    .iter()
-                    .enumerate()
-                    .map(|(var_idx, (variant, _))| {
-                        let idx_statement = Index::from(var_idx);
-                        quote! {#variant => #idx_statement}
-                    });
+            let idx_statements = sortable_variants
+                .iter()
+                .enumerate()
+                .map(|(var_idx, variant)| {
+                    let idx_statement = Index::from(var_idx);
+                    let pattern = &variant.pattern;
+                    quote! {#pattern => #idx_statement}
+                });
             let idx_statements = quote! {
                 #(#idx_statements,)*
             };
-            // What do we compare when we have different variants? We compare the discriminant based on the order of variant declaration.
-            // TODO: And what about variants that have no fields marked to cmp?
+            // Values of different variants are ordered by the declaration order of their variants,
+            // values of a variant without marked fields are equal.
+            let idx_ord_statement = comparison.wrap(parse_quote_spanned! { input_span =>
+                match this {
+                    #idx_statements
+                }.cmp(
+                    &match other {
+                        #idx_statements
+                    }
+                )
+            });
+            // The fallback would be unreachable for a single variant with marked fields.
+            let fallback = (ord_statements.len() < sortable_variants.len()
+                || sortable_variants.len() > 1)
+                .then(|| quote!((this, other) => #idx_ord_statement,));
             let stream = quote_spanned! { input_span =>
                 match (self, other) {
                     #(#ord_statements,)*
-                    (this, other) => {
-                        match this {
-                            #idx_statements
-                        }.cmp(
-                            &match other {
-                                #idx_statements
-                            }
-                        )
-                    }
+                    #fallback
                 }
             };
-            // println!("{stream}");
-            Some(match parse2(stream.clone()) {
-                Ok(ts) => ts,
-                Err(err) => {
-                    println!("{stream}");
-                    panic!("{err}");
-                }
-            })
+            Some(parse2(stream)?)
         }
     };
     // println!("Successfully generated field cmps");
 
     // println!("Entering gen_cmp_expr");
-    let expr_ord_statements = sortable_expressions
-        .iter()
-        .map(|expr| {
-            if expr.to_token_stream().to_string() == "_fields" {
-                parse_quote_spanned! { expr.span() =>
-                    #field_ord_statement
-                }
+    let expr_ord_statements = sortable_expressions.iter().map(|key| {
+        let expr = &key.expr;
+        if expr.to_token_stream().to_string() == "_fields" {
+            let field_ord_statement: Expr = parse_quote_spanned! { expr.span() =>
+                #field_ord_statement
+            };
+            if key.modifiers.reverse.is_some() {
+                comparison.reverse(field_ord_statement)
             } else {
-                parse_quote_spanned! { expr.span() =>
-                    self.#expr.cmp(&other.#expr)
-                }
-            }
-        })
-        .reduce(|ord_expr: Expr, expr| {
-            // println!("Combining {} with {}", quote!(#ord_expr), quote!(#expr));
-            parse_quote_spanned! {expr.span() =>
-                #ord_expr.then_with(|| #expr)
+                field_ord_statement
             }
-        });
+        } else {
+            gen_cmp_expr(key, comparison, descending)
+        }
+    });
     // println!("Successfully generated preceding expressions cmps");
 
-    let ord_expression = match (expr_ord_statements, field_ord_statement) {
-        (Some(exprs), Some(fields)) => {
-            parse_quote_spanned! {input_span =>
-                #exprs.then_with(|| #fields)
+    // The fields are compared in place of `_fields`, or after the top-level items.
+    let trailing_field_ord_statement = field_ord_statement.clone().filter(|_| {
+        !sortable_expressions
+            .iter()
+            .any(|key| key.expr.to_token_stream().to_string() == "_fields")
+    });
+    let Some(ord_expression) =
+        // The fields are chained in a nested block, which is labeled apart from this one.
+        comparison.chain(
+            expr_ord_statements.chain(trailing_field_ord_statement),
+            parse_quote!('cmp_by_items),
+        )
+    else {
+        unreachable!("Error of no fields to compare on should be handled in the parsing stage.")
+    };
+    // println!("Successfully combined preceding expressions with fields cmps");
+    if descending {
+        Ok(comparison.reverse(ord_expression))
+    } else {
+        Ok(ord_expression)
+    }
+}
+
+fn gen_cmp_exprs(sortable_expr: &[Key], comparison: Comparison, reversed: bool) -> Option<Expr> {
+    // println!("Entering gen_cmp_expr");
+    comparison.chain(
+        sortable_expr
+            .iter()
+            .map(|key| gen_cmp_expr(key, comparison, reversed)),
+        parse_quote!('cmp_by_fields),
+    )
+}
+
+/// Generate the expression comparing `key` of `self` to `other`, `reversed` when the comparison is reversed
+/// afterwards, by `descending` or a reversed `_fields`.
+fn gen_cmp_expr(key: &Key, comparison: Comparison, reversed: bool) -> Expr {
+    // Calls that are checked for `None` besides being compared are only evaluated once per side.
+    if let Some((locals, hoisted)) = key.hoisted().filter(|_| key.modifiers.none_last.is_some()) {
+        let cmp_expr = gen_cmp_expr(&hoisted, comparison, reversed);
+        return parse_quote!({
+            #locals
+            #cmp_expr
+        });
+    }
+    let Key {
+        expr, modifiers, ..
+    } = key;
+    let (this, other) = if modifiers.reverse.is_some() {
+        (quote!(other), quote!(self))
+    } else {
+        (quote!(self), quote!(other))
+    };
+    let (this_ref, other_ref) = (key.reference(&this), key.reference(&other));
+    let ord_expr = match (&modifiers.with, &modifiers.key) {
+        (Some(with), _) => comparison.wrap(parse_quote_spanned! { expr.span() =>
+            #with(#this_ref, #other_ref)
+        }),
+        // Only the comparator of `self` is used, whatever the direction of the comparison.
+        (None, _) if modifiers.comparator.is_some() => {
+            let comparator = &modifiers.comparator;
+            comparison.wrap(parse_quote_spanned! { expr.span() =>
+                (self.#comparator)(#this_ref, #other_ref)
+            })
+        }
+        (None, _) if modifiers.collate_with.is_some() => {
+            let collated = key.collated(&this, &other);
+            comparison.wrap(parse_quote_spanned! { expr.span() => #collated })
+        }
+        (None, _) if modifiers.on_err.is_some() => {
+            let (this, other) = (key.projected(&this), key.projected(&other));
+            let ok_ord = match comparison {
+                Comparison::Total => quote!(this.cmp(other)),
+                Comparison::Partial => quote!(this.partial_cmp(other)),
+            };
+            let err_ord = comparison.wrap(if key.err_greater() {
+                parse_quote!(this.is_err().cmp(&other.is_err()))
+            } else {
+                parse_quote!(other.is_err().cmp(&this.is_err()))
+            });
+            // Errors are all equal, whatever their value.
+            parse_quote_spanned! { expr.span() =>
+                match (&#this, &#other) {
+                    (::core::result::Result::Ok(this), ::core::result::Result::Ok(other)) => #ok_ord,
+                    (this, other) => #err_ord,
+                }
+            }
+        }
+        // The first pair of items that differ decides, a prefix of the other side is equal to it.
+        (None, _) if modifiers.lex.is_some() => {
+            let pairs = key.lex_pairs(&this, &other);
+            match comparison {
+                Comparison::Total => parse_quote_spanned! { expr.span() =>
+                    #pairs
+                        .map(|(this, other)| this.cmp(other))
+                        .find(|ord| ord.is_ne())
+                        .unwrap_or(::core::cmp::Ordering::Equal)
+                },
+                Comparison::Partial => parse_quote_spanned! { expr.span() =>
+                    #pairs
+                        .map(|(this, other)| this.partial_cmp(other))
+                        .find(|ord| *ord != ::core::option::Option::Some(::core::cmp::Ordering::Equal))
+                        .unwrap_or(::core::option::Option::Some(::core::cmp::Ordering::Equal))
+                },
+            }
+        }
+        (None, _) if modifiers.epsilon.is_some() => {
+            let epsilon = &modifiers.epsilon;
+            let (this, other) = (key.copied(&this), key.copied(&other));
+            // Values within `epsilon` of each other are equal, the others are ordered like with `total`.
+            // The block is parenthesized to be usable as a receiver.
+            comparison.wrap(parse_quote_spanned! { expr.span() =>
+                ({
+                    let (this, other) = (#this, #other);
+                    if this - other <= #epsilon && other - this <= #epsilon {
+                        ::core::cmp::Ordering::Equal
+                    } else {
+                        this.total_cmp(&other)
+                    }
+                })
+            })
+        }
+        (None, _) if modifiers.assume_total.is_some() => {
+            let assumed_total = key.assumed_total(&this, &other);
+            comparison.wrap(parse_quote_spanned! { expr.span() => #assumed_total })
+        }
+        (None, _) => {
+            let (this, other) = (key.projected(&this), key.projected(&other));
+            match (modifiers.total, modifiers.case_insensitive, comparison) {
+                (Some(_), _, _) => comparison.wrap(parse_quote_spanned! { expr.span() =>
+                    #this.total_cmp(&#other)
+                }),
+                // Compare the bytes folded to lowercase one by one, without allocating.
+                (None, Some(_), _) => comparison.wrap(parse_quote_spanned! { expr.span() =>
+                    #this.bytes().map(|byte| byte.to_ascii_lowercase()).cmp(
+                        #other.bytes().map(|byte| byte.to_ascii_lowercase())
+                    )
+                }),
+                (None, None, Comparison::Total) => parse_quote_spanned! { expr.span() =>
+                    #this.cmp(&#other)
+                },
+                (None, None, Comparison::Partial) => parse_quote_spanned! { expr.span() =>
+                    #this.partial_cmp(&#other)
+                },
             }
         }
-        (None, Some(ts)) | (Some(ts), None) => ts,
-        (None, None) => {
-            unreachable!("Error of no fields to compare on should be handled in the parsing stage.")
+    };
+    // `None` compares equal to anything, so only values that are both `Some` are ordered.
+    let ord_expr = if modifiers.both_some_only.is_some() {
+        let (this, other) = (key.value(&quote!(self)), key.value(&quote!(other)));
+        let equal = comparison.wrap(parse_quote!(::core::cmp::Ordering::Equal));
+        parse_quote_spanned! { expr.span() =>
+            if #this.is_some() && #other.is_some() {
+                #ord_expr
+            } else {
+                #equal
+            }
         }
+    } else {
+        ord_expr
     };
-    // println!("Successfully combined preceding expressions with fields cmps");
+    // `None` stays last whatever the direction of the comparison.
+    if modifiers.none_last.is_some() {
+        let (this, other) = if reversed {
+            (key.value(&quote!(other)), key.value(&quote!(self)))
+        } else {
+            (key.value(&quote!(self)), key.value(&quote!(other)))
+        };
+        comparison.then(
+            comparison.wrap(parse_quote_spanned! { expr.span() =>
+                #this.is_none().cmp(&#other.is_none())
+            }),
+            ord_expr,
+        )
+    } else {
+        ord_expr
+    }
+}
 
-    let where_clause = &generics.where_clause;
-    let generics_params = &generics_params;
+#[cfg(test)]
+pub(crate) mod test {
+    use quote::quote;
 
-    quote_spanned! {input_span =>
-        impl #generics ::core::cmp::Eq for #struct_name <#(#generics_params),*> #where_clause {}
+    #[macro_export]
+    macro_rules! assert_rust_eq {
+        ($actual: expr, $expected: expr$(, $msg: literal)?) => {
+            use rust_format::Formatter;
+            let rust_fmt = rust_format::RustFmt::default();
+            assert_eq!(
+                rust_fmt
+                    .format_str($actual)
+                    .expect("Actual value could not be formatted"),
+                rust_fmt
+                    .format_str($expected)
+                    .expect("Expected value could not be formatted"),
+                $($msg)?
+            );
+        };
+    }
 
-        impl #generics ::core::cmp::PartialEq<Self> for #struct_name <#(#generics_params),*> #where_clause {
-            #[inline]
-            fn eq(&self, other: &Self) -> bool {
-                self.cmp(other).is_eq()
+    #[test]
+    fn test_automatically_derived() {
+        let input: syn::DeriveInput = syn::parse_quote! {
+            #[cmp_by(gen_key, gen_min_heap_key)]
+            #[cmp_by(other = "u32", key = "a")]
+            #[eq_by(other = "u32", key = "a")]
+            struct Toto {
+                #[cmp_by]
+                #[eq_by]
+                #[hash_by]
+                a: u32,
             }
-        }
+        };
+        let mut partial_ord_input = input.clone();
+        partial_ord_input.attrs.remove(0);
 
-        impl #generics ::core::cmp::PartialOrd<Self> for #struct_name <#(#generics_params),*> #where_clause {
-            #[inline]
-            fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
-                ::core::option::Option::Some(self.cmp(other))
-            }
+        let outputs = [
+            crate::cmp_by::impl_cmp_by_derive(input.clone()),
+            crate::partial_ord_by::impl_partial_ord_by_derive(partial_ord_input),
+            crate::eq_by::impl_eq_by_derive(input.clone()),
+            crate::hash_by::impl_hash_by_derive(input),
+        ];
+        for output in outputs {
+            let file: syn::File = syn::parse2(output).unwrap();
+            let impls = file.items.iter().filter_map(|item| match item {
+                syn::Item::Impl(item) => Some(item),
+                _ => None,
+            });
+            assert_eq!(
+                impls
+                    .clone()
+                    .filter(|item| {
+                        item.attrs
+                            .iter()
+                            .any(|attr| attr.path().is_ident("automatically_derived"))
+                    })
+                    .count(),
+                impls.count(),
+            );
         }
+    }
 
-        impl #generics ::core::cmp::Ord for #struct_name <#(#generics_params),*> #where_clause {
-            #[inline]
-            fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
-                #ord_expression
+    #[test]
+    fn test_struct() {
+        let input = syn::parse_quote! {
+            #[cmp_by(embed.otherfield)]
+            struct Toto {
+                #[cmp_by]
+                a: u16,
+                #[cmp_by]
+                c: u32,
+                b: f32,
+                embed: EmbedStruct
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Toto {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Self> for Toto {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.embed.otherfield == other.embed.otherfield && self.a == other.a && self.c == other.c
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<Self> for Toto {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Ord for Toto {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        self.embed
+            .otherfield
+            .cmp(&other.embed.otherfield)
+            .then_with(|| self.a.cmp(&other.a).then_with(|| self.c.cmp(&other.c)))
+    }
+}
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::cmp::Ord>() {}
+    #[allow(dead_code)]
+    fn assert_fields(_: &Toto) {
+        assert_bound::<u16>();
+        assert_bound::<u32>();
+    }
+};
+"#
+        );
+    }
+
+    #[test]
+    fn test_reverse() {
+        let input = syn::parse_quote! {
+            struct Player {
+                #[cmp_by(reverse)]
+                score: u32,
+                #[cmp_by]
+                name: String,
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Player {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Self> for Player {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.score == other.score && self.name == other.name
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<Self> for Player {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Ord for Player {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        other
+            .score
+            .cmp(&self.score)
+            .then_with(|| self.name.cmp(&other.name))
+    }
+}
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::cmp::Ord>() {}
+    #[allow(dead_code)]
+    fn assert_fields(_: &Player) {
+        assert_bound::<u32>();
+        assert_bound::<String>();
+    }
+};
+"#
+        );
+    }
+
+    #[test]
+    fn test_reverse_expression() {
+        let input = syn::parse_quote! {
+            #[cmp_by(Reverse(priority()), _fields, Reverse(meta.rank))]
+            struct Task {
+                #[cmp_by]
+                name: String,
+                meta: Meta,
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Task {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Self> for Task {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.priority() == other.priority()
+            && self.meta.rank == other.meta.rank
+            && self.name == other.name
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<Self> for Task {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Ord for Task {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        other
+            .priority()
+            .cmp(&self.priority())
+            .then_with(|| self.name.cmp(&other.name))
+            .then_with(|| other.meta.rank.cmp(&self.meta.rank))
+    }
+}
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::cmp::Ord>() {}
+    #[allow(dead_code)]
+    fn assert_fields(_: &Task) {
+        assert_bound::<String>();
+    }
+};
+"#
+        );
+    }
+
+    #[test]
+    fn test_descending_none_last() {
+        let input = syn::parse_quote! {
+            #[cmp_by(descending)]
+            struct Job {
+                #[cmp_by(none_last)]
+                deadline: Option<u32>,
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        // `None` is ordered first before the comparison is reversed, so that it ends up last.
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Job {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Self> for Job {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.deadline == other.deadline
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<Self> for Job {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Ord for Job {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        (other
+            .deadline
+            .is_none()
+            .cmp(&self.deadline.is_none())
+            .then_with(|| self.deadline.cmp(&other.deadline)))
+        .reverse()
+    }
+}
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::cmp::Ord>() {}
+    #[allow(dead_code)]
+    fn assert_fields(_: &Job) {
+        assert_bound::<Option<u32>>();
+    }
+};
+"#
+        );
+    }
+
+    #[test]
+    fn test_descending() {
+        let input = syn::parse_quote! {
+            #[cmp_by(descending, size())]
+            struct Job {
+                #[cmp_by]
+                priority: u8,
+                data: Vec<u8>,
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Job {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Self> for Job {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.size() == other.size() && self.priority == other.priority
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<Self> for Job {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Ord for Job {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        (self
+            .size()
+            .cmp(&other.size())
+            .then_with(|| self.priority.cmp(&other.priority)))
+        .reverse()
+    }
+}
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::cmp::Ord>() {}
+    #[allow(dead_code)]
+    fn assert_fields(_: &Job) {
+        assert_bound::<u8>();
+    }
+};
+"#
+        );
+    }
+
+    #[test]
+    fn test_with() {
+        let input = syn::parse_quote! {
+            struct Samples {
+                #[cmp_by(with = "cmp_len")]
+                values: Vec<f32>,
+                #[cmp_by(with = "utils::cmp_len", reverse)]
+                weights: Vec<f32>,
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Samples {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Self> for Samples {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        cmp_len(&self.values, &other.values).is_eq()
+            && utils::cmp_len(&self.weights, &other.weights).is_eq()
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<Self> for Samples {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Ord for Samples {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        cmp_len(&self.values, &other.values)
+            .then_with(|| utils::cmp_len(&other.weights, &self.weights))
+    }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_key() {
+        let input = syn::parse_quote! {
+            struct Label {
+                #[cmp_by(key = "str::len")]
+                text: String,
+                #[cmp_by(key = "parse_version", reverse)]
+                version: String,
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Label {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Self> for Label {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        str::len(&self.text) == str::len(&other.text)
+            && parse_version(&self.version) == parse_version(&other.version)
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<Self> for Label {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Ord for Label {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        str::len(&self.text)
+            .cmp(&str::len(&other.text))
+            .then_with(|| parse_version(&other.version).cmp(&parse_version(&self.version)))
+    }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_both_some_only() {
+        let input = syn::parse_quote! {
+            struct Revision {
+                #[cmp_by(both_some_only, reverse)]
+                parent: Option<u32>,
+                #[cmp_by]
+                id: u32,
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Revision {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Self> for Revision {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        (self.parent.is_none() || other.parent.is_none() || self.parent == other.parent)
+            && self.id == other.id
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<Self> for Revision {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Ord for Revision {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        if self.parent.is_some() && other.parent.is_some() {
+            other.parent.cmp(&self.parent)
+        } else {
+            ::core::cmp::Ordering::Equal
+        }
+        .then_with(|| self.id.cmp(&other.id))
+    }
+}
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::cmp::Ord>() {}
+    #[allow(dead_code)]
+    fn assert_fields(_: &Revision) {
+        assert_bound::<Option<u32>>();
+        assert_bound::<u32>();
+    }
+};
+"#
+        );
+    }
+
+    #[test]
+    fn test_ord_only() {
+        let input = syn::parse_quote! {
+            #[cmp_by(ord_only)]
+            struct Version {
+                #[cmp_by]
+                major: u16,
+                #[cmp_by]
+                minor: u16,
+                label: String,
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::PartialOrd<Self> for Version {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Ord for Version {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        self.major
+            .cmp(&other.major)
+            .then_with(|| self.minor.cmp(&other.minor))
+    }
+}
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::cmp::Ord>() {}
+    #[allow(dead_code)]
+    fn assert_fields(_: &Version) {
+        assert_bound::<u16>();
+    }
+};
+"#
+        );
+    }
+
+    #[test]
+    fn test_eq_only_flag() {
+        let input = syn::parse_quote! {
+            #[cmp_by(eq_only)]
+            struct Version<T> {
+                #[cmp_by]
+                major: u16,
+                #[cmp_by]
+                label: T,
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl<T> ::core::cmp::Eq for Version<T> where T: ::core::cmp::Eq {}
+#[automatically_derived]
+impl<T> ::core::cmp::PartialEq<Self> for Version<T>
+where
+    T: ::core::cmp::Eq,
+{
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.major == other.major && self.label == other.label
+    }
+}
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::cmp::PartialEq>() {}
+    #[allow(dead_code)]
+    fn assert_fields<T>(_: &Version<T>)
+    where
+        T: ::core::cmp::Eq,
+    {
+        assert_bound::<u16>();
+    }
+};
+"#
+        );
+    }
+
+    #[test]
+    fn test_eq_only() {
+        let input = syn::parse_quote! {
+            struct Blob<T> {
+                #[cmp_by]
+                name: String,
+                #[cmp_by(eq)]
+                digest: [u8; 32],
+                #[cmp_by(eq)]
+                meta: T,
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl<T> ::core::cmp::Eq for Blob<T> where T: ::core::cmp::Eq {}
+#[automatically_derived]
+impl<T> ::core::cmp::PartialEq<Self> for Blob<T>
+where
+    T: ::core::cmp::Eq,
+{
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.digest == other.digest && self.meta == other.meta
+    }
+}
+#[automatically_derived]
+impl<T> ::core::cmp::PartialOrd<Self> for Blob<T>
+where
+    T: ::core::cmp::Eq,
+{
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl<T> ::core::cmp::Ord for Blob<T>
+where
+    T: ::core::cmp::Eq,
+{
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        self.name.cmp(&other.name)
+    }
+}
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::cmp::Ord>() {}
+    #[allow(dead_code)]
+    fn assert_fields<T>(_: &Blob<T>)
+    where
+        T: ::core::cmp::Eq,
+    {
+        assert_bound::<String>();
+    }
+};
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::cmp::PartialEq>() {}
+    #[allow(dead_code)]
+    fn assert_fields<T>(_: &Blob<T>)
+    where
+        T: ::core::cmp::Eq,
+    {
+        assert_bound::<[u8; 32]>();
+    }
+};
+"#
+        );
+    }
+
+    #[test]
+    fn test_none_last() {
+        let input = syn::parse_quote! {
+            #[cmp_by(NoneLast(reviewer()))]
+            struct Task {
+                #[cmp_by(none_last, reverse)]
+                deadline: Option<u32>,
+                reviewers: Vec<String>,
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Task {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Self> for Task {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.reviewer() == other.reviewer() && self.deadline == other.deadline
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<Self> for Task {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Ord for Task {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        {
+            let self_key = &self.reviewer();
+            let other_key = &other.reviewer();
+            self_key
+                .is_none()
+                .cmp(&other_key.is_none())
+                .then_with(|| self_key.cmp(&other_key))
+        }
+        .then_with(|| {
+            self.deadline
+                .is_none()
+                .cmp(&other.deadline.is_none())
+                .then_with(|| other.deadline.cmp(&self.deadline))
+        })
+    }
+}
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::cmp::Ord>() {}
+    #[allow(dead_code)]
+    fn assert_fields(_: &Task) {
+        assert_bound::<Option<u32>>();
+    }
+};
+"#
+        );
+    }
+
+    #[test]
+    fn test_floats_total() {
+        let input = syn::parse_quote! {
+            #[cmp_by(floats = "total")]
+            struct Sample {
+                #[cmp_by]
+                value: f32,
+                #[cmp_by(reverse)]
+                weight: f64,
+                #[cmp_by]
+                count: u32,
+                #[cmp_by(with = "compare")]
+                raw: f64,
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Sample {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Self> for Sample {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.value.total_cmp(&other.value).is_eq()
+            && self.weight.total_cmp(&other.weight).is_eq()
+            && self.count == other.count
+            && compare(&self.raw, &other.raw).is_eq()
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<Self> for Sample {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Ord for Sample {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        self.value
+            .total_cmp(&other.value)
+            .then_with(|| other.weight.total_cmp(&self.weight))
+            .then_with(|| self.count.cmp(&other.count))
+            .then_with(|| compare(&self.raw, &other.raw))
+    }
+}
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::cmp::Ord>() {}
+    #[allow(dead_code)]
+    fn assert_fields(_: &Sample) {
+        assert_bound::<u32>();
+    }
+};
+"#
+        );
+    }
+
+    #[test]
+    fn test_as_sorted() {
+        let input = syn::parse_quote! {
+            enum Inventory {
+                Store {
+                    #[cmp_by(as_sorted)]
+                    stock: HashMap<String, u32>,
+                },
+                Shelf(#[cmp_by(as_sorted, reverse)] HashSet<u8>),
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Inventory {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Self> for Inventory {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (
+                Self::Store {
+                    stock: self_stock, ..
+                },
+                Self::Store {
+                    stock: other_stock, ..
+                },
+            ) => {
+                ({
+                    let mut items: ::std::vec::Vec<_> =
+                        ::core::iter::IntoIterator::into_iter(self_stock).collect();
+                    items.sort_unstable();
+                    items
+                }) == ({
+                    let mut items: ::std::vec::Vec<_> =
+                        ::core::iter::IntoIterator::into_iter(other_stock).collect();
+                    items.sort_unstable();
+                    items
+                })
+            }
+            (Self::Shelf { 0: self_0, .. }, Self::Shelf { 0: other_0, .. }) => {
+                ({
+                    let mut items: ::std::vec::Vec<_> =
+                        ::core::iter::IntoIterator::into_iter(self_0).collect();
+                    items.sort_unstable();
+                    items
+                }) == ({
+                    let mut items: ::std::vec::Vec<_> =
+                        ::core::iter::IntoIterator::into_iter(other_0).collect();
+                    items.sort_unstable();
+                    items
+                })
+            }
+            (this, other) => ::core::mem::discriminant(this) == ::core::mem::discriminant(other),
+        }
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<Self> for Inventory {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Ord for Inventory {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        match (self, other) {
+            (
+                Self::Store {
+                    stock: self_stock, ..
+                },
+                Self::Store {
+                    stock: other_stock, ..
+                },
+            ) => ({
+                let mut items: ::std::vec::Vec<_> =
+                    ::core::iter::IntoIterator::into_iter(self_stock).collect();
+                items.sort_unstable();
+                items
+            })
+            .cmp(
+                &({
+                    let mut items: ::std::vec::Vec<_> =
+                        ::core::iter::IntoIterator::into_iter(other_stock).collect();
+                    items.sort_unstable();
+                    items
+                }),
+            ),
+            (Self::Shelf { 0: self_0, .. }, Self::Shelf { 0: other_0, .. }) => ({
+                let mut items: ::std::vec::Vec<_> =
+                    ::core::iter::IntoIterator::into_iter(other_0).collect();
+                items.sort_unstable();
+                items
+            })
+            .cmp(
+                &({
+                    let mut items: ::std::vec::Vec<_> =
+                        ::core::iter::IntoIterator::into_iter(self_0).collect();
+                    items.sort_unstable();
+                    items
+                }),
+            ),
+            (this, other) => match this {
+                Self::Store { .. } => 0,
+                Self::Shelf(..) => 1,
+            }
+            .cmp(&match other {
+                Self::Store { .. } => 0,
+                Self::Shelf(..) => 1,
+            }),
+        }
+    }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_on_err() {
+        let input = syn::parse_quote! {
+            #[cmp_by(ErrLess(checksum()))]
+            struct Download {
+                #[cmp_by(on_err = "greater", reverse)]
+                size: Result<u64, Error>,
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Download {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Self> for Download {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        (match (&self.checksum(), &other.checksum()) {
+            (::core::result::Result::Ok(this), ::core::result::Result::Ok(other)) => this == other,
+            (this, other) => this.is_err() == other.is_err(),
+        }) && (match (&self.size, &other.size) {
+            (::core::result::Result::Ok(this), ::core::result::Result::Ok(other)) => this == other,
+            (this, other) => this.is_err() == other.is_err(),
+        })
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<Self> for Download {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Ord for Download {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        match (&self.checksum(), &other.checksum()) {
+            (::core::result::Result::Ok(this), ::core::result::Result::Ok(other)) => {
+                this.cmp(other)
+            }
+            (this, other) => other.is_err().cmp(&this.is_err()),
+        }
+        .then_with(|| match (&other.size, &self.size) {
+            (::core::result::Result::Ok(this), ::core::result::Result::Ok(other)) => {
+                this.cmp(other)
+            }
+            (this, other) => this.is_err().cmp(&other.is_err()),
+        })
+    }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_gen_reverse() {
+        let input = syn::parse_quote! {
+            #[cmp_by(gen_reverse = "RevEvent")]
+            pub struct Event<T> {
+                #[cmp_by]
+                time: u64,
+                payload: T,
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl<T> ::core::cmp::Eq for Event<T> {}
+#[automatically_derived]
+impl<T> ::core::cmp::PartialEq<Self> for Event<T> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.time == other.time
+    }
+}
+#[automatically_derived]
+impl<T> ::core::cmp::PartialOrd<Self> for Event<T> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl<T> ::core::cmp::Ord for Event<T> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        self.time.cmp(&other.time)
+    }
+}
+#[doc = " `Event` ordered in reverse, e.g. to pop the least value first from a `BinaryHeap`."]
+pub struct RevEvent<T>(pub Event<T>);
+#[automatically_derived]
+impl<T> ::core::cmp::Eq for RevEvent<T> {}
+#[automatically_derived]
+impl<T> ::core::cmp::PartialEq<Self> for RevEvent<T> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+#[automatically_derived]
+impl<T> ::core::cmp::PartialOrd<Self> for RevEvent<T> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl<T> ::core::cmp::Ord for RevEvent<T> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        other.0.cmp(&self.0)
+    }
+}
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::cmp::Ord>() {}
+    #[allow(dead_code)]
+    fn assert_fields<T>(_: &Event<T>) {
+        assert_bound::<u64>();
+    }
+};
+"#
+        );
+    }
+
+    #[test]
+    fn test_many_fields() {
+        let input = syn::parse_quote! {
+            #[cmp_by(all, descending)]
+            struct Wide(u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8);
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Wide {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Self> for Wide {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+            && self.1 == other.1
+            && self.2 == other.2
+            && self.3 == other.3
+            && self.4 == other.4
+            && self.5 == other.5
+            && self.6 == other.6
+            && self.7 == other.7
+            && self.8 == other.8
+            && self.9 == other.9
+            && self.10 == other.10
+            && self.11 == other.11
+            && self.12 == other.12
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<Self> for Wide {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Ord for Wide {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        ('cmp_by_fields: {
+            match self.0.cmp(&other.0) {
+                ::core::cmp::Ordering::Equal => {}
+                ord => break 'cmp_by_fields ord,
+            }
+            match self.1.cmp(&other.1) {
+                ::core::cmp::Ordering::Equal => {}
+                ord => break 'cmp_by_fields ord,
+            }
+            match self.2.cmp(&other.2) {
+                ::core::cmp::Ordering::Equal => {}
+                ord => break 'cmp_by_fields ord,
+            }
+            match self.3.cmp(&other.3) {
+                ::core::cmp::Ordering::Equal => {}
+                ord => break 'cmp_by_fields ord,
+            }
+            match self.4.cmp(&other.4) {
+                ::core::cmp::Ordering::Equal => {}
+                ord => break 'cmp_by_fields ord,
+            }
+            match self.5.cmp(&other.5) {
+                ::core::cmp::Ordering::Equal => {}
+                ord => break 'cmp_by_fields ord,
+            }
+            match self.6.cmp(&other.6) {
+                ::core::cmp::Ordering::Equal => {}
+                ord => break 'cmp_by_fields ord,
+            }
+            match self.7.cmp(&other.7) {
+                ::core::cmp::Ordering::Equal => {}
+                ord => break 'cmp_by_fields ord,
+            }
+            match self.8.cmp(&other.8) {
+                ::core::cmp::Ordering::Equal => {}
+                ord => break 'cmp_by_fields ord,
+            }
+            match self.9.cmp(&other.9) {
+                ::core::cmp::Ordering::Equal => {}
+                ord => break 'cmp_by_fields ord,
+            }
+            match self.10.cmp(&other.10) {
+                ::core::cmp::Ordering::Equal => {}
+                ord => break 'cmp_by_fields ord,
+            }
+            match self.11.cmp(&other.11) {
+                ::core::cmp::Ordering::Equal => {}
+                ord => break 'cmp_by_fields ord,
+            }
+            self.12.cmp(&other.12)
+        })
+        .reverse()
+    }
+}
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::cmp::Ord>() {}
+    #[allow(dead_code)]
+    fn assert_fields(_: &Wide) {
+        assert_bound::<u8>();
+    }
+};
+"#
+        );
+    }
+
+    #[test]
+    fn test_epsilon() {
+        let input = syn::parse_quote! {
+            struct Particle {
+                #[cmp_by(epsilon = 0.001)]
+                x: f64,
+                #[cmp_by(epsilon = 0.5, reverse)]
+                mass: f32,
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Particle {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Self> for Particle {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        ({
+            let (this, other) = (self.x, other.x);
+            (this - other <= 0.001 && other - this <= 0.001) || this.total_cmp(&other).is_eq()
+        }) && ({
+            let (this, other) = (self.mass, other.mass);
+            (this - other <= 0.5 && other - this <= 0.5) || this.total_cmp(&other).is_eq()
+        })
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<Self> for Particle {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Ord for Particle {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        ({
+            let (this, other) = (self.x, other.x);
+            if this - other <= 0.001 && other - this <= 0.001 {
+                ::core::cmp::Ordering::Equal
+            } else {
+                this.total_cmp(&other)
+            }
+        })
+        .then_with(|| {
+            ({
+                let (this, other) = (other.mass, self.mass);
+                if this - other <= 0.5 && other - this <= 0.5 {
+                    ::core::cmp::Ordering::Equal
+                } else {
+                    this.total_cmp(&other)
+                }
+            })
+        })
+    }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_use_comparator() {
+        let input = syn::parse_quote! {
+            #[cmp_by(use_comparator = "cmp_fn", on = "data")]
+            #[cmp_by(use_comparator = "cmp_fn", on = "Reverse(backup)")]
+            struct Sorter {
+                cmp_fn: Box<dyn Fn(&u32, &u32) -> Ordering>,
+                data: u32,
+                backup: u32,
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Sorter {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Self> for Sorter {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        (self.cmp_fn)(&self.data, &other.data).is_eq()
+            && (self.cmp_fn)(&self.backup, &other.backup).is_eq()
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<Self> for Sorter {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Ord for Sorter {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        (self.cmp_fn)(&self.data, &other.data)
+            .then_with(|| (self.cmp_fn)(&other.backup, &self.backup))
+    }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_total() {
+        let input = syn::parse_quote! {
+            struct Measure {
+                #[cmp_by(total)]
+                value: f64,
+                #[cmp_by(total, reverse)]
+                weight: f32,
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Measure {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Self> for Measure {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.value.total_cmp(&other.value).is_eq() && self.weight.total_cmp(&other.weight).is_eq()
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<Self> for Measure {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Ord for Measure {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        self.value
+            .total_cmp(&other.value)
+            .then_with(|| other.weight.total_cmp(&self.weight))
+    }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_assume_total_equal() {
+        let input = syn::parse_quote! {
+            struct Sample {
+                #[cmp_by(assume_total = "equal")]
+                value: f64,
+                #[cmp_by(assume_total = "equal", reverse)]
+                weight: f32,
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Sample {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Self> for Sample {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        ::core::cmp::PartialOrd::partial_cmp(&self.value, &other.value)
+            .unwrap_or(::core::cmp::Ordering::Equal)
+            .is_eq()
+            && ::core::cmp::PartialOrd::partial_cmp(&self.weight, &other.weight)
+                .unwrap_or(::core::cmp::Ordering::Equal)
+                .is_eq()
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<Self> for Sample {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Ord for Sample {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        ::core::cmp::PartialOrd::partial_cmp(&self.value, &other.value)
+            .unwrap_or(::core::cmp::Ordering::Equal)
+            .then_with(|| {
+                ::core::cmp::PartialOrd::partial_cmp(&other.weight, &self.weight)
+                    .unwrap_or(::core::cmp::Ordering::Equal)
+            })
+    }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_assume_total_panic() {
+        let input = syn::parse_quote! {
+            struct Sample {
+                #[cmp_by(assume_total = "panic")]
+                value: f64,
+                #[cmp_by(assume_total = "panic", reverse)]
+                weight: f32,
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Sample {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Self> for Sample {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        ::core::cmp::PartialOrd::partial_cmp(&self.value, &other.value)
+            .expect("`value` isn't comparable, but is compared with `assume_total`")
+            .is_eq()
+            && ::core::cmp::PartialOrd::partial_cmp(&self.weight, &other.weight)
+                .expect("`weight` isn't comparable, but is compared with `assume_total`")
+                .is_eq()
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<Self> for Sample {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Ord for Sample {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        ::core::cmp::PartialOrd::partial_cmp(&self.value, &other.value)
+            .expect("`value` isn't comparable, but is compared with `assume_total`")
+            .then_with(|| {
+                ::core::cmp::PartialOrd::partial_cmp(&other.weight, &self.weight)
+                    .expect("`weight` isn't comparable, but is compared with `assume_total`")
+            })
+    }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_assume_total_debug_checked() {
+        let input = syn::parse_quote! {
+            struct Sample {
+                #[cmp_by(assume_total = "debug_checked")]
+                value: f64,
+                #[cmp_by(assume_total = "debug_checked", reverse)]
+                weight: f32,
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Sample {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Self> for Sample {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        ({
+            let ord = ::core::cmp::PartialOrd::partial_cmp(&self.value, &other.value);
+            ::core::debug_assert!(
+                ord.is_some(),
+                "{}",
+                "`value` isn't comparable, but is compared with `assume_total`"
+            );
+            ord.unwrap_or(::core::cmp::Ordering::Equal)
+        })
+        .is_eq()
+            && ({
+                let ord = ::core::cmp::PartialOrd::partial_cmp(&self.weight, &other.weight);
+                ::core::debug_assert!(
+                    ord.is_some(),
+                    "{}",
+                    "`weight` isn't comparable, but is compared with `assume_total`"
+                );
+                ord.unwrap_or(::core::cmp::Ordering::Equal)
+            })
+            .is_eq()
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<Self> for Sample {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Ord for Sample {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        ({
+            let ord = ::core::cmp::PartialOrd::partial_cmp(&self.value, &other.value);
+            ::core::debug_assert!(
+                ord.is_some(),
+                "{}",
+                "`value` isn't comparable, but is compared with `assume_total`"
+            );
+            ord.unwrap_or(::core::cmp::Ordering::Equal)
+        })
+        .then_with(|| {
+            ({
+                let ord = ::core::cmp::PartialOrd::partial_cmp(&other.weight, &self.weight);
+                ::core::debug_assert!(
+                    ord.is_some(),
+                    "{}",
+                    "`weight` isn't comparable, but is compared with `assume_total`"
+                );
+                ord.unwrap_or(::core::cmp::Ordering::Equal)
+            })
+        })
+    }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_deref() {
+        let input = syn::parse_quote! {
+            struct Entry<T> {
+                #[cmp_by(deref)]
+                name: Box<str>,
+                #[cmp_by(deref, reverse)]
+                inner: Arc<T>,
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl<T> ::core::cmp::Eq for Entry<T> where <Arc<T> as ::core::ops::Deref>::Target: ::core::cmp::Ord {}
+#[automatically_derived]
+impl<T> ::core::cmp::PartialEq<Self> for Entry<T>
+where
+    <Arc<T> as ::core::ops::Deref>::Target: ::core::cmp::Ord,
+{
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        (*self.name) == (*other.name) && (*self.inner) == (*other.inner)
+    }
+}
+#[automatically_derived]
+impl<T> ::core::cmp::PartialOrd<Self> for Entry<T>
+where
+    <Arc<T> as ::core::ops::Deref>::Target: ::core::cmp::Ord,
+{
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl<T> ::core::cmp::Ord for Entry<T>
+where
+    <Arc<T> as ::core::ops::Deref>::Target: ::core::cmp::Ord,
+{
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        (*self.name)
+            .cmp(&(*other.name))
+            .then_with(|| (*other.inner).cmp(&(*self.inner)))
+    }
+}
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::cmp::Ord>() {}
+    #[allow(dead_code)]
+    fn assert_fields<T>(_: &Entry<T>)
+    where
+        <Arc<T> as ::core::ops::Deref>::Target: ::core::cmp::Ord,
+    {
+        assert_bound::<<Box<str> as ::core::ops::Deref>::Target>();
+    }
+};
+"#
+        );
+    }
+
+    #[test]
+    fn test_by_len() {
+        let input = syn::parse_quote! {
+            struct Playlist<T> {
+                #[cmp_by(by_len)]
+                tracks: Vec<T>,
+                #[cmp_by]
+                name: String,
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl<T> ::core::cmp::Eq for Playlist<T> {}
+#[automatically_derived]
+impl<T> ::core::cmp::PartialEq<Self> for Playlist<T> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.tracks.len() == other.tracks.len() && self.name == other.name
+    }
+}
+#[automatically_derived]
+impl<T> ::core::cmp::PartialOrd<Self> for Playlist<T> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl<T> ::core::cmp::Ord for Playlist<T> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        self.tracks
+            .len()
+            .cmp(&other.tracks.len())
+            .then_with(|| self.name.cmp(&other.name))
+    }
+}
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::cmp::Ord>() {}
+    #[allow(dead_code)]
+    fn assert_fields<T>(_: &Playlist<T>) {
+        assert_bound::<String>();
+    }
+};
+"#
+        );
+    }
+
+    #[cfg(feature = "ordered-float")]
+    #[test]
+    fn test_ordered_float() {
+        let input = syn::parse_quote! {
+            struct Point {
+                #[cmp_by(ordered_float)]
+                x: f64,
+                #[cmp_by(ordered_float, reverse)]
+                y: f32,
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Point {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Self> for Point {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        ::ordered_float::OrderedFloat(self.x) == ::ordered_float::OrderedFloat(other.x)
+            && ::ordered_float::OrderedFloat(self.y) == ::ordered_float::OrderedFloat(other.y)
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<Self> for Point {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Ord for Point {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        ::ordered_float::OrderedFloat(self.x)
+            .cmp(&::ordered_float::OrderedFloat(other.x))
+            .then_with(|| {
+                ::ordered_float::OrderedFloat(other.y).cmp(&::ordered_float::OrderedFloat(self.y))
+            })
+    }
+}
+"#
+        );
+    }
+
+    #[cfg(feature = "indexmap")]
+    #[test]
+    fn test_as_pairs() {
+        let input = syn::parse_quote! {
+            enum Config {
+                Ordered(#[cmp_by(as_pairs)] IndexMap<String, u32>),
+                Settings {
+                    #[cmp_by(as_pairs, reverse)]
+                    values: IndexMap<String, u32>,
+                },
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Config {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Self> for Config {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Ordered { 0: self_0, .. }, Self::Ordered { 0: other_0, .. }) => {
+                ::indexmap::IndexMap::as_slice(self_0) == ::indexmap::IndexMap::as_slice(other_0)
+            }
+            (
+                Self::Settings {
+                    values: self_values,
+                    ..
+                },
+                Self::Settings {
+                    values: other_values,
+                    ..
+                },
+            ) => {
+                ::indexmap::IndexMap::as_slice(self_values)
+                    == ::indexmap::IndexMap::as_slice(other_values)
+            }
+            (this, other) => ::core::mem::discriminant(this) == ::core::mem::discriminant(other),
+        }
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<Self> for Config {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Ord for Config {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        match (self, other) {
+            (Self::Ordered { 0: self_0, .. }, Self::Ordered { 0: other_0, .. }) => {
+                ::indexmap::IndexMap::as_slice(self_0).cmp(&::indexmap::IndexMap::as_slice(other_0))
+            }
+            (
+                Self::Settings {
+                    values: self_values,
+                    ..
+                },
+                Self::Settings {
+                    values: other_values,
+                    ..
+                },
+            ) => ::indexmap::IndexMap::as_slice(other_values)
+                .cmp(&::indexmap::IndexMap::as_slice(self_values)),
+            (this, other) => match this {
+                Self::Ordered(..) => 0,
+                Self::Settings { .. } => 1,
+            }
+            .cmp(&match other {
+                Self::Ordered(..) => 0,
+                Self::Settings { .. } => 1,
+            }),
+        }
+    }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_abs() {
+        let input = syn::parse_quote! {
+            struct Residual {
+                #[cmp_by(abs)]
+                offset: i32,
+                #[cmp_by(abs, total)]
+                error: f64,
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Residual {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Self> for Residual {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.offset.abs() == other.offset.abs()
+            && self.error.abs().total_cmp(&other.error.abs()).is_eq()
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<Self> for Residual {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Ord for Residual {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        self.offset
+            .abs()
+            .cmp(&other.offset.abs())
+            .then_with(|| self.error.abs().total_cmp(&other.error.abs()))
+    }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_cast() {
+        let input = syn::parse_quote! {
+            enum Reading {
+                Small(#[cmp_by(as = "u64")] u8),
+                Large {
+                    #[cmp_by(as = "u64", reverse)]
+                    value: u32,
+                    #[cmp_by(as = "isize")]
+                    kind: Kind,
+                },
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Reading {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Self> for Reading {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Small { 0: self_0, .. }, Self::Small { 0: other_0, .. }) => {
+                (*self_0 as u64) == (*other_0 as u64)
+            }
+            (
+                Self::Large {
+                    value: self_value,
+                    kind: self_kind,
+                    ..
+                },
+                Self::Large {
+                    value: other_value,
+                    kind: other_kind,
+                    ..
+                },
+            ) => {
+                (*self_value as u64) == (*other_value as u64)
+                    && (*self_kind as isize) == (*other_kind as isize)
+            }
+            (this, other) => ::core::mem::discriminant(this) == ::core::mem::discriminant(other),
+        }
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<Self> for Reading {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Ord for Reading {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        match (self, other) {
+            (Self::Small { 0: self_0, .. }, Self::Small { 0: other_0, .. }) => {
+                (*self_0 as u64).cmp(&(*other_0 as u64))
+            }
+            (
+                Self::Large {
+                    value: self_value,
+                    kind: self_kind,
+                    ..
+                },
+                Self::Large {
+                    value: other_value,
+                    kind: other_kind,
+                    ..
+                },
+            ) => (*other_value as u64)
+                .cmp(&(*self_value as u64))
+                .then_with(|| (*self_kind as isize).cmp(&(*other_kind as isize))),
+            (this, other) => match this {
+                Self::Small(..) => 0,
+                Self::Large { .. } => 1,
+            }
+            .cmp(&match other {
+                Self::Small(..) => 0,
+                Self::Large { .. } => 1,
+            }),
+        }
+    }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_lex() {
+        let input = syn::parse_quote! {
+            enum Path {
+                Version(#[cmp_by(lex = "prefix")] Vec<u32>),
+                Host {
+                    #[cmp_by(lex = "suffix", reverse)]
+                    labels: Vec<String>,
+                },
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Path {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Self> for Path {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Version { 0: self_0, .. }, Self::Version { 0: other_0, .. }) => {
+                ::core::iter::Iterator::zip(self_0.iter(), other_0.iter())
+                    .all(|(this, other)| this == other)
+            }
+            (
+                Self::Host {
+                    labels: self_labels,
+                    ..
+                },
+                Self::Host {
+                    labels: other_labels,
+                    ..
+                },
+            ) => ::core::iter::Iterator::zip(
+                ::core::iter::Iterator::rev(self_labels.iter()),
+                ::core::iter::Iterator::rev(other_labels.iter()),
+            )
+            .all(|(this, other)| this == other),
+            (this, other) => ::core::mem::discriminant(this) == ::core::mem::discriminant(other),
+        }
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<Self> for Path {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Ord for Path {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        match (self, other) {
+            (Self::Version { 0: self_0, .. }, Self::Version { 0: other_0, .. }) => {
+                ::core::iter::Iterator::zip(self_0.iter(), other_0.iter())
+                    .map(|(this, other)| this.cmp(other))
+                    .find(|ord| ord.is_ne())
+                    .unwrap_or(::core::cmp::Ordering::Equal)
+            }
+            (
+                Self::Host {
+                    labels: self_labels,
+                    ..
+                },
+                Self::Host {
+                    labels: other_labels,
+                    ..
+                },
+            ) => ::core::iter::Iterator::zip(
+                ::core::iter::Iterator::rev(other_labels.iter()),
+                ::core::iter::Iterator::rev(self_labels.iter()),
+            )
+            .map(|(this, other)| this.cmp(other))
+            .find(|ord| ord.is_ne())
+            .unwrap_or(::core::cmp::Ordering::Equal),
+            (this, other) => match this {
+                Self::Version(..) => 0,
+                Self::Host { .. } => 1,
+            }
+            .cmp(&match other {
+                Self::Version(..) => 0,
+                Self::Host { .. } => 1,
+            }),
+        }
+    }
+}
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::cmp::Ord>() {}
+    #[allow(dead_code)]
+    fn assert_fields(_: &Path) {
+        assert_bound::<Vec<u32>>();
+        assert_bound::<Vec<String>>();
+    }
+};
+"#
+        );
+    }
+
+    #[test]
+    fn test_by_display() {
+        let input = syn::parse_quote! {
+            enum Log {
+                Failure(#[cmp_by(by_display)] Error),
+                Warning {
+                    #[cmp_by(by_display, reverse)]
+                    message: Message,
+                },
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Log {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Self> for Log {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Failure { 0: self_0, .. }, Self::Failure { 0: other_0, .. }) => {
+                ::std::string::ToString::to_string(&self_0)
+                    == ::std::string::ToString::to_string(&other_0)
+            }
+            (
+                Self::Warning {
+                    message: self_message,
+                    ..
+                },
+                Self::Warning {
+                    message: other_message,
+                    ..
+                },
+            ) => {
+                ::std::string::ToString::to_string(&self_message)
+                    == ::std::string::ToString::to_string(&other_message)
+            }
+            (this, other) => ::core::mem::discriminant(this) == ::core::mem::discriminant(other),
+        }
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<Self> for Log {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Ord for Log {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        match (self, other) {
+            (Self::Failure { 0: self_0, .. }, Self::Failure { 0: other_0, .. }) => {
+                ::std::string::ToString::to_string(&self_0)
+                    .cmp(&::std::string::ToString::to_string(&other_0))
+            }
+            (
+                Self::Warning {
+                    message: self_message,
+                    ..
+                },
+                Self::Warning {
+                    message: other_message,
+                    ..
+                },
+            ) => ::std::string::ToString::to_string(&other_message)
+                .cmp(&::std::string::ToString::to_string(&self_message)),
+            (this, other) => match this {
+                Self::Failure(..) => 0,
+                Self::Warning { .. } => 1,
+            }
+            .cmp(&match other {
+                Self::Failure(..) => 0,
+                Self::Warning { .. } => 1,
+            }),
+        }
+    }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_collate_with() {
+        let input = syn::parse_quote! {
+            struct Person {
+                #[cmp_by(collate_with = "locale::collate")]
+                last: String,
+                #[cmp_by(collate_with = "locale::collate", reverse)]
+                first: String,
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Person {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Self> for Person {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        locale::collate(
+            ::core::convert::AsRef::<str>::as_ref(&self.last),
+            ::core::convert::AsRef::<str>::as_ref(&other.last),
+        )
+        .is_eq()
+            && locale::collate(
+                ::core::convert::AsRef::<str>::as_ref(&self.first),
+                ::core::convert::AsRef::<str>::as_ref(&other.first),
+            )
+            .is_eq()
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<Self> for Person {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Ord for Person {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        locale::collate(
+            ::core::convert::AsRef::<str>::as_ref(&self.last),
+            ::core::convert::AsRef::<str>::as_ref(&other.last),
+        )
+        .then_with(|| {
+            locale::collate(
+                ::core::convert::AsRef::<str>::as_ref(&other.first),
+                ::core::convert::AsRef::<str>::as_ref(&self.first),
+            )
+        })
+    }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_non_exhaustive() {
+        // Every variant has its own arm, so a variant the derive didn't handle fails to compile instead of being
+        // ordered by a wildcard.
+        let input = syn::parse_quote! {
+            #[non_exhaustive]
+            enum Event {
+                Click(#[cmp_by] u32),
+                #[non_exhaustive]
+                Scroll { #[cmp_by] delta: i32 },
+                #[non_exhaustive]
+                Close,
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Event {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Self> for Event {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Click { 0: self_0, .. }, Self::Click { 0: other_0, .. }) => self_0 == other_0,
+            (
+                Self::Scroll {
+                    delta: self_delta, ..
+                },
+                Self::Scroll {
+                    delta: other_delta, ..
+                },
+            ) => self_delta == other_delta,
+            (this, other) => ::core::mem::discriminant(this) == ::core::mem::discriminant(other),
+        }
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<Self> for Event {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Ord for Event {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        match (self, other) {
+            (Self::Click { 0: self_0, .. }, Self::Click { 0: other_0, .. }) => self_0.cmp(&other_0),
+            (
+                Self::Scroll {
+                    delta: self_delta, ..
+                },
+                Self::Scroll {
+                    delta: other_delta, ..
+                },
+            ) => self_delta.cmp(&other_delta),
+            (this, other) => match this {
+                Self::Click(..) => 0,
+                Self::Scroll { .. } => 1,
+                Self::Close => 2,
+            }
+            .cmp(&match other {
+                Self::Click(..) => 0,
+                Self::Scroll { .. } => 1,
+                Self::Close => 2,
+            }),
+        }
+    }
+}
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::cmp::Ord>() {}
+    #[allow(dead_code)]
+    fn assert_fields(_: &Event) {
+        assert_bound::<u32>();
+        assert_bound::<i32>();
+    }
+};
+"#
+        );
+    }
+
+    #[test]
+    fn test_map() {
+        let input = syn::parse_quote! {
+            struct Tag {
+                #[cmp_by(map = |name| name.trim())]
+                name: String,
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Tag {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Self> for Tag {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        ::core::option::Option::Some(&self.name).map(|name| name.trim())
+            == ::core::option::Option::Some(&other.name).map(|name| name.trim())
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<Self> for Tag {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Ord for Tag {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        ::core::option::Option::Some(&self.name)
+            .map(|name| name.trim())
+            .cmp(&::core::option::Option::Some(&other.name).map(|name| name.trim()))
+    }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_index() {
+        let input = syn::parse_quote! {
+            enum Packet {
+                V4(#[cmp_by(index_range = "0..4")] [u8; 16]),
+                V6 { #[cmp_by(index = 0)] bytes: Vec<u8> },
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Packet {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Self> for Packet {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::V4 { 0: self_0, .. }, Self::V4 { 0: other_0, .. }) => {
+                self_0[0..4] == other_0[0..4]
+            }
+            (
+                Self::V6 {
+                    bytes: self_bytes, ..
+                },
+                Self::V6 {
+                    bytes: other_bytes, ..
+                },
+            ) => self_bytes[0] == other_bytes[0],
+            (this, other) => ::core::mem::discriminant(this) == ::core::mem::discriminant(other),
+        }
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<Self> for Packet {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Ord for Packet {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        match (self, other) {
+            (Self::V4 { 0: self_0, .. }, Self::V4 { 0: other_0, .. }) => {
+                self_0[0..4].cmp(&other_0[0..4])
+            }
+            (
+                Self::V6 {
+                    bytes: self_bytes, ..
+                },
+                Self::V6 {
+                    bytes: other_bytes, ..
+                },
+            ) => self_bytes[0].cmp(&other_bytes[0]),
+            (this, other) => match this {
+                Self::V4(..) => 0,
+                Self::V6 { .. } => 1,
+            }
+            .cmp(&match other {
+                Self::V4(..) => 0,
+                Self::V6 { .. } => 1,
+            }),
+        }
+    }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_case_insensitive() {
+        let input = syn::parse_quote! {
+            struct User {
+                #[cmp_by(case_insensitive)]
+                name: String,
+                #[cmp_by(case_insensitive, reverse)]
+                nickname: &'static str,
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for User {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Self> for User {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.name.eq_ignore_ascii_case(&other.name)
+            && self.nickname.eq_ignore_ascii_case(&other.nickname)
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<Self> for User {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Ord for User {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        self.name
+            .bytes()
+            .map(|byte| byte.to_ascii_lowercase())
+            .cmp(other.name.bytes().map(|byte| byte.to_ascii_lowercase()))
+            .then_with(|| {
+                other
+                    .nickname
+                    .bytes()
+                    .map(|byte| byte.to_ascii_lowercase())
+                    .cmp(self.nickname.bytes().map(|byte| byte.to_ascii_lowercase()))
+            })
+    }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_nested_field() {
+        let input = syn::parse_quote! {
+            struct Event<T> (
+                #[cmp_by = "0.timestamp"]
+                Metadata<T>,
+                #[cmp_by]
+                T,
+            );
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl<T> ::core::cmp::Eq for Event<T> where T: ::core::cmp::Ord {}
+#[automatically_derived]
+impl<T> ::core::cmp::PartialEq<Self> for Event<T>
+where
+    T: ::core::cmp::Ord,
+{
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.0.timestamp == other.0.timestamp && self.1 == other.1
+    }
+}
+#[automatically_derived]
+impl<T> ::core::cmp::PartialOrd<Self> for Event<T>
+where
+    T: ::core::cmp::Ord,
+{
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl<T> ::core::cmp::Ord for Event<T>
+where
+    T: ::core::cmp::Ord,
+{
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        self.0
+            .timestamp
+            .cmp(&other.0.timestamp)
+            .then_with(|| self.1.cmp(&other.1))
+    }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_tuple_indices() {
+        let input = syn::parse_quote! {
+            #[cmp_by(2, len(), Reverse(_fields), 0.name())]
+            struct Something (
+              Label,
+              #[cmp_by]
+              u32,
+              f32,
+            );
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Something {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Self> for Something {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.2 == other.2
+            && self.len() == other.len()
+            && self.0.name() == other.0.name()
+            && self.1 == other.1
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<Self> for Something {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Ord for Something {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        self.2
+            .cmp(&other.2)
+            .then_with(|| self.len().cmp(&other.len()))
+            .then_with(|| (self.1.cmp(&other.1)).reverse())
+            .then_with(|| self.0.name().cmp(&other.0.name()))
+    }
+}
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::cmp::Ord>() {}
+    #[allow(dead_code)]
+    fn assert_fields(_: &Something) {
+        assert_bound::<u32>();
+    }
+};
+"#
+        );
+    }
+
+    #[test]
+    fn test_multiple_attributes() {
+        let input = syn::parse_quote! {
+            #[cmp_by(a())]
+            #[derive(Debug)]
+            #[cmp_by(b(), c)]
+            struct Toto {
+                #[cmp_by]
+                d: u16,
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Toto {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Self> for Toto {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.a() == other.a() && self.b() == other.b() && self.c == other.c && self.d == other.d
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<Self> for Toto {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Ord for Toto {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        self.a()
+            .cmp(&other.a())
+            .then_with(|| self.b().cmp(&other.b()))
+            .then_with(|| self.c.cmp(&other.c))
+            .then_with(|| self.d.cmp(&other.d))
+    }
+}
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::cmp::Ord>() {}
+    #[allow(dead_code)]
+    fn assert_fields(_: &Toto) {
+        assert_bound::<u16>();
+    }
+};
+"#
+        );
+    }
+
+    #[test]
+    fn test_call_arguments() {
+        let input = syn::parse_quote! {
+            #[cmp_by(value_in(Unit::Meters), digit(2), inner.scaled(10, Unit::Feet))]
+            struct Distance {
+                #[cmp_by]
+                id: u32,
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Distance {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Self> for Distance {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.value_in(Unit::Meters) == other.value_in(Unit::Meters)
+            && self.digit(2) == other.digit(2)
+            && self.inner.scaled(10, Unit::Feet) == other.inner.scaled(10, Unit::Feet)
+            && self.id == other.id
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<Self> for Distance {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Ord for Distance {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        self.value_in(Unit::Meters)
+            .cmp(&other.value_in(Unit::Meters))
+            .then_with(|| self.digit(2).cmp(&other.digit(2)))
+            .then_with(|| {
+                self.inner
+                    .scaled(10, Unit::Feet)
+                    .cmp(&other.inner.scaled(10, Unit::Feet))
+            })
+            .then_with(|| self.id.cmp(&other.id))
+    }
+}
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::cmp::Ord>() {}
+    #[allow(dead_code)]
+    fn assert_fields(_: &Distance) {
+        assert_bound::<u32>();
+    }
+};
+"#
+        );
+    }
+
+    #[test]
+    fn test_turbofish() {
+        let input = syn::parse_quote! {
+            #[cmp_by(get::<Position>(), components.get::<Velocity>())]
+            struct Entity {
+                #[cmp_by]
+                id: u32,
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Entity {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Self> for Entity {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.get::<Position>() == other.get::<Position>()
+            && self.components.get::<Velocity>() == other.components.get::<Velocity>()
+            && self.id == other.id
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<Self> for Entity {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Ord for Entity {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        self.get::<Position>()
+            .cmp(&other.get::<Position>())
+            .then_with(|| {
+                self.components
+                    .get::<Velocity>()
+                    .cmp(&other.components.get::<Velocity>())
+            })
+            .then_with(|| self.id.cmp(&other.id))
+    }
+}
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::cmp::Ord>() {}
+    #[allow(dead_code)]
+    fn assert_fields(_: &Entity) {
+        assert_bound::<u32>();
+    }
+};
+"#
+        );
+    }
+
+    #[test]
+    fn test_free_function() {
+        let input = syn::parse_quote! {
+            #[cmp_by(my_crate::weight(self), Reverse(distance(origin(), self)))]
+            struct Parcel {
+                #[cmp_by]
+                id: u32,
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Parcel {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Self> for Parcel {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        my_crate::weight(self) == my_crate::weight(other)
+            && distance(origin(), self) == distance(origin(), other)
+            && self.id == other.id
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<Self> for Parcel {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Ord for Parcel {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        my_crate::weight(self)
+            .cmp(&my_crate::weight(other))
+            .then_with(|| distance(origin(), other).cmp(&distance(origin(), self)))
+            .then_with(|| self.id.cmp(&other.id))
+    }
+}
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::cmp::Ord>() {}
+    #[allow(dead_code)]
+    fn assert_fields(_: &Parcel) {
+        assert_bound::<u32>();
+    }
+};
+"#
+        );
+    }
+
+    #[test]
+    fn test_all() {
+        let input = syn::parse_quote! {
+            #[cmp_by(all)]
+            struct Toto {
+                a: u16,
+                #[cmp_by(skip)]
+                cached_hash: u64,
+                #[cmp_by(reverse)]
+                b: u32,
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Toto {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Self> for Toto {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.a == other.a && self.b == other.b
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<Self> for Toto {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Ord for Toto {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        self.a.cmp(&other.a).then_with(|| other.b.cmp(&self.b))
+    }
+}
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::cmp::Ord>() {}
+    #[allow(dead_code)]
+    fn assert_fields(_: &Toto) {
+        assert_bound::<u16>();
+        assert_bound::<u32>();
+    }
+};
+"#
+        );
+    }
+
+    #[test]
+    fn test_order() {
+        let input = syn::parse_quote! {
+            struct Toto {
+                #[cmp_by]
+                a: u16,
+                #[cmp_by(order = 1)]
+                b: u32,
+                #[cmp_by]
+                c: u8,
+                #[cmp_by(order = 0, reverse)]
+                d: u64,
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Toto {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Self> for Toto {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.d == other.d && self.b == other.b && self.a == other.a && self.c == other.c
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<Self> for Toto {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Ord for Toto {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        other
+            .d
+            .cmp(&self.d)
+            .then_with(|| self.b.cmp(&other.b))
+            .then_with(|| self.a.cmp(&other.a))
+            .then_with(|| self.c.cmp(&other.c))
+    }
+}
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::cmp::Ord>() {}
+    #[allow(dead_code)]
+    fn assert_fields(_: &Toto) {
+        assert_bound::<u64>();
+        assert_bound::<u32>();
+        assert_bound::<u16>();
+        assert_bound::<u8>();
+    }
+};
+"#
+        );
+    }
+
+    #[test]
+    fn test_cache() {
+        let input = syn::parse_quote! {
+            struct Blob {
+                data: Vec<u8>,
+                #[cmp_by(cache = "Self::digest")]
+                digest: OnceCell<u64>,
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Blob {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Self> for Blob {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.digest.get_or_init(|| Self::digest(self))
+            == other.digest.get_or_init(|| Self::digest(other))
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<Self> for Blob {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Ord for Blob {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        self.digest
+            .get_or_init(|| Self::digest(self))
+            .cmp(&other.digest.get_or_init(|| Self::digest(other)))
+    }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_other() {
+        let input = syn::parse_quote! {
+            #[cmp_by(other = "Timestamp", key = "ts()")]
+            #[cmp_by(other = "Priority", key = "Reverse(priority)")]
+            struct Event {
+                #[cmp_by]
+                id: u32,
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Event {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Self> for Event {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<Self> for Event {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Ord for Event {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        self.id.cmp(&other.id)
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Timestamp> for Event {
+    #[inline]
+    fn eq(&self, other: &Timestamp) -> bool {
+        self.ts().eq(other)
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Priority> for Event {
+    #[inline]
+    fn eq(&self, other: &Priority) -> bool {
+        self.priority.eq(other)
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<Timestamp> for Event {
+    #[inline]
+    fn partial_cmp(&self, other: &Timestamp) -> ::core::option::Option<::core::cmp::Ordering> {
+        self.ts().partial_cmp(other)
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<Priority> for Event {
+    #[inline]
+    fn partial_cmp(&self, other: &Priority) -> ::core::option::Option<::core::cmp::Ordering> {
+        (self.priority.partial_cmp(other)).map(::core::cmp::Ordering::reverse)
+    }
+}
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::cmp::Ord>() {}
+    #[allow(dead_code)]
+    fn assert_fields(_: &Event) {
+        assert_bound::<u32>();
+    }
+};
+"#
+        );
+    }
+
+    #[test]
+    fn test_borrow_key() {
+        let input = syn::parse_quote! {
+            #[cmp_by(borrow_key = "str")]
+            struct Event {
+                #[cmp_by]
+                name: String,
+                at: u64,
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Event {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Self> for Event {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<Self> for Event {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Ord for Event {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        self.name.cmp(&other.name)
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<str> for Event {
+    #[inline]
+    fn eq(&self, other: &str) -> bool {
+        ::core::borrow::Borrow::<str>::borrow(&self.name).eq(other)
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<str> for Event {
+    #[inline]
+    fn partial_cmp(&self, other: &str) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::borrow::Borrow::<str>::borrow(&self.name).partial_cmp(other)
+    }
+}
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::cmp::Ord>() {}
+    #[allow(dead_code)]
+    fn assert_fields(_: &Event) {
+        assert_bound::<String>();
+    }
+};
+"#
+        );
+    }
+
+    #[test]
+    fn test_impl_sort_key() {
+        let input = syn::parse_quote! {
+            #[cmp_by(impl_sort_key)]
+            struct Task<'a, T> {
+                #[cmp_by(reverse)]
+                priority: u8,
+                #[cmp_by(none_last)]
+                deadline: Option<u32>,
+                #[cmp_by]
+                name: &'a str,
+                #[cmp_by]
+                payload: T,
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl<'a, T> ::core::cmp::Eq for Task<'a, T> where T: ::core::cmp::Ord {}
+#[automatically_derived]
+impl<'a, T> ::core::cmp::PartialEq<Self> for Task<'a, T>
+where
+    T: ::core::cmp::Ord,
+{
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.priority == other.priority
+            && self.deadline == other.deadline
+            && self.name == other.name
+            && self.payload == other.payload
+    }
+}
+#[automatically_derived]
+impl<'a, T> ::core::cmp::PartialOrd<Self> for Task<'a, T>
+where
+    T: ::core::cmp::Ord,
+{
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl<'a, T> ::core::cmp::Ord for Task<'a, T>
+where
+    T: ::core::cmp::Ord,
+{
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        other
+            .priority
+            .cmp(&self.priority)
+            .then_with(|| {
+                self.deadline
+                    .is_none()
+                    .cmp(&other.deadline.is_none())
+                    .then_with(|| self.deadline.cmp(&other.deadline))
+            })
+            .then_with(|| self.name.cmp(&other.name))
+            .then_with(|| self.payload.cmp(&other.payload))
+    }
+}
+#[automatically_derived]
+impl<'a, T> SortKey for Task<'a, T>
+where
+    T: ::core::cmp::Ord,
+{
+    type Key<'key>
+        = (
+        ::core::cmp::Reverse<&'key u8>,
+        (bool, &'key Option<u32>),
+        &'key &'a str,
+        &'key T,
+    )
+    where
+        Self: 'key;
+    #[inline]
+    fn sort_key(&self) -> Self::Key<'_> {
+        (
+            ::core::cmp::Reverse(&self.priority),
+            (self.deadline.is_none(), &self.deadline),
+            &self.name,
+            &self.payload,
+        )
+    }
+}
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::cmp::Ord>() {}
+    #[allow(dead_code)]
+    fn assert_fields<'a, T>(_: &Task<'a, T>)
+    where
+        T: ::core::cmp::Ord,
+    {
+        assert_bound::<u8>();
+        assert_bound::<Option<u32>>();
+        assert_bound::<&'a str>();
+    }
+};
+"#
+        );
+    }
+
+    #[test]
+    fn test_gen_key() {
+        let input = syn::parse_quote! {
+            #[cmp_by(gen_key, priority(), _fields)]
+            struct Task {
+                #[cmp_by(reverse)]
+                name: String,
+                #[cmp_by(by_len)]
+                tags: Vec<String>,
+                #[cmp_by(none_last)]
+                due: Option<u32>,
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Task {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Self> for Task {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.priority() == other.priority()
+            && self.name == other.name
+            && self.tags.len() == other.tags.len()
+            && self.due == other.due
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<Self> for Task {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Ord for Task {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        self.priority().cmp(&other.priority()).then_with(|| {
+            other
+                .name
+                .cmp(&self.name)
+                .then_with(|| self.tags.len().cmp(&other.tags.len()))
+                .then_with(|| {
+                    self.due
+                        .is_none()
+                        .cmp(&other.due.is_none())
+                        .then_with(|| self.due.cmp(&other.due))
+                })
+        })
+    }
+}
+#[automatically_derived]
+impl Task {
+    #[doc = r" The compared items, in the order they are compared."]
+    #[inline]
+    pub fn cmp_key(&self) -> impl ::core::cmp::Ord + '_ {
+        (
+            self.priority(),
+            ::core::cmp::Reverse(&self.name),
+            self.tags.len(),
+            (self.due.is_none(), &self.due),
+        )
+    }
+}
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::cmp::Ord>() {}
+    #[allow(dead_code)]
+    fn assert_fields(_: &Task) {
+        assert_bound::<String>();
+        assert_bound::<Option<u32>>();
+    }
+};
+"#
+        );
+    }
+
+    #[test]
+    fn test_tuple_cmp() {
+        let input = syn::parse_quote! {
+            #[cmp_by(tuple_cmp, priority(), _fields)]
+            struct Task {
+                #[cmp_by(reverse)]
+                name: String,
+                #[cmp_by]
+                id: u32,
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Task {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Self> for Task {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.priority() == other.priority() && self.name == other.name && self.id == other.id
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<Self> for Task {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Ord for Task {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        (self.priority(), ::core::cmp::Reverse(&self.name), &self.id).cmp(&(
+            other.priority(),
+            ::core::cmp::Reverse(&other.name),
+            &other.id,
+        ))
+    }
+}
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::cmp::Ord>() {}
+    #[allow(dead_code)]
+    fn assert_fields(_: &Task) {
+        assert_bound::<String>();
+        assert_bound::<u32>();
+    }
+};
+"#
+        );
+    }
+
+    #[test]
+    fn test_const_fn() {
+        let input = syn::parse_quote! {
+            #[cmp_by(const_fn)]
+            struct Version {
+                #[cmp_by]
+                major: u16,
+                #[cmp_by(reverse, as = "u32")]
+                minor: u8,
+                #[cmp_by(abs)]
+                offset: i8,
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Version {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Self> for Version {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.major == other.major
+            && (self.minor as u32) == (other.minor as u32)
+            && self.offset.abs() == other.offset.abs()
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<Self> for Version {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Ord for Version {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        self.major
+            .cmp(&other.major)
+            .then_with(|| (other.minor as u32).cmp(&(self.minor as u32)))
+            .then_with(|| self.offset.abs().cmp(&other.offset.abs()))
+    }
+}
+#[automatically_derived]
+impl Version {
+    #[doc = r" Compare like `Ord::cmp`, in const contexts."]
+    #[inline]
+    pub const fn const_cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        if self.major != other.major {
+            return if self.major < other.major {
+                ::core::cmp::Ordering::Less
+            } else {
+                ::core::cmp::Ordering::Greater
+            };
+        }
+        if (other.minor as u32) != (self.minor as u32) {
+            return if (other.minor as u32) < (self.minor as u32) {
+                ::core::cmp::Ordering::Less
+            } else {
+                ::core::cmp::Ordering::Greater
+            };
+        }
+        if self.offset.abs() != other.offset.abs() {
+            return if self.offset.abs() < other.offset.abs() {
+                ::core::cmp::Ordering::Less
+            } else {
+                ::core::cmp::Ordering::Greater
+            };
+        }
+        ::core::cmp::Ordering::Equal
+    }
+}
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::cmp::Ord>() {}
+    #[allow(dead_code)]
+    fn assert_fields(_: &Version) {
+        assert_bound::<u16>();
+    }
+};
+"#
+        );
+    }
+
+    #[test]
+    fn test_gen_key_fields() {
+        let input = syn::parse_quote! {
+            #[cmp_by(gen_key, gen_min_heap_key, descending)]
+            struct Task<T> {
+                #[cmp_by]
+                priority: u8,
+                #[cmp_by(none_last)]
+                owner: Option<T>,
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl<T> ::core::cmp::Eq for Task<T> where Option<T>: ::core::cmp::Ord {}
+#[automatically_derived]
+impl<T> ::core::cmp::PartialEq<Self> for Task<T>
+where
+    Option<T>: ::core::cmp::Ord,
+{
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.priority == other.priority && self.owner == other.owner
+    }
+}
+#[automatically_derived]
+impl<T> ::core::cmp::PartialOrd<Self> for Task<T>
+where
+    Option<T>: ::core::cmp::Ord,
+{
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl<T> ::core::cmp::Ord for Task<T>
+where
+    Option<T>: ::core::cmp::Ord,
+{
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        (self.priority.cmp(&other.priority).then_with(|| {
+            other
+                .owner
+                .is_none()
+                .cmp(&self.owner.is_none())
+                .then_with(|| self.owner.cmp(&other.owner))
+        }))
+        .reverse()
+    }
+}
+#[automatically_derived]
+impl<T> Task<T>
+where
+    Option<T>: ::core::cmp::Ord,
+{
+    #[doc = r" The compared items, in the order they are compared."]
+    #[inline]
+    pub fn cmp_key<'key>(
+        &'key self,
+    ) -> (
+        ::core::cmp::Reverse<&'key u8>,
+        (bool, ::core::cmp::Reverse<&'key Option<T>>),
+    ) {
+        (
+            ::core::cmp::Reverse(&self.priority),
+            (self.owner.is_none(), ::core::cmp::Reverse(&self.owner)),
+        )
+    }
+    #[doc = r" The compared items, reversed so that a `BinaryHeap` pops the least value first."]
+    #[inline]
+    pub fn min_heap_key<'key>(
+        &'key self,
+    ) -> ::core::cmp::Reverse<(
+        ::core::cmp::Reverse<&'key u8>,
+        (bool, ::core::cmp::Reverse<&'key Option<T>>),
+    )> {
+        ::core::cmp::Reverse((
+            ::core::cmp::Reverse(&self.priority),
+            (self.owner.is_none(), ::core::cmp::Reverse(&self.owner)),
+        ))
+    }
+}
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::cmp::Ord>() {}
+    #[allow(dead_code)]
+    fn assert_fields<T>(_: &Task<T>)
+    where
+        Option<T>: ::core::cmp::Ord,
+    {
+        assert_bound::<u8>();
+    }
+};
+"#
+        );
+    }
+
+    #[test]
+    fn test_gen_min_heap_key() {
+        let input = syn::parse_quote! {
+            #[cmp_by(gen_key, gen_min_heap_key, NoneLast(deadline()), _fields)]
+            struct Event {
+                #[cmp_by]
+                time: u64,
+                #[cmp_by(reverse)]
+                priority: u8,
             }
-        }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Event {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Self> for Event {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.deadline() == other.deadline()
+            && self.time == other.time
+            && self.priority == other.priority
     }
 }
-
-fn gen_cmp_exprs(sortable_expr: &[Expr]) -> Option<Expr> {
-    // println!("Entering gen_cmp_expr");
-    sortable_expr
-        .iter()
-        .map(|expr| {
-            parse_quote_spanned! { expr.span() =>
-                self.#expr.cmp(&other.#expr)
-            }
-        })
-        .reduce(|ord_expr: Expr, expr| {
-            // println!("Combining {} with {}", quote!(#ord_expr), quote!(#expr));
-            parse_quote_spanned! {expr.span() =>
-                #ord_expr.then_with(|| #expr)
-            }
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<Self> for Event {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Ord for Event {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        {
+            let self_key = &self.deadline();
+            let other_key = &other.deadline();
+            self_key
+                .is_none()
+                .cmp(&other_key.is_none())
+                .then_with(|| self_key.cmp(&other_key))
+        }
+        .then_with(|| {
+            self.time
+                .cmp(&other.time)
+                .then_with(|| other.priority.cmp(&self.priority))
         })
+    }
 }
-
-#[cfg(test)]
-pub(crate) mod test {
-    use quote::quote;
-
-    #[macro_export]
-    macro_rules! assert_rust_eq {
-        ($actual: expr, $expected: expr$(, $msg: literal)?) => {
-            use rust_format::Formatter;
-            let rust_fmt = rust_format::RustFmt::default();
-            assert_eq!(
-                rust_fmt
-                    .format_str($actual)
-                    .expect("Actual value could not be formatted"),
-                rust_fmt
-                    .format_str($expected)
-                    .expect("Expected value could not be formatted"),
-                $($msg)?
-            );
-        };
+#[automatically_derived]
+impl Event {
+    #[doc = r" The compared items, in the order they are compared."]
+    #[inline]
+    pub fn cmp_key(&self) -> impl ::core::cmp::Ord + '_ {
+        (
+            {
+                let key = self.deadline();
+                (key.is_none(), key)
+            },
+            &self.time,
+            ::core::cmp::Reverse(&self.priority),
+        )
+    }
+    #[doc = r" The compared items, reversed so that a `BinaryHeap` pops the least value first."]
+    #[inline]
+    pub fn min_heap_key(&self) -> ::core::cmp::Reverse<impl ::core::cmp::Ord + '_> {
+        ::core::cmp::Reverse((
+            {
+                let key = self.deadline();
+                (key.is_none(), key)
+            },
+            &self.time,
+            ::core::cmp::Reverse(&self.priority),
+        ))
+    }
+}
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::cmp::Ord>() {}
+    #[allow(dead_code)]
+    fn assert_fields(_: &Event) {
+        assert_bound::<u64>();
+        assert_bound::<u8>();
+    }
+};
+"#
+        );
     }
 
     #[test]
-    fn test_struct() {
+    fn test_inline_always() {
         let input = syn::parse_quote! {
-            #[cmp_by(embed.otherfield)]
+            #[cmp_by(inline = "always")]
             struct Toto {
                 #[cmp_by]
                 a: u16,
-                #[cmp_by]
-                c: u32,
-                b: f32,
-                embed: EmbedStruct
             }
         };
 
         let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
         assert_rust_eq!(
             output.to_string(),
-            r#"impl ::core::cmp::Eq for Toto {}
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Toto {}
+#[automatically_derived]
 impl ::core::cmp::PartialEq<Self> for Toto {
-    #[inline]
+    #[inline(always)]
     fn eq(&self, other: &Self) -> bool {
-        self.cmp(other).is_eq()
+        self.a == other.a
     }
 }
+#[automatically_derived]
 impl ::core::cmp::PartialOrd<Self> for Toto {
-    #[inline]
+    #[inline(always)]
     fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
         ::core::option::Option::Some(self.cmp(other))
     }
 }
+#[automatically_derived]
 impl ::core::cmp::Ord for Toto {
-    #[inline]
+    #[inline(always)]
     fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
-        self.embed
-            .otherfield
-            .cmp(&other.embed.otherfield)
-            .then_with(|| self.a.cmp(&other.a)
-            .then_with(|| self.c.cmp(&other.c)))
+        self.a.cmp(&other.a)
     }
 }
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::cmp::Ord>() {}
+    #[allow(dead_code)]
+    fn assert_fields(_: &Toto) {
+        assert_bound::<u16>();
+    }
+};
 "#
         );
     }
@@ -241,19 +4691,32 @@ impl ::core::cmp::Ord for Toto {
         let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
         assert_rust_eq!(
             output.to_string(),
-            r#"impl ::core::cmp::Eq for Toto {}
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Toto {}
+#[automatically_derived]
 impl ::core::cmp::PartialEq<Self> for Toto {
     #[inline]
     fn eq(&self, other: &Self) -> bool {
-        self.cmp(other).is_eq()
+        self.this == other.this
+            && self.this.that == other.this.that
+            && self.get_something() == other.get_something()
+            && self.something.do_this() == other.something.do_this()
+            && match (self, other) {
+                (Self::A { 0: self_0, .. }, Self::A { 0: other_0, .. }) => self_0 == other_0,
+                (this, other) => {
+                    ::core::mem::discriminant(this) == ::core::mem::discriminant(other)
+                }
+            }
     }
 }
+#[automatically_derived]
 impl ::core::cmp::PartialOrd<Self> for Toto {
     #[inline]
     fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
         ::core::option::Option::Some(self.cmp(other))
     }
 }
+#[automatically_derived]
 impl ::core::cmp::Ord for Toto {
     #[inline]
     fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
@@ -263,7 +4726,7 @@ impl ::core::cmp::Ord for Toto {
             .then_with(|| self.get_something().cmp(&other.get_something()))
             .then_with(|| self.something.do_this().cmp(&other.something.do_this()))
             .then_with(|| match (self, other) {
-                (this @ Self::A(..), other @ Self::A(..)) => self.0.cmp(&other.0),
+                (Self::A { 0: self_0, .. }, Self::A { 0: other_0, .. }) => self_0.cmp(&other_0),
                 (this, other) => match this {
                     Self::A(..) => 0,
                     Self::B => 1,
@@ -277,6 +4740,166 @@ impl ::core::cmp::Ord for Toto {
             })
     }
 }
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::cmp::Ord>() {}
+    #[allow(dead_code)]
+    fn assert_fields(_: &Toto) {
+        assert_bound::<u32>();
+    }
+};
+"#
+        );
+    }
+
+    #[test]
+    fn test_enum_variant_fields() {
+        let input = syn::parse_quote! {
+            enum Shape {
+                Circle(#[cmp_by] u32, f32),
+                Rect { #[cmp_by] width: u32, #[cmp_by(reverse)] height: u32 },
+                Empty,
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Shape {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Self> for Shape {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::Circle { 0: self_0, .. }, Self::Circle { 0: other_0, .. }) => self_0 == other_0,
+            (
+                Self::Rect {
+                    width: self_width,
+                    height: self_height,
+                    ..
+                },
+                Self::Rect {
+                    width: other_width,
+                    height: other_height,
+                    ..
+                },
+            ) => self_width == other_width && self_height == other_height,
+            (this, other) => ::core::mem::discriminant(this) == ::core::mem::discriminant(other),
+        }
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<Self> for Shape {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Ord for Shape {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        match (self, other) {
+            (Self::Circle { 0: self_0, .. }, Self::Circle { 0: other_0, .. }) => {
+                self_0.cmp(&other_0)
+            }
+            (
+                Self::Rect {
+                    width: self_width,
+                    height: self_height,
+                    ..
+                },
+                Self::Rect {
+                    width: other_width,
+                    height: other_height,
+                    ..
+                },
+            ) => self_width
+                .cmp(&other_width)
+                .then_with(|| other_height.cmp(&self_height)),
+            (this, other) => match this {
+                Self::Circle(..) => 0,
+                Self::Rect { .. } => 1,
+                Self::Empty => 2,
+            }
+            .cmp(&match other {
+                Self::Circle(..) => 0,
+                Self::Rect { .. } => 1,
+                Self::Empty => 2,
+            }),
+        }
+    }
+}
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::cmp::Ord>() {}
+    #[allow(dead_code)]
+    fn assert_fields(_: &Shape) {
+        assert_bound::<u32>();
+    }
+};
+"#
+        );
+    }
+
+    #[test]
+    fn test_enum_variant_positions() {
+        let input = syn::parse_quote! {
+            enum Pair {
+                #[cmp_by(1)]
+                A(u8, u8),
+                #[cmp_by(0)]
+                B(u8),
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Pair {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Self> for Pair {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Self::A { 1: self_1, .. }, Self::A { 1: other_1, .. }) => self_1 == other_1,
+            (Self::B { 0: self_0, .. }, Self::B { 0: other_0, .. }) => self_0 == other_0,
+            (this, other) => ::core::mem::discriminant(this) == ::core::mem::discriminant(other),
+        }
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<Self> for Pair {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Ord for Pair {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        match (self, other) {
+            (Self::A { 1: self_1, .. }, Self::A { 1: other_1, .. }) => self_1.cmp(&other_1),
+            (Self::B { 0: self_0, .. }, Self::B { 0: other_0, .. }) => self_0.cmp(&other_0),
+            (this, other) => match this {
+                Self::A(..) => 0,
+                Self::B(..) => 1,
+            }
+            .cmp(&match other {
+                Self::A(..) => 0,
+                Self::B(..) => 1,
+            }),
+        }
+    }
+}
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::cmp::Ord>() {}
+    #[allow(dead_code)]
+    fn assert_fields(_: &Pair) {
+        assert_bound::<u8>();
+    }
+};
 "#
         );
     }
@@ -295,19 +4918,28 @@ impl ::core::cmp::Ord for Toto {
         let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
         assert_rust_eq!(
             output.to_string(),
-            r#"impl ::core::cmp::Eq for Toto {}
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Toto {}
+#[automatically_derived]
 impl ::core::cmp::PartialEq<Self> for Toto {
     #[inline]
     fn eq(&self, other: &Self) -> bool {
-        self.cmp(other).is_eq()
+        self.get_something() == other.get_something()
+            && match (self, other) {
+                (this, other) => {
+                    ::core::mem::discriminant(this) == ::core::mem::discriminant(other)
+                }
+            }
     }
 }
+#[automatically_derived]
 impl ::core::cmp::PartialOrd<Self> for Toto {
     #[inline]
     fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
         ::core::option::Option::Some(self.cmp(other))
     }
 }
+#[automatically_derived]
 impl ::core::cmp::Ord for Toto {
     #[inline]
     fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
@@ -347,16 +4979,19 @@ impl ::core::cmp::Ord for Toto {
         let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
         assert_rust_eq!(
             output.to_string(),
-            r#"impl<'a, T> ::core::cmp::Eq for ContextWrapper<'a, T> where T: Ctx {}
+            r#"#[automatically_derived]
+impl<'a, T> ::core::cmp::Eq for ContextWrapper<'a, T> where T: Ctx {}
+#[automatically_derived]
 impl<'a, T> ::core::cmp::PartialEq<Self> for ContextWrapper<'a, T>
 where
     T: Ctx,
 {
     #[inline]
     fn eq(&self, other: &Self) -> bool {
-        self.cmp(other).is_eq()
+        self.elapsed == other.elapsed
     }
 }
+#[automatically_derived]
 impl<'a, T> ::core::cmp::PartialOrd<Self> for ContextWrapper<'a, T>
 where
     T: Ctx,
@@ -366,6 +5001,7 @@ where
         ::core::option::Option::Some(self.cmp(other))
     }
 }
+#[automatically_derived]
 impl<'a, T> ::core::cmp::Ord for ContextWrapper<'a, T>
 where
     T: Ctx,
@@ -375,6 +5011,231 @@ where
         self.elapsed.cmp(&other.elapsed)
     }
 }
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::cmp::Ord>() {}
+    #[allow(dead_code)]
+    fn assert_fields<'a, T>(_: &ContextWrapper<'a, T>)
+    where
+        T: Ctx,
+    {
+        assert_bound::<i32>();
+    }
+};
+"#
+        );
+    }
+
+    #[test]
+    fn test_generic_bounds() {
+        let input = syn::parse_quote! {
+            struct Wrapper<T, U, V: Copy = u8> {
+                #[cmp_by]
+                inner: T,
+                #[cmp_by]
+                items: Vec<V>,
+                #[cmp_by(key = "len")]
+                other: U,
+                ignored: U,
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl<T, U, V: Copy> ::core::cmp::Eq for Wrapper<T, U, V>
+where
+    T: ::core::cmp::Ord,
+    Vec<V>: ::core::cmp::Ord,
+{
+}
+#[automatically_derived]
+impl<T, U, V: Copy> ::core::cmp::PartialEq<Self> for Wrapper<T, U, V>
+where
+    T: ::core::cmp::Ord,
+    Vec<V>: ::core::cmp::Ord,
+{
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.inner == other.inner
+            && self.items == other.items
+            && len(&self.other) == len(&other.other)
+    }
+}
+#[automatically_derived]
+impl<T, U, V: Copy> ::core::cmp::PartialOrd<Self> for Wrapper<T, U, V>
+where
+    T: ::core::cmp::Ord,
+    Vec<V>: ::core::cmp::Ord,
+{
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl<T, U, V: Copy> ::core::cmp::Ord for Wrapper<T, U, V>
+where
+    T: ::core::cmp::Ord,
+    Vec<V>: ::core::cmp::Ord,
+{
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        self.inner
+            .cmp(&other.inner)
+            .then_with(|| self.items.cmp(&other.items))
+            .then_with(|| len(&self.other).cmp(&len(&other.other)))
+    }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_cow() {
+        let input = syn::parse_quote! {
+            struct Entry<'a, T: ?Sized + ToOwned> {
+                #[cmp_by]
+                name: Cow<'a, str>,
+                #[cmp_by]
+                value: Cow<'a, T>,
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl<'a, T: ?Sized + ToOwned> ::core::cmp::Eq for Entry<'a, T> where Cow<'a, T>: ::core::cmp::Ord {}
+#[automatically_derived]
+impl<'a, T: ?Sized + ToOwned> ::core::cmp::PartialEq<Self> for Entry<'a, T>
+where
+    Cow<'a, T>: ::core::cmp::Ord,
+{
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.name == other.name && self.value == other.value
+    }
+}
+#[automatically_derived]
+impl<'a, T: ?Sized + ToOwned> ::core::cmp::PartialOrd<Self> for Entry<'a, T>
+where
+    Cow<'a, T>: ::core::cmp::Ord,
+{
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl<'a, T: ?Sized + ToOwned> ::core::cmp::Ord for Entry<'a, T>
+where
+    Cow<'a, T>: ::core::cmp::Ord,
+{
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        self.name
+            .cmp(&other.name)
+            .then_with(|| self.value.cmp(&other.value))
+    }
+}
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::cmp::Ord>() {}
+    #[allow(dead_code)]
+    fn assert_fields<'a, T: ?Sized + ToOwned>(_: &Entry<'a, T>)
+    where
+        Cow<'a, T>: ::core::cmp::Ord,
+    {
+        assert_bound::<Cow<'a, str>>();
+    }
+};
+"#
+        );
+    }
+
+    #[test]
+    fn test_phantom_data() {
+        let input = syn::parse_quote! {
+            struct Id<T> {
+                #[cmp_by]
+                id: u32,
+                #[cmp_by]
+                _marker: ::core::marker::PhantomData<T>,
+            }
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl<T> ::core::cmp::Eq for Id<T> {}
+#[automatically_derived]
+impl<T> ::core::cmp::PartialEq<Self> for Id<T> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id && self._marker == other._marker
+    }
+}
+#[automatically_derived]
+impl<T> ::core::cmp::PartialOrd<Self> for Id<T> {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl<T> ::core::cmp::Ord for Id<T> {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        self.id
+            .cmp(&other.id)
+            .then_with(|| self._marker.cmp(&other._marker))
+    }
+}
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::cmp::Ord>() {}
+    #[allow(dead_code)]
+    fn assert_fields<T>(_: &Id<T>) {
+        assert_bound::<u32>();
+    }
+};
+"#
+        );
+    }
+
+    #[test]
+    fn test_unit_struct() {
+        let input = syn::parse_quote! {
+            #[cmp_by(rank())]
+            struct Marker;
+        };
+
+        let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Marker {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Self> for Marker {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.rank() == other.rank()
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<Self> for Marker {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Ord for Marker {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        self.rank().cmp(&other.rank())
+    }
+}
 "#
         );
     }
@@ -395,19 +5256,27 @@ where
         let output = crate::cmp_by::impl_cmp_by_derive(syn::parse2(input).unwrap());
         assert_rust_eq!(
             output.to_string(),
-            r#"impl ::core::cmp::Eq for Something {}
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Something {}
+#[automatically_derived]
 impl ::core::cmp::PartialEq<Self> for Something {
     #[inline]
     fn eq(&self, other: &Self) -> bool {
-        self.cmp(other).is_eq()
+        self.somemethod() == other.somemethod()
+            && self.literal == other.literal
+            && self.some.path == other.some.path
+            && self.0 == other.0
+            && self.1 == other.1
     }
 }
+#[automatically_derived]
 impl ::core::cmp::PartialOrd<Self> for Something {
     #[inline]
     fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
         ::core::option::Option::Some(self.cmp(other))
     }
 }
+#[automatically_derived]
 impl ::core::cmp::Ord for Something {
     #[inline]
     fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
@@ -415,10 +5284,17 @@ impl ::core::cmp::Ord for Something {
             .cmp(&other.somemethod())
             .then_with(|| self.literal.cmp(&other.literal))
             .then_with(|| self.some.path.cmp(&other.some.path))
-            .then_with(|| self.0.cmp(&other.0)
-            .then_with(|| self.1.cmp(&other.1)))
+            .then_with(|| self.0.cmp(&other.0).then_with(|| self.1.cmp(&other.1)))
     }
 }
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::cmp::Ord>() {}
+    #[allow(dead_code)]
+    fn assert_fields(_: &Something) {
+        assert_bound::<u16>();
+        assert_bound::<u32>();
+    }
+};
 "#
         );
     }
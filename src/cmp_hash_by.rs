@@ -0,0 +1,181 @@
+use crate::cmp_by::impl_cmp_by_derive;
+use crate::hash_by::gen_hash_impl;
+use crate::parsing::{parse_input, Key, ParsedInput};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{spanned::Spanned, DeriveInput, Error};
+
+pub fn impl_cmp_hash_by_derive(input: DeriveInput) -> TokenStream {
+    let input_span = input.span();
+    let struct_name = input.ident.clone();
+    let comparisons = impl_cmp_by_derive(input.clone());
+
+    // Invalid specs are reported by the comparisons.
+    let Ok(ParsedInput {
+        flags,
+        expressions,
+        fields,
+        generics,
+        ..
+    }) = parse_input(input, "cmp_by")
+    else {
+        return comparisons;
+    };
+
+    if let Err(err) = check_modifiers(expressions.iter().chain(fields.keys())) {
+        return err.into_compile_error();
+    }
+
+    let inline = match flags.inline() {
+        Ok(inline) => inline,
+        Err(err) => return err.into_compile_error(),
+    };
+    let hash = gen_hash_impl(
+        input_span,
+        &struct_name,
+        &flags,
+        &expressions,
+        &fields,
+        generics,
+        &inline,
+    )
+    .unwrap_or_else(Error::into_compile_error);
+
+    quote! {
+        #comparisons
+        #hash
+    }
+}
+
+/// Reject the items compared in a way that can consider values equal although they hash differently.
+fn check_modifiers<'a>(keys: impl Iterator<Item = &'a Key>) -> Result<(), Error> {
+    keys.flat_map(|Key { modifiers, .. }| {
+        [
+            modifiers.with.as_ref().map(|with| (with.span(), "with")),
+            modifiers
+                .collate_with
+                .as_ref()
+                .map(|collate_with| (collate_with.span(), "collate_with")),
+            modifiers.key.as_ref().map(|key| (key.span(), "key")),
+            modifiers.map.as_ref().map(|map| (map.span(), "map")),
+            modifiers
+                .comparator
+                .as_ref()
+                .map(|comparator| (comparator.span(), "use_comparator")),
+            modifiers
+                .both_some_only
+                .map(|span| (span, "both_some_only")),
+            modifiers
+                .on_err
+                .as_ref()
+                .map(|on_err| (on_err.span(), "on_err")),
+            modifiers
+                .epsilon
+                .as_ref()
+                .map(|epsilon| (epsilon.span(), "epsilon")),
+            modifiers
+                .assume_total
+                .as_ref()
+                .map(|assume_total| (assume_total.span(), "assume_total")),
+            modifiers.lex.as_ref().map(|lex| (lex.span(), "lex")),
+        ]
+    })
+    .flatten()
+    .map(|(span, name)| {
+        Error::new(
+            span,
+            format!("CmpHashBy: `{name}` can't be hashed consistently, values it compares equal could hash differently"),
+        )
+    })
+    .reduce(|mut acc, err| {
+        acc.combine(err);
+        acc
+    })
+    .map_or(Ok(()), Err)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::assert_rust_eq;
+
+    #[test]
+    fn test_struct() {
+        let input = syn::parse_quote! {
+            struct Tag {
+                #[cmp_by(case_insensitive, reverse)]
+                name: String,
+                #[cmp_by(total)]
+                weight: f64,
+                note: String,
+            }
+        };
+
+        let output = impl_cmp_hash_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Tag {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Self> for Tag {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.name.eq_ignore_ascii_case(&other.name) && self.weight.total_cmp(&other.weight).is_eq()
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::PartialOrd<Self> for Tag {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        ::core::option::Option::Some(self.cmp(other))
+    }
+}
+#[automatically_derived]
+impl ::core::cmp::Ord for Tag {
+    #[inline]
+    fn cmp(&self, other: &Self) -> ::core::cmp::Ordering {
+        other
+            .name
+            .bytes()
+            .map(|byte| byte.to_ascii_lowercase())
+            .cmp(self.name.bytes().map(|byte| byte.to_ascii_lowercase()))
+            .then_with(|| self.weight.total_cmp(&other.weight))
+    }
+}
+#[automatically_derived]
+impl ::core::hash::Hash for Tag {
+    #[inline]
+    fn hash<H: ::core::hash::Hasher>(&self, state: &mut H) {
+        {
+            self.name
+                .bytes()
+                .for_each(|byte| state.write_u8(byte.to_ascii_lowercase()));
+            state.write_u8(0xff)
+        };
+        self.weight.to_bits().hash(state);
+    }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_inconsistent() {
+        let input = syn::parse_quote! {
+            struct Tag {
+                #[cmp_by(with = "cmp_names")]
+                name: String,
+                #[cmp_by(epsilon = 0.1)]
+                weight: f64,
+            }
+        };
+
+        let output = impl_cmp_hash_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"::core::compile_error! { "CmpHashBy: `with` can't be hashed consistently, values it compares equal could hash differently" }
+::core::compile_error! { "CmpHashBy: `epsilon` can't be hashed consistently, values it compares equal could hash differently" }
+"#
+        );
+    }
+}
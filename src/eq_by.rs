@@ -0,0 +1,379 @@
+use crate::cmp_by::needs_bound;
+use crate::parsing::{
+    add_bounds, assert_bounds, parse_input, Flags, Key, OtherComparison, ParsedFields, ParsedInput,
+    ParsingError,
+};
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::{quote, quote_spanned, ToTokens};
+use syn::{parse_quote_spanned, spanned::Spanned, DeriveInput, Error, Expr, Generics};
+
+pub fn impl_eq_by_derive(input: DeriveInput) -> TokenStream {
+    let input_span = input.span();
+    let struct_name = input.ident.clone();
+
+    let ParsedInput {
+        flags,
+        expressions: sortable_expressions,
+        fields: sortable_fields,
+        others,
+        generics,
+    } = match parse_input(input, "eq_by") {
+        Ok(value) => value,
+        Err(err) => {
+            return match err {
+                ParsingError::Error(err) => err,
+                ParsingError::Union(span) => Error::new(span, "EqBy: unions are not supported"),
+                ParsingError::NoField(span) => Error::new(
+                    span,
+                    "EqBy: no field to compare on. Mark fields to compare on with #[eq_by]",
+                ),
+            }
+            .into_compile_error()
+        }
+    };
+
+    if let Err(err) = check_modifiers(&flags, &sortable_expressions, &sortable_fields) {
+        return err.into_compile_error();
+    }
+
+    let eq_expression = gen_eq_expression(input_span, &sortable_expressions, &sortable_fields);
+    let inline = match flags.inline() {
+        Ok(inline) => inline,
+        Err(err) => return err.into_compile_error(),
+    };
+    let other_partial_eq = gen_other_partial_eq(&struct_name, &generics, &others, &inline);
+
+    let bounded_keys = || sortable_fields.keys().filter(|key| needs_bound(key));
+    let mut partial_eq_generics = generics.clone();
+    // Fields are compared with `==`, so they don't need to implement `Eq` for `PartialEq`.
+    add_bounds(
+        &mut partial_eq_generics,
+        bounded_keys(),
+        quote!(::core::cmp::PartialEq),
+    );
+    let (impl_generics, ty_generics, where_clause) = partial_eq_generics.split_for_impl();
+    let mut eq_generics = generics;
+    add_bounds(&mut eq_generics, bounded_keys(), quote!(::core::cmp::Eq));
+    let (eq_impl_generics, _, eq_where_clause) = eq_generics.split_for_impl();
+    // `Eq` is implemented unconditionally for types that aren't bounded, so they must implement it.
+    let bound_assertions = assert_bounds(
+        &struct_name,
+        &eq_generics,
+        bounded_keys(),
+        quote!(::core::cmp::Eq),
+    );
+
+    quote_spanned! {input_span =>
+        #[automatically_derived]
+        impl #eq_impl_generics ::core::cmp::Eq for #struct_name #ty_generics #eq_where_clause {}
+
+        #[automatically_derived]
+        impl #impl_generics ::core::cmp::PartialEq<Self> for #struct_name #ty_generics #where_clause {
+            #inline
+            fn eq(&self, other: &Self) -> bool {
+                #eq_expression
+            }
+        }
+
+        #other_partial_eq
+        #bound_assertions
+    }
+}
+
+/// Generate the `PartialEq` impls comparing `Self` to other types.
+pub(crate) fn gen_other_partial_eq(
+    struct_name: &Ident,
+    generics: &Generics,
+    others: &[OtherComparison],
+    inline: &TokenStream,
+) -> TokenStream {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let impls = others.iter().map(|other| {
+        let OtherComparison { ty, .. } = other;
+        let value = other.value();
+        quote_spanned! {ty.span() =>
+            #[automatically_derived]
+            impl #impl_generics ::core::cmp::PartialEq<#ty> for #struct_name #ty_generics #where_clause {
+                #inline
+                fn eq(&self, other: &#ty) -> bool {
+                    #value.eq(other)
+                }
+            }
+        }
+    });
+    quote!(#(#impls)*)
+}
+
+/// Reject the flags and modifiers that only affect the ordering.
+fn check_modifiers(flags: &Flags, expressions: &[Key], fields: &ParsedFields) -> Result<(), Error> {
+    let descending = flags.descending.map(|span| (span, "descending"));
+    let modifiers = expressions
+        .iter()
+        .chain(fields.keys())
+        .flat_map(|Key { modifiers, .. }| {
+            [
+                modifiers.reverse.map(|span| (span, "reverse")),
+                modifiers.none_last.map(|span| (span, "none_last")),
+            ]
+        })
+        .flatten();
+    let hash_flags =
+        flags
+            .hash_by_only()
+            .chain(
+                expressions
+                    .iter()
+                    .chain(fields.keys())
+                    .flat_map(|key| key.modifiers.hash_by_only()),
+            )
+            .map(|(span, name)| Error::new(span, format!("EqBy: `{name}` only applies to HashBy")))
+            .chain(flags.cmp_by_only().map(|(span, name)| {
+                Error::new(span, format!("EqBy: `{name}` only applies to CmpBy"))
+            }));
+    let eq = expressions
+        .iter()
+        .chain(fields.keys())
+        .filter_map(|key| key.modifiers.eq)
+        .map(|span| {
+            Error::new(
+                span,
+                "EqBy: `eq` has no effect, every marked field is compared for equality",
+            )
+        });
+    descending
+        .into_iter()
+        .chain(modifiers)
+        .map(|(span, name)| Error::new(span, format!("EqBy: `{name}` has no effect on equality")))
+        .chain(eq)
+        .chain(hash_flags)
+        .reduce(|mut acc, err| {
+            acc.combine(err);
+            acc
+        })
+        .map_or(Ok(()), Err)
+}
+
+/// Generate the expression testing `self` and `other` for equality, field by field.
+///
+/// Modifiers that only affect the ordering, like `reverse`, are ignored.
+pub(crate) fn gen_eq_expression(
+    input_span: Span,
+    sortable_expressions: &[Key],
+    sortable_fields: &ParsedFields,
+) -> Expr {
+    let expr_eq_statements = sortable_expressions
+        .iter()
+        .filter(|key| key.expr.to_token_stream().to_string() != "_fields")
+        .map(gen_eq_expr);
+
+    let field_eq_statement = match sortable_fields {
+        ParsedFields::Struct(sortable_expr) => gen_eq_exprs(sortable_expr),
+        ParsedFields::Enum(sortable_variants) => {
+            let eq_statements = sortable_variants
+                .iter()
+                .filter(|variant| !variant.keys.is_empty())
+                .map(|variant| {
+                    let (this, other) = (variant.bind(&quote!(self)), variant.bind(&quote!(other)));
+                    let eq_pattern = quote_spanned! {variant.pattern.span() => (#this, #other)};
+                    let eq_statement = gen_eq_exprs(&variant.keys);
+                    quote! {#eq_pattern => #eq_statement}
+                })
+                .collect::<Vec<_>>();
+            // Values of different variants are never equal.
+            let fallback = (eq_statements.len() < sortable_variants.len()
+                || sortable_variants.len() > 1)
+                .then(|| {
+                    quote! {
+                        (this, other) => ::core::mem::discriminant(this) == ::core::mem::discriminant(other),
+                    }
+                });
+            Some(parse_quote_spanned! { input_span =>
+                match (self, other) {
+                    #(#eq_statements,)*
+                    #fallback
+                }
+            })
+        }
+    };
+
+    expr_eq_statements
+        .chain(field_eq_statement)
+        .reduce(|eq_expr, expr| {
+            parse_quote_spanned! {expr.span() =>
+                #eq_expr && #expr
+            }
+        })
+        .expect("Error of no fields to compare on should be handled in the parsing stage.")
+}
+
+fn gen_eq_exprs(sortable_expr: &[Key]) -> Option<Expr> {
+    sortable_expr
+        .iter()
+        .map(gen_eq_expr)
+        .reduce(|eq_expr, expr| {
+            parse_quote_spanned! {expr.span() =>
+                #eq_expr && #expr
+            }
+        })
+}
+
+fn gen_eq_expr(key: &Key) -> Expr {
+    let Key {
+        expr, modifiers, ..
+    } = key;
+    let (this, other) = (quote!(self), quote!(other));
+    let (this_ref, other_ref) = (key.reference(&this), key.reference(&other));
+    let (this_value, other_value) = (key.projected(&this), key.projected(&other));
+    let eq_expr = match (&modifiers.with, modifiers.total) {
+        (Some(with), _) => parse_quote_spanned! { expr.span() =>
+            #with(#this_ref, #other_ref).is_eq()
+        },
+        (None, Some(_)) => parse_quote_spanned! { expr.span() =>
+            #this_value.total_cmp(&#other_value).is_eq()
+        },
+        // Strings the collation function considers equal are equal, consistently with `CmpBy`.
+        (None, None) if modifiers.collate_with.is_some() => {
+            let collated = key.collated(&this, &other);
+            parse_quote_spanned! { expr.span() => #collated.is_eq() }
+        }
+        (None, None) if modifiers.comparator.is_some() => {
+            let comparator = &modifiers.comparator;
+            parse_quote_spanned! { expr.span() =>
+                (self.#comparator)(#this_ref, #other_ref).is_eq()
+            }
+        }
+        // Values within `epsilon` of each other are equal, consistently with `CmpBy`.
+        (None, None) if modifiers.epsilon.is_some() => {
+            let epsilon = &modifiers.epsilon;
+            let (this, other) = (key.copied(&this), key.copied(&other));
+            parse_quote_spanned! { expr.span() =>
+                ({
+                    let (this, other) = (#this, #other);
+                    (this - other <= #epsilon && other - this <= #epsilon) || this.total_cmp(&other).is_eq()
+                })
+            }
+        }
+        // Incomparable values panic or are equal, consistently with `CmpBy`.
+        (None, None) if modifiers.assume_total.is_some() => {
+            let assumed_total = key.assumed_total(&this, &other);
+            parse_quote_spanned! { expr.span() => #assumed_total.is_eq() }
+        }
+        // Errors are all equal, whatever their value.
+        // The `match` is parenthesized to be usable as an operand of `&&`.
+        (None, None) if modifiers.on_err.is_some() => parse_quote_spanned! { expr.span() =>
+            (match (&#this_value, &#other_value) {
+                (::core::result::Result::Ok(this), ::core::result::Result::Ok(other)) => this == other,
+                (this, other) => this.is_err() == other.is_err(),
+            })
+        },
+        // A prefix of the other side is equal to it, consistently with `CmpBy`.
+        (None, None) if modifiers.lex.is_some() => {
+            let pairs = key.lex_pairs(&this, &other);
+            parse_quote_spanned! { expr.span() =>
+                #pairs.all(|(this, other)| this == other)
+            }
+        }
+        (None, None) if modifiers.case_insensitive.is_some() => {
+            parse_quote_spanned! { expr.span() =>
+                #this_value.eq_ignore_ascii_case(&#other_value)
+            }
+        }
+        (None, None) => parse_quote_spanned! { expr.span() =>
+            #this_value == #other_value
+        },
+    };
+    if modifiers.both_some_only.is_some() {
+        let (this, other) = (key.value(&this), key.value(&other));
+        parse_quote_spanned! { expr.span() =>
+            (#this.is_none() || #other.is_none() || #eq_expr)
+        }
+    } else {
+        eq_expr
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::assert_rust_eq;
+
+    #[test]
+    fn test_struct() {
+        let input = syn::parse_quote! {
+            #[eq_by(id(), meta.rev)]
+            struct Document<T> {
+                #[eq_by]
+                path: String,
+                #[eq_by]
+                content: T,
+                #[eq_by(total)]
+                score: f32,
+                #[eq_by(key = "str::len")]
+                title: String,
+                cache: Vec<u8>,
+            }
+        };
+
+        let output = impl_eq_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl<T> ::core::cmp::Eq for Document<T> where T: ::core::cmp::Eq {}
+#[automatically_derived]
+impl<T> ::core::cmp::PartialEq<Self> for Document<T>
+where
+    T: ::core::cmp::PartialEq,
+{
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.id() == other.id()
+            && self.meta.rev == other.meta.rev
+            && self.path == other.path
+            && self.content == other.content
+            && self.score.total_cmp(&other.score).is_eq()
+            && str::len(&self.title) == str::len(&other.title)
+    }
+}
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::cmp::Eq>() {}
+    #[allow(dead_code)]
+    fn assert_fields<T>(_: &Document<T>)
+    where
+        T: ::core::cmp::Eq,
+    {
+        assert_bound::<String>();
+    }
+};
+"#
+        );
+    }
+
+    #[test]
+    fn test_enum() {
+        let input = syn::parse_quote! {
+            #[eq_by(name())]
+            enum Shape {
+                Circle(f32),
+                Square { side: f32 },
+            }
+        };
+
+        let output = impl_eq_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::Eq for Shape {}
+#[automatically_derived]
+impl ::core::cmp::PartialEq<Self> for Shape {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.name() == other.name()
+            && match (self, other) {
+                (this, other) => ::core::mem::discriminant(this) == ::core::mem::discriminant(other),
+            }
+    }
+}
+"#
+        );
+    }
+}
@@ -1,23 +1,41 @@
-use crate::parsing::{parse_input, ParsedFields, ParsedInput, ParsingError};
-use proc_macro2::{Literal, TokenStream};
-use quote::{quote, quote_spanned, ToTokens};
+use crate::parsing::{
+    add_bounds, assert_bounds, parse_input, Flags, Key, OtherComparison, ParsedFields, ParsedInput,
+    ParsingError,
+};
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::{format_ident, quote, quote_spanned, ToTokens};
 use syn::spanned::Spanned;
-use syn::{DeriveInput, Error};
+use syn::{
+    punctuated::Punctuated, Attribute, DeriveInput, Error, Generics, Lit, Path, Token, Type,
+    TypePath,
+};
 
 pub fn impl_hash_by_derive(input: DeriveInput) -> TokenStream {
     let input_span = input.span();
     let struct_name = input.ident.clone();
+    let compared_input = input.clone();
+
+    // Both would implement `Hash`, which rustc reports as conflicting implementations at the type.
+    if let Some(hash) = derived_hash(&input.attrs) {
+        return Error::new_spanned(
+            hash,
+            "HashBy: `derive(Hash)` can't be combined with HashBy, both implement `Hash`",
+        )
+        .into_compile_error();
+    }
 
     let ParsedInput {
+        flags,
         expressions: sortable_expressions,
         fields: sortable_fields,
+        others,
         generics,
-        generic_arguments: generics_params,
     } = match parse_input(input, "hash_by") {
         Ok(value) => value,
         Err(err) => {
             return match err {
                 ParsingError::Error(err) => err,
+                ParsingError::Union(span) => Error::new(span, "HashBy: unions are not supported"),
                 ParsingError::NoField(span) => Error::new(
                     span,
                     "HashBy: no field to compare on. Mark fields to compare on with #[hash_by]",
@@ -27,24 +45,93 @@ pub fn impl_hash_by_derive(input: DeriveInput) -> TokenStream {
         }
     };
 
-    let expr_hash_statements = {
-        let mut hash_exprs = sortable_expressions
-            .iter()
-            .map(|expr| quote_spanned!(expr.span() => self.#expr.hash(state)))
-            .peekable();
-        if hash_exprs.peek().is_some() {
-            Some(quote!(#(#hash_exprs);*;))
-        } else {
-            None
+    if let Some(OtherComparison { ty, borrow, .. }) = others.first() {
+        let name = if *borrow { "borrow_key" } else { "other" };
+        return Error::new(
+            ty.span(),
+            format!("HashBy: `{name}` has no effect on hashing"),
+        )
+        .into_compile_error();
+    }
+
+    if let Some(floats) = &flags.floats {
+        return Error::new(floats.span(), "HashBy: `floats` has no effect on hashing")
+            .into_compile_error();
+    }
+
+    let inline = match flags.inline() {
+        Ok(inline) => inline,
+        Err(err) => return err.into_compile_error(),
+    };
+
+    if let Err(err) = check_modifiers(&flags, &sortable_expressions, &sortable_fields) {
+        return err.into_compile_error();
+    }
+
+    if let Some(verify_eq) = flags.verify_eq {
+        if let Err(err) = check_eq_consistency(
+            verify_eq,
+            compared_input,
+            &sortable_expressions,
+            &sortable_fields,
+        ) {
+            return err.into_compile_error();
+        }
+    }
+
+    gen_hash_impl(
+        input_span,
+        &struct_name,
+        &flags,
+        &sortable_expressions,
+        &sortable_fields,
+        generics,
+        &inline,
+    )
+    .unwrap_or_else(Error::into_compile_error)
+}
+
+/// Generate the `Hash` impl hashing `expressions` and `fields`, in the order they are compared.
+pub(crate) fn gen_hash_impl(
+    input_span: Span,
+    struct_name: &Ident,
+    flags: &Flags,
+    sortable_expressions: &[Key],
+    sortable_fields: &ParsedFields,
+    mut generics: Generics,
+    inline: &TokenStream,
+) -> Result<TokenStream, Error> {
+    // Written first, so that values of types with different tags hash differently.
+    let tag_statement = match &flags.tag {
+        None => None,
+        Some(Lit::Int(tag)) => Some(quote_spanned!(tag.span() => state.write_u64(#tag);)),
+        Some(Lit::Str(tag)) => Some(quote_spanned!(tag.span() => state.write(#tag.as_bytes());)),
+        Some(tag) => {
+            return Err(Error::new(
+                tag.span(),
+                "HashBy: expected an integer or string `tag`",
+            ));
+        }
+    };
+
+    // Different variants hash differently even when their fields coincide, like `#[derive(Hash)]` does.
+    let discriminant_hash_statement = match (&sortable_fields, flags.no_discriminant) {
+        (ParsedFields::Enum(_), None) => {
+            Some(quote_spanned!(input_span => ::core::mem::discriminant(self).hash(state);))
+        }
+        (ParsedFields::Enum(_), Some(_)) => None,
+        (ParsedFields::Struct(_), None) => None,
+        (ParsedFields::Struct(_), Some(no_discriminant)) => {
+            return Err(Error::new(
+                no_discriminant,
+                "HashBy: `no_discriminant` only applies to enums",
+            ));
         }
     };
 
     let field_hash_expressions = match &sortable_fields {
         ParsedFields::Struct(sortable_expr) => {
-            let mut hash_exprs = sortable_expr
-                .iter()
-                .map(|expr| quote_spanned!(expr.span() => self.#expr.hash(state)))
-                .peekable();
+            let mut hash_exprs = sortable_expr.iter().map(gen_hash_stmt).peekable();
             if hash_exprs.peek().is_some() {
                 Some(quote! { #(#hash_exprs);*; })
             } else {
@@ -54,27 +141,27 @@ pub fn impl_hash_by_derive(input: DeriveInput) -> TokenStream {
         ParsedFields::Enum(sortable_variants) => {
             let mut hash_statements = sortable_variants
                 .iter()
-                .enumerate()
-                .filter(|(_, (_, sortable_expr))| !sortable_expr.is_empty())
-                .map(|(i, (variant, sortable_expr))| {
-                    let hash_pattern = quote_spanned! {variant.span() => this @ #variant};
-                    let variant_num = Literal::usize_unsuffixed(i).to_token_stream();
-                    let variant_hash_statement = quote! {state.write_u8(#variant_num)};
-                    let hash_statement = sortable_expr
-                        .iter()
-                        .map(|expr| quote_spanned!(expr.span() => self.#expr.hash(state)));
+                .filter(|variant| !variant.keys.is_empty())
+                .map(|variant| {
+                    let hash_pattern = variant.bind(&quote!(self));
+                    let hash_statement = variant.keys.iter().map(gen_hash_stmt);
                     quote! {
                         #hash_pattern => {
-                            #variant_hash_statement;
                             #(#hash_statement);*
                         }
                     }
                 })
                 .peekable();
+            // Variants without hashed fields don't hash anything.
+            let fallback = sortable_variants
+                .iter()
+                .any(|variant| variant.keys.is_empty())
+                .then(|| quote!(_ => {}));
             if hash_statements.peek().is_some() {
                 Some(quote! {
                     match self {
-                        #(#hash_statements),*
+                        #(#hash_statements,)*
+                        #fallback
                     }
                 })
             } else {
@@ -83,26 +170,330 @@ pub fn impl_hash_by_derive(input: DeriveInput) -> TokenStream {
         }
     };
 
-    let hash_expr = match (expr_hash_statements, field_hash_expressions) {
-        (Some(exprs), Some(fields)) => {
-            quote! {
-                #exprs
-                #fields
-            }
+    // The fields are hashed in place of `_fields`, or after the top-level items.
+    let is_fields = |key: &Key| key.expr.to_token_stream().to_string() == "_fields";
+    let expr_hash_statements = sortable_expressions.iter().map(|key| {
+        if is_fields(key) {
+            quote!(#field_hash_expressions)
+        } else {
+            let stmt = gen_hash_stmt(key);
+            quote!(#stmt;)
         }
-        (Some(stmts), None) | (None, Some(stmts)) => stmts,
-        (None, None) => unreachable!("Missing fields to hash by should have errored earlier."),
+    });
+    let trailing_field_hash_expressions =
+        (!sortable_expressions.iter().any(is_fields)).then_some(&field_hash_expressions);
+    let hash_expr = quote! {
+        #(#expr_hash_statements)*
+        #trailing_field_hash_expressions
     };
 
-    let where_clause = &generics.where_clause;
+    let bounded_keys = || {
+        sortable_fields.keys().filter(|key| {
+            key.modifiers.case_insensitive.is_none()
+                && key.modifiers.cache.is_none()
+                && key.modifiers.with.is_none()
+                && key.modifiers.by_len.is_none()
+                && key.modifiers.by_display.is_none()
+                && key.modifiers.cast.is_none()
+                && key.modifiers.abs.is_none()
+                && key.modifiers.ordered_float.is_none()
+                && key.modifiers.bytes.is_none()
+                && key.modifiers.as_sorted.is_none()
+                && key.modifiers.as_pairs.is_none()
+                && key.modifiers.total.is_none()
+                && key.modifiers.index.is_none()
+        })
+    };
+    // Only plain old data can be hashed as bytes, which `bytemuck` asserts with `Pod`.
+    let bytes_keys = || {
+        sortable_fields
+            .keys()
+            .filter(|key| key.modifiers.bytes.is_some() && key.modifiers.index.is_none())
+    };
+    add_bounds(&mut generics, bounded_keys(), quote!(::core::hash::Hash));
+    add_bounds(&mut generics, bytes_keys(), quote!(::bytemuck::Pod));
+    let bound_assertions = [
+        assert_bounds(
+            struct_name,
+            &generics,
+            bounded_keys(),
+            quote!(::core::hash::Hash),
+        ),
+        assert_bounds(
+            struct_name,
+            &generics,
+            bytes_keys(),
+            quote!(::bytemuck::Pod),
+        ),
+    ];
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
 
-    quote_spanned! {input_span =>
-        impl #generics ::core::hash::Hash for #struct_name <#(#generics_params),*> #where_clause {
+    Ok(quote_spanned! {input_span =>
+        #[automatically_derived]
+        impl #impl_generics ::core::hash::Hash for #struct_name #ty_generics #where_clause {
+            #inline
             fn hash<H: ::core::hash::Hasher>(&self, state: &mut H) {
+                #tag_statement
+                #discriminant_hash_statement
                 #hash_expr
             }
         }
 
+        #(#bound_assertions)*
+    })
+}
+
+fn gen_hash_stmt(key: &Key) -> TokenStream {
+    let value = key.value(&quote!(self));
+    if key.modifiers.case_insensitive.is_some() {
+        // Hash the bytes folded to lowercase, terminated like `str` does.
+        quote_spanned! {key.expr.span() =>
+            {
+                #value.bytes().for_each(|byte| state.write_u8(byte.to_ascii_lowercase()));
+                state.write_u8(0xff)
+            }
+        }
+    } else if key.modifiers.total.is_some() {
+        // `total_cmp` only considers floats with the same bits equal, which `CmpHashBy` compares them with.
+        quote_spanned!(key.expr.span() => #value.to_bits().hash(state))
+    } else if let Some(with) = &key.modifiers.with {
+        let reference = key.reference(&quote!(self));
+        quote_spanned!(key.expr.span() => #with(#reference).hash(state))
+    } else if key.modifiers.as_sorted.is_some() || key.modifiers.as_pairs.is_some() {
+        let projected = key.projected(&quote!(self));
+        quote_spanned!(key.expr.span() => #projected.hash(state))
+    } else if key.modifiers.bytes.is_some() {
+        let reference = key.reference(&quote!(self));
+        quote_spanned!(key.expr.span() => state.write(::bytemuck::bytes_of(#reference)))
+    } else if let Some(write) = key
+        .modifiers
+        .raw
+        .and(key.modifiers.cast.as_ref().or(key.ty.as_ref()))
+        .and_then(integer_write)
+    {
+        // This is what `Hash` does for integers, without going through it.
+        let value = key.copied(&quote!(self));
+        quote_spanned!(key.expr.span() => state.#write(#value))
+    } else {
+        quote_spanned!(key.expr.span() => #value.hash(state))
+    }
+}
+
+/// The `Hash` derived by a `#[derive]` attribute of `attrs`.
+///
+/// A derive only receives the attributes following its own `#[derive]`, so `Hash` derived in the same attribute as
+/// `HashBy`, or in a previous one, isn't found.
+fn derived_hash(attrs: &[Attribute]) -> Option<Path> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("derive"))
+        .filter_map(|attr| {
+            attr.parse_args_with(Punctuated::<Path, Token![,]>::parse_terminated)
+                .ok()
+        })
+        .flatten()
+        .find(|path| {
+            path.segments
+                .last()
+                .is_some_and(|segment| segment.ident == "Hash")
+        })
+}
+
+/// The `Hasher` method writing integers of type `ty`, e.g. `write_u32` for `u32`, `None` for other types.
+fn integer_write(ty: &Type) -> Option<Ident> {
+    const INTEGERS: [&str; 12] = [
+        "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize",
+    ];
+    let Type::Path(TypePath { qself: None, path }) = ty else {
+        return None;
+    };
+    let ident = path.get_ident()?;
+    INTEGERS
+        .contains(&ident.to_string().as_str())
+        .then(|| format_ident!("write_{}", ident))
+}
+
+/// Reject the flags and modifiers that only make sense for comparisons.
+fn check_modifiers(flags: &Flags, expressions: &[Key], fields: &ParsedFields) -> Result<(), Error> {
+    let descending = flags
+        .descending
+        .map(|span| (span, "descending"))
+        .into_iter()
+        .chain(flags.cmp_by_only());
+    let modifiers = expressions
+        .iter()
+        .chain(fields.keys())
+        .flat_map(|Key { modifiers, .. }| {
+            [
+                modifiers.reverse.map(|span| (span, "reverse")),
+                modifiers.none_last.map(|span| (span, "none_last")),
+                modifiers
+                    .both_some_only
+                    .map(|span| (span, "both_some_only")),
+                modifiers.eq.map(|span| (span, "eq")),
+                modifiers
+                    .on_err
+                    .as_ref()
+                    .map(|on_err| (on_err.span(), "on_err")),
+                modifiers.total.map(|span| (span, "total")),
+                modifiers
+                    .assume_total
+                    .as_ref()
+                    .map(|assume_total| (assume_total.span(), "assume_total")),
+                modifiers
+                    .comparator
+                    .as_ref()
+                    .map(|comparator| (comparator.span(), "use_comparator")),
+                modifiers.key.as_ref().map(|key| (key.span(), "key")),
+                modifiers.map.as_ref().map(|map| (map.span(), "map")),
+                modifiers.lex.as_ref().map(|lex| (lex.span(), "lex")),
+                modifiers
+                    .collate_with
+                    .as_ref()
+                    .map(|collate_with| (collate_with.span(), "collate_with")),
+            ]
+        })
+        .flatten();
+    let epsilon = expressions
+        .iter()
+        .chain(fields.keys())
+        .filter_map(|key| key.modifiers.epsilon.as_ref())
+        .map(|epsilon| {
+            Error::new(
+                epsilon.span(),
+                "HashBy: `epsilon` can't be hashed consistently, values within epsilon of each other are equal but hash differently",
+            )
+        });
+    descending
+        .chain(modifiers)
+        .map(|(span, name)| Error::new(span, format!("HashBy: `{name}` has no effect on hashing")))
+        .chain(epsilon)
+        .reduce(|mut acc, err| {
+            acc.combine(err);
+            acc
+        })
+        .map_or(Ok(()), Err)
+}
+
+/// Check that every hashed item is also compared for equality by `CmpBy` or `EqBy`.
+///
+/// Values that are equal must have the same hash, so hashing an item that isn't compared,
+/// or that is compared through `with` or `key`, would break `HashMap` and `HashSet`.
+fn check_eq_consistency(
+    verify_eq: Span,
+    input: DeriveInput,
+    expressions: &[Key],
+    fields: &ParsedFields,
+) -> Result<(), Error> {
+    let Some(compared) = ["cmp_by", "eq_by"].into_iter().find_map(|attr| {
+        parse_input(input.clone(), attr).ok().filter(|compared| {
+            compared.fields.keys().next().is_some() || !compared.expressions.is_empty()
+        })
+    }) else {
+        return Err(Error::new(
+            verify_eq,
+            "HashBy: `verify_eq` found no item compared for equality, mark them with #[cmp_by] or #[eq_by]",
+        ));
+    };
+    let compared = labelled_keys(&compared.expressions, &compared.fields);
+    let cast = |key: &Key| {
+        key.modifiers
+            .cast
+            .as_ref()
+            .map(|ty| ty.to_token_stream().to_string())
+    };
+
+    labelled_keys(expressions, fields)
+        .into_iter()
+        .filter_map(|(label, key)| {
+            let name = key.expr.to_token_stream().to_string();
+            match compared.iter().find(|(compared_label, _)| *compared_label == label) {
+                None => Some(Error::new(
+                    key.expr.span(),
+                    format!("HashBy: `{name}` is hashed but not compared for equality, so equal values could hash differently"),
+                )),
+                Some((_, compared_key)) => {
+                    let modifier = match (&compared_key.modifiers.with, &compared_key.modifiers.key) {
+                        (Some(_), _) => "with",
+                        (None, Some(_)) => "key",
+                        (None, None) if compared_key.modifiers.map.is_some() => "map",
+                        (None, None) if compared_key.modifiers.comparator.is_some() => "use_comparator",
+                        // `None` is equal to every value, which can't be hashed consistently.
+                        (None, None) if compared_key.modifiers.both_some_only.is_some() => "both_some_only",
+                        // Equal strings ignoring case must be hashed ignoring case as well.
+                        (None, None) if compared_key.modifiers.case_insensitive.is_some()
+                            && key.modifiers.case_insensitive.is_none() => "case_insensitive",
+                        // Incomparable values are equal to every value.
+                        (None, None) if compared_key.modifiers.assume_total.is_some() => "assume_total",
+                        // Values with equal elements must only hash those elements.
+                        (None, None) if compared_key.modifiers.index.is_some()
+                            && key.modifiers.index.is_none() => "index",
+                        // `OrderedFloat` is equal for `0.0` and `-0.0` and for every NaN, which have different bytes.
+                        (None, None) if compared_key.modifiers.ordered_float.is_some()
+                            && key.modifiers.bytes.is_some() => "ordered_float",
+                        // Errors are all equal, so their value can't be hashed.
+                        (None, None) if compared_key.modifiers.on_err.is_some() => "on_err",
+                        // Values within epsilon of each other are equal, whatever their bits.
+                        (None, None) if compared_key.modifiers.epsilon.is_some() => "epsilon",
+                        // Values are equal to their prefixes, whatever their other items.
+                        (None, None) if compared_key.modifiers.lex.is_some() => "lex",
+                        // Collators can consider different strings equal.
+                        (None, None) if compared_key.modifiers.collate_with.is_some() => "collate_with",
+                        // Collections with the same items in another order must hash the same.
+                        (None, None) if compared_key.modifiers.as_sorted.is_some()
+                            && key.modifiers.as_sorted.is_none() => "as_sorted",
+                        // Values with the same magnitude must be hashed by their magnitude as well.
+                        (None, None) if compared_key.modifiers.abs.is_some()
+                            && key.modifiers.abs.is_none() => "abs",
+                        // Values rendered the same must be hashed rendered as well.
+                        (None, None) if compared_key.modifiers.by_display.is_some()
+                            && key.modifiers.by_display.is_none() => "by_display",
+                        // Values converting to the same value must be hashed converted as well.
+                        (None, None) if compared_key.modifiers.cast.is_some()
+                            && cast(key) != cast(compared_key) => "as",
+                        // Equal lengths must be hashed as lengths as well.
+                        (None, None) if compared_key.modifiers.by_len.is_some()
+                            && key.modifiers.by_len.is_none() => "by_len",
+                        (None, None) => return None,
+                    };
+                    Some(Error::new(
+                        key.expr.span(),
+                        format!("HashBy: `{name}` is compared with `{modifier}`, so equal values could hash differently"),
+                    ))
+                }
+            }
+        })
+        .reduce(|mut acc, err| {
+            acc.combine(err);
+            acc
+        })
+        .map_or(Ok(()), Err)
+}
+
+/// Label the items with what they access, so that the same item can be found in another derive.
+///
+/// `_fields` only marks where the fields are used, so it isn't labelled.
+///
+/// Top-level items and struct fields both access `self`, while variant fields are only compared within their variant.
+fn labelled_keys<'a>(expressions: &'a [Key], fields: &'a ParsedFields) -> Vec<(String, &'a Key)> {
+    let label = |prefix: &str, key: &Key| format!("{prefix} {}", key.expr.to_token_stream());
+    let expressions = expressions
+        .iter()
+        .filter(|key| key.expr.to_token_stream().to_string() != "_fields")
+        .map(|key| (label("self", key), key));
+    match fields {
+        ParsedFields::Struct(keys) => expressions
+            .chain(keys.iter().map(|key| (label("self", key), key)))
+            .collect(),
+        ParsedFields::Enum(variants) => expressions
+            .chain(variants.iter().flat_map(|variant| {
+                let pattern = variant.pattern.to_string();
+                variant
+                    .keys
+                    .iter()
+                    .map(move |key| (label(&pattern, key), key))
+            }))
+            .collect(),
     }
 }
 
@@ -128,13 +519,23 @@ mod test {
         let output = impl_hash_by_derive(syn::parse2(input).unwrap());
         assert_rust_eq!(
             output.to_string(),
-            r#"impl ::core::hash::Hash for Toto {
+            r#"#[automatically_derived]
+impl ::core::hash::Hash for Toto {
+    #[inline]
     fn hash<H: ::core::hash::Hasher>(&self, state: &mut H) {
         self.embed.otherfield.hash(state);
         self.a.hash(state);
         self.c.hash(state);
     }
 }
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::hash::Hash>() {}
+    #[allow(dead_code)]
+    fn assert_fields(_: &Toto) {
+        assert_bound::<u16>();
+        assert_bound::<u32>();
+    }
+};
 "#
         );
     }
@@ -153,20 +554,466 @@ mod test {
         let output = impl_hash_by_derive(syn::parse2(input).unwrap());
         assert_rust_eq!(
             output.to_string(),
-            r#"impl ::core::hash::Hash for Toto {
+            r#"#[automatically_derived]
+impl ::core::hash::Hash for Toto {
+    #[inline]
     fn hash<H: ::core::hash::Hasher>(&self, state: &mut H) {
+        ::core::mem::discriminant(self).hash(state);
         self.this.hash(state);
         self.this.that.hash(state);
         self.get_something().hash(state);
         self.something.do_this().hash(state);
         match self {
-            this @ Self::A(..) => {
-                state.write_u8(0);
-                self.0.hash(state)
+            Self::A { 0: self_0, .. } => self_0.hash(state),
+            _ => {}
+        }
+    }
+}
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::hash::Hash>() {}
+    #[allow(dead_code)]
+    fn assert_fields(_: &Toto) {
+        assert_bound::<u32>();
+    }
+};
+"#
+        );
+    }
+
+    #[test]
+    fn test_no_discriminant() {
+        let input = syn::parse_quote! {
+            #[hash_by(no_discriminant)]
+            enum Shape {
+                Circle(#[hash_by] u32),
+                Square(#[hash_by] u32),
             }
+        };
+
+        let output = impl_hash_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::hash::Hash for Shape {
+    #[inline]
+    fn hash<H: ::core::hash::Hasher>(&self, state: &mut H) {
+        match self {
+            Self::Circle { 0: self_0, .. } => self_0.hash(state),
+            Self::Square { 0: self_0, .. } => self_0.hash(state),
         }
     }
 }
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::hash::Hash>() {}
+    #[allow(dead_code)]
+    fn assert_fields(_: &Shape) {
+        assert_bound::<u32>();
+    }
+};
+"#
+        );
+    }
+
+    #[cfg(feature = "ordered-float")]
+    #[test]
+    fn test_ordered_float() {
+        let input = syn::parse_quote! {
+            enum Shape {
+                Circle(#[hash_by(ordered_float)] f64),
+                Point { #[hash_by(ordered_float)] x: f32 },
+            }
+        };
+
+        let output = impl_hash_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::hash::Hash for Shape {
+    #[inline]
+    fn hash<H: ::core::hash::Hasher>(&self, state: &mut H) {
+        ::core::mem::discriminant(self).hash(state);
+        match self {
+            Self::Circle { 0: self_0, .. } => ::ordered_float::OrderedFloat(*self_0).hash(state),
+            Self::Point { x: self_x, .. } => ::ordered_float::OrderedFloat(*self_x).hash(state),
+        }
+    }
+}
+"#
+        );
+    }
+
+    #[cfg(feature = "bytemuck")]
+    #[test]
+    fn test_bytes() {
+        let input = syn::parse_quote! {
+            struct Packet<T> {
+                #[hash_by(bytes)]
+                header: [u8; 16],
+                #[hash_by(bytes)]
+                payload: T,
+                #[hash_by]
+                id: u32,
+            }
+        };
+
+        let output = impl_hash_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl<T> ::core::hash::Hash for Packet<T>
+where
+    T: ::bytemuck::Pod,
+{
+    #[inline]
+    fn hash<H: ::core::hash::Hasher>(&self, state: &mut H) {
+        state.write(::bytemuck::bytes_of(&self.header));
+        state.write(::bytemuck::bytes_of(&self.payload));
+        self.id.hash(state);
+    }
+}
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::hash::Hash>() {}
+    #[allow(dead_code)]
+    fn assert_fields<T>(_: &Packet<T>)
+    where
+        T: ::bytemuck::Pod,
+    {
+        assert_bound::<u32>();
+    }
+};
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::bytemuck::Pod>() {}
+    #[allow(dead_code)]
+    fn assert_fields<T>(_: &Packet<T>)
+    where
+        T: ::bytemuck::Pod,
+    {
+        assert_bound::<[u8; 16]>();
+    }
+};
+"#
+        );
+    }
+
+    #[cfg(feature = "indexmap")]
+    #[test]
+    fn test_as_pairs() {
+        let input = syn::parse_quote! {
+            struct Headers {
+                #[hash_by(as_pairs)]
+                entries: IndexMap<String, String>,
+            }
+        };
+
+        let output = impl_hash_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::hash::Hash for Headers {
+    #[inline]
+    fn hash<H: ::core::hash::Hasher>(&self, state: &mut H) {
+        ::indexmap::IndexMap::as_slice(&self.entries).hash(state);
+    }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_raw() {
+        let input = syn::parse_quote! {
+            enum Pixel {
+                Gray(#[hash_by(raw)] u8),
+                Point {
+                    #[hash_by(raw)]
+                    x: i64,
+                    #[hash_by(raw)]
+                    y: usize,
+                    #[hash_by(raw)]
+                    label: String,
+                },
+            }
+        };
+
+        let output = impl_hash_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::hash::Hash for Pixel {
+    #[inline]
+    fn hash<H: ::core::hash::Hasher>(&self, state: &mut H) {
+        ::core::mem::discriminant(self).hash(state);
+        match self {
+            Self::Gray { 0: self_0, .. } => state.write_u8(*self_0),
+            Self::Point {
+                x: self_x,
+                y: self_y,
+                label: self_label,
+                ..
+            } => {
+                state.write_i64(*self_x);
+                state.write_usize(*self_y);
+                self_label.hash(state)
+            }
+        }
+    }
+}
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::hash::Hash>() {}
+    #[allow(dead_code)]
+    fn assert_fields(_: &Pixel) {
+        assert_bound::<u8>();
+        assert_bound::<i64>();
+        assert_bound::<usize>();
+        assert_bound::<String>();
+    }
+};
+"#
+        );
+    }
+
+    #[test]
+    fn test_with() {
+        let input = syn::parse_quote! {
+            enum Reading {
+                Celsius(#[hash_by(with = "celsius_bits")] f64),
+                Raw { #[hash_by(with = "u16_swapped")] value: u16 },
+            }
+        };
+
+        let output = impl_hash_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::hash::Hash for Reading {
+    #[inline]
+    fn hash<H: ::core::hash::Hasher>(&self, state: &mut H) {
+        ::core::mem::discriminant(self).hash(state);
+        match self {
+            Self::Celsius { 0: self_0, .. } => celsius_bits(self_0).hash(state),
+            Self::Raw {
+                value: self_value, ..
+            } => u16_swapped(self_value).hash(state),
+        }
+    }
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_case_insensitive() {
+        let input = syn::parse_quote! {
+            struct User {
+                #[hash_by(case_insensitive)]
+                name: String,
+                #[hash_by]
+                id: u32,
+            }
+        };
+
+        let output = impl_hash_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::hash::Hash for User {
+    #[inline]
+    fn hash<H: ::core::hash::Hasher>(&self, state: &mut H) {
+        {
+            self.name
+                .bytes()
+                .for_each(|byte| state.write_u8(byte.to_ascii_lowercase()));
+            state.write_u8(0xff)
+        };
+        self.id.hash(state);
+    }
+}
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::hash::Hash>() {}
+    #[allow(dead_code)]
+    fn assert_fields(_: &User) {
+        assert_bound::<u32>();
+    }
+};
+"#
+        );
+    }
+
+    #[test]
+    fn test_verify_eq() {
+        let input = syn::parse_quote! {
+            #[cmp_by(kind())]
+            #[hash_by(verify_eq, kind())]
+            struct Toto {
+                #[cmp_by]
+                #[hash_by]
+                a: u16,
+                #[cmp_by]
+                b: u32,
+                c: f32,
+            }
+        };
+
+        let output = impl_hash_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::hash::Hash for Toto {
+    #[inline]
+    fn hash<H: ::core::hash::Hasher>(&self, state: &mut H) {
+        self.kind().hash(state);
+        self.a.hash(state);
+    }
+}
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::hash::Hash>() {}
+    #[allow(dead_code)]
+    fn assert_fields(_: &Toto) {
+        assert_bound::<u16>();
+    }
+};
+"#
+        );
+    }
+
+    #[test]
+    fn test_tag() {
+        let input = syn::parse_quote! {
+            #[hash_by(tag = 0xAB)]
+            enum Toto {
+                A(#[hash_by] u32),
+                B,
+            }
+        };
+
+        let output = impl_hash_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::hash::Hash for Toto {
+    #[inline]
+    fn hash<H: ::core::hash::Hasher>(&self, state: &mut H) {
+        state.write_u64(0xAB);
+        ::core::mem::discriminant(self).hash(state);
+        match self {
+            Self::A { 0: self_0, .. } => self_0.hash(state),
+            _ => {}
+        }
+    }
+}
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::hash::Hash>() {}
+    #[allow(dead_code)]
+    fn assert_fields(_: &Toto) {
+        assert_bound::<u32>();
+    }
+};
+"#
+        );
+    }
+
+    #[test]
+    fn test_str_tag() {
+        let input = syn::parse_quote! {
+            #[hash_by(tag = "Toto")]
+            struct Toto {
+                #[hash_by]
+                a: u16,
+            }
+        };
+
+        let output = impl_hash_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::hash::Hash for Toto {
+    #[inline]
+    fn hash<H: ::core::hash::Hasher>(&self, state: &mut H) {
+        state.write("Toto".as_bytes());
+        self.a.hash(state);
+    }
+}
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::hash::Hash>() {}
+    #[allow(dead_code)]
+    fn assert_fields(_: &Toto) {
+        assert_bound::<u16>();
+    }
+};
+"#
+        );
+    }
+
+    #[test]
+    fn test_fields_position() {
+        let input = syn::parse_quote! {
+            #[cmp_by(kind(), _fields)]
+            #[hash_by(verify_eq, _fields, kind())]
+            struct Toto {
+                #[cmp_by]
+                #[hash_by]
+                a: u16,
+                #[cmp_by]
+                #[hash_by]
+                b: u32,
+            }
+        };
+
+        let output = impl_hash_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::hash::Hash for Toto {
+    #[inline]
+    fn hash<H: ::core::hash::Hasher>(&self, state: &mut H) {
+        self.a.hash(state);
+        self.b.hash(state);
+        self.kind().hash(state);
+    }
+}
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::hash::Hash>() {}
+    #[allow(dead_code)]
+    fn assert_fields(_: &Toto) {
+        assert_bound::<u16>();
+        assert_bound::<u32>();
+    }
+};
+"#
+        );
+    }
+
+    #[test]
+    fn test_multiple_attributes() {
+        let input = syn::parse_quote! {
+            #[hash_by(a())]
+            #[hash_by(b(), c)]
+            struct Toto {
+                #[hash_by]
+                d: u16,
+            }
+        };
+
+        let output = impl_hash_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::hash::Hash for Toto {
+    #[inline]
+    fn hash<H: ::core::hash::Hasher>(&self, state: &mut H) {
+        self.a().hash(state);
+        self.b().hash(state);
+        self.c.hash(state);
+        self.d.hash(state);
+    }
+}
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::hash::Hash>() {}
+    #[allow(dead_code)]
+    fn assert_fields(_: &Toto) {
+        assert_bound::<u16>();
+    }
+};
 "#
         );
     }
@@ -185,8 +1032,11 @@ mod test {
         let output = impl_hash_by_derive(syn::parse2(input).unwrap());
         assert_rust_eq!(
             output.to_string(),
-            r#"impl ::core::hash::Hash for Toto {
+            r#"#[automatically_derived]
+impl ::core::hash::Hash for Toto {
+    #[inline]
     fn hash<H: ::core::hash::Hasher>(&self, state: &mut H) {
+        ::core::mem::discriminant(self).hash(state);
         self.get_something().hash(state);
     }
 }
@@ -210,14 +1060,105 @@ mod test {
         let output = impl_hash_by_derive(syn::parse2(input).unwrap());
         assert_rust_eq!(
             output.to_string(),
-            r#"impl<'a, T> ::core::hash::Hash for ContextWrapper<'a, T>
+            r#"#[automatically_derived]
+impl<'a, T> ::core::hash::Hash for ContextWrapper<'a, T>
 where
     T: Ctx,
 {
+    #[inline]
     fn hash<H: ::core::hash::Hasher>(&self, state: &mut H) {
         self.elapsed.hash(state);
     }
 }
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::hash::Hash>() {}
+    #[allow(dead_code)]
+    fn assert_fields<'a, T>(_: &ContextWrapper<'a, T>)
+    where
+        T: Ctx,
+    {
+        assert_bound::<i32>();
+    }
+};
+"#
+        );
+    }
+
+    #[test]
+    fn test_generic_bounds() {
+        let input = syn::parse_quote! {
+            struct Wrapper<'a, T, U> {
+                #[hash_by]
+                inner: &'a T,
+                #[hash_by]
+                id: u32,
+                ignored: U,
+            }
+        };
+
+        let output = impl_hash_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl<'a, T, U> ::core::hash::Hash for Wrapper<'a, T, U>
+where
+    &'a T: ::core::hash::Hash,
+{
+    #[inline]
+    fn hash<H: ::core::hash::Hasher>(&self, state: &mut H) {
+        self.inner.hash(state);
+        self.id.hash(state);
+    }
+}
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::hash::Hash>() {}
+    #[allow(dead_code)]
+    fn assert_fields<'a, T, U>(_: &Wrapper<'a, T, U>)
+    where
+        &'a T: ::core::hash::Hash,
+    {
+        assert_bound::<u32>();
+    }
+};
+"#
+        );
+    }
+
+    #[test]
+    fn test_const_generics() {
+        let input = syn::parse_quote! {
+            struct Matrix<T, const N: usize> {
+                #[hash_by]
+                data: [T; N],
+                #[hash_by]
+                rows: [u8; N],
+            }
+        };
+
+        let output = impl_hash_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl<T, const N: usize> ::core::hash::Hash for Matrix<T, N>
+where
+    [T; N]: ::core::hash::Hash,
+{
+    #[inline]
+    fn hash<H: ::core::hash::Hasher>(&self, state: &mut H) {
+        self.data.hash(state);
+        self.rows.hash(state);
+    }
+}
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::hash::Hash>() {}
+    #[allow(dead_code)]
+    fn assert_fields<T, const N: usize>(_: &Matrix<T, N>)
+    where
+        [T; N]: ::core::hash::Hash,
+    {
+        assert_bound::<[u8; N]>();
+    }
+};
 "#
         );
     }
@@ -238,7 +1179,9 @@ where
         let output = impl_hash_by_derive(syn::parse2(input).unwrap());
         assert_rust_eq!(
             output.to_string(),
-            r#"impl ::core::hash::Hash for Something {
+            r#"#[automatically_derived]
+impl ::core::hash::Hash for Something {
+    #[inline]
     fn hash<H: ::core::hash::Hasher>(&self, state: &mut H) {
         self.somemethod().hash(state);
         self.literal.hash(state);
@@ -247,6 +1190,14 @@ where
         self.1.hash(state);
     }
 }
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::hash::Hash>() {}
+    #[allow(dead_code)]
+    fn assert_fields(_: &Something) {
+        assert_bound::<u16>();
+        assert_bound::<u32>();
+    }
+};
 "#
         );
     }
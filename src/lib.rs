@@ -138,17 +138,22 @@
 //! );
 //! ```
 //!
-//! Now I have a `Note` enum that will cmp by `global_time`, `channel`, `pitch`, and lastly by variant order ( `enum_sequence` ). Note that `None` is always less than `Some`.
+//! Now I have a `Note` enum that will cmp by `global_time`, `channel`, `pitch`, and lastly by variant order ( `enum_sequence` ). Note that `None` is less than `Some`, unless `NoneLast(...)` or `#[cmp_by(none_last)]` is used.
 //!
 //! Conversely, separate structs such as `NoteOn` may derive from `CmpBy` in order to ignore some fields ( ex: `velocity` may be a `f32`, so we can't directly derive `Ord` ).
 use syn::{parse_macro_input, DeriveInput};
 
 mod cmp_by;
+mod cmp_hash_by;
+mod eq_by;
 mod hash_by;
 mod parsing;
+mod partial_ord_by;
 
 /// Fields that should be used for comparing are marked with the attribute `#[cmp_by]`.
 /// Other fields will be ignored.
+/// `PartialEq` compares the same fields directly with `==` and stops at the first difference,
+/// every item being evaluated only once per side.
 ///
 /// ```rust
 /// # use std::cmp::Ordering;
@@ -164,6 +169,10 @@ mod parsing;
 /// assert_eq!(Something{a: 2, b: 0}.cmp(&Something{a: 1, b: 1}), Ordering::Greater); // a is compared
 /// assert_eq!(Something{a: 1, b: 0}.cmp(&Something{a: 1, b: 1}), Ordering::Equal); // b is ignored
 /// ```
+///
+/// When another derive in scope also claims `cmp_by`, the attribute can be spelled `#[order_by]` instead,
+/// everywhere `#[cmp_by]` is accepted, for `CmpBy` and `PartialOrdBy` alike.
+///
 /// You can use it the same way with tuple structs:
 ///
 /// ```rust
@@ -171,28 +180,841 @@ mod parsing;
 /// # use cmp_by_derive::CmpBy;
 /// #
 /// #[derive(CmpBy)]
-/// struct Something(
-///     #[cmp_by]
-///     u16,
-///     #[cmp_by]
-///     u32,
-///     f32
-/// );
+/// struct Something(
+///     #[cmp_by]
+///     u16,
+///     #[cmp_by]
+///     u32,
+///     f32
+/// );
+///
+/// assert_eq!(Something(1, 0, 1.0).cmp(&Something(1, 0, 2.0)), Ordering::Equal); // Compares only specified fields
+/// assert_eq!(Something(2, 0, 1.0).cmp(&Something(1, 0, 2.0)), Ordering::Greater); // Compares only specified fields
+/// ```
+///
+/// On enums, the fields of the variants are marked the same way and compared when both values are the same variant.
+/// Values of different variants are ordered by the declaration order of their variants,
+/// and values of a variant without marked fields are equal: this is the order of `#[derive(Ord)]`, restricted to the marked fields.
+/// The variant order always comes after the top-level items, so values of different variants never compare equal
+/// even when all their items are: this order is stable, but arbitrary as far as the items are concerned.
+/// The variant order is matched with one arm per variant and no wildcard, so `#[non_exhaustive]` enums are supported,
+/// since the derive is always in their defining crate, and a variant added later gets its own arm.
+///
+/// ```rust
+/// # use std::cmp::Ordering;
+/// # use cmp_by_derive::CmpBy;
+/// #
+/// #[derive(CmpBy)]
+/// enum Shape {
+///     Circle(#[cmp_by] u32, f32),
+///     Rect { #[cmp_by] width: u32, #[cmp_by] height: u32 },
+///     Empty,
+/// }
+///
+/// assert_eq!(Shape::Circle(1, 0.5).cmp(&Shape::Circle(2, 0.1)), Ordering::Less); // Same variant, compares the marked fields
+/// assert_eq!(Shape::Circle(9, 0.5).cmp(&Shape::Rect { width: 0, height: 0 }), Ordering::Less); // Circle is declared before Rect
+/// assert_eq!(Shape::Empty.cmp(&Shape::Empty), Ordering::Equal);
+/// ```
+///
+/// The fields of a tuple variant can also be selected by listing their positions on the variant, in the order they are
+/// compared. Values of different variants are still ordered by the declaration order of their variants.
+///
+/// ```rust
+/// # use std::cmp::Ordering;
+/// # use cmp_by_derive::CmpBy;
+/// #
+/// #[derive(CmpBy)]
+/// enum Pair {
+///     #[cmp_by(1)]
+///     A(u8, u8),
+///     #[cmp_by(0)]
+///     B(u8),
+/// }
+///
+/// assert_eq!(Pair::A(9, 1).cmp(&Pair::A(0, 2)), Ordering::Less); // Compares the second field
+/// assert_eq!(Pair::A(0, 9).cmp(&Pair::B(0)), Ordering::Less); // A is declared before B
+/// ```
+///
+/// Generic types are supported: the types of the compared fields that depend on a type parameter are bounded by `Ord`,
+/// so that ignored fields don't need to be comparable.
+/// `PhantomData` fields are never bounded, even when compared, since `PhantomData<T>` is comparable for any `T`.
+/// The types of the other compared fields are checked separately, so that a field that isn't comparable is reported at its type.
+///
+/// ```rust
+/// # use std::cmp::Ordering;
+/// # use cmp_by_derive::CmpBy;
+/// #
+/// struct Handle;
+///
+/// #[derive(CmpBy)]
+/// struct Tagged<T, U> {
+///     #[cmp_by]
+///     tag: T,
+///     value: U,
+/// }
+///
+/// assert_eq!(Tagged{tag: 1, value: Handle}.cmp(&Tagged{tag: 2, value: Handle}), Ordering::Less);
+/// ```
+///
+/// A field can be sorted in descending order with `#[cmp_by(reverse)]`, the other fields keep their ascending order:
+///
+/// ```rust
+/// # use std::cmp::Ordering;
+/// # use cmp_by_derive::CmpBy;
+/// #
+/// #[derive(CmpBy)]
+/// struct Player {
+///     #[cmp_by(reverse)]
+///     score: u32,
+///     #[cmp_by]
+///     name: &'static str,
+/// }
+///
+/// assert_eq!(Player{score: 10, name: "b"}.cmp(&Player{score: 5, name: "a"}), Ordering::Less); // highest score first
+/// assert_eq!(Player{score: 10, name: "a"}.cmp(&Player{score: 10, name: "b"}), Ordering::Less); // then alphabetical
+/// assert!(Player{score: 10, name: "a"} == Player{score: 10, name: "a"}); // reversing doesn't affect equality
+/// ```
+///
+/// Fields that don't implement `Ord` can be compared with a function of signature `fn(&T, &T) -> Ordering`
+/// given by `#[cmp_by(with = "path::to::function")]`. It can be combined with `reverse`.
+///
+/// ```rust
+/// # use std::cmp::Ordering;
+/// # use cmp_by_derive::CmpBy;
+/// #
+/// fn cmp_len(a: &Vec<f32>, b: &Vec<f32>) -> Ordering {
+///     a.len().cmp(&b.len())
+/// }
+///
+/// #[derive(CmpBy)]
+/// struct Samples {
+///     #[cmp_by(with = "cmp_len")]
+///     values: Vec<f32>,
+///     #[cmp_by(with = "cmp_len", reverse)]
+///     weights: Vec<f32>,
+/// }
+///
+/// assert_eq!(Samples{values: vec![0.5], weights: vec![]}.cmp(&Samples{values: vec![1.0, 2.0], weights: vec![]}), Ordering::Less);
+/// assert_eq!(Samples{values: vec![0.5], weights: vec![1.0]}.cmp(&Samples{values: vec![1.0], weights: vec![]}), Ordering::Less);
+/// ```
+///
+/// Strings meant for people, such as names, can be ordered by a collation function of signature
+/// `fn(&str, &str) -> Ordering` with `#[cmp_by(collate_with = "path::to::collate")]`. The field is borrowed with
+/// `AsRef<str>`, so `String`, `&str`, `Box<str>` or `Cow<str>` fields can be collated. `Ord::cmp` takes no context,
+/// so a collator depending on a locale has to read it from elsewhere, typically a thread-local set before sorting.
+/// The function must then order consistently for as long as the values are compared, e.g. while they are in a
+/// `BTreeMap`, and `a.cmp(&b)` and `b.cmp(&a)` must agree. Equality also uses the collation function, so strings
+/// it considers equal, e.g. differing only by accents, are equal.
+///
+/// ```rust
+/// # use std::cell::Cell;
+/// # use std::cmp::Ordering;
+/// # use cmp_by_derive::CmpBy;
+/// #
+/// thread_local! {
+///     static IGNORE_CASE: Cell<bool> = const { Cell::new(false) };
+/// }
+///
+/// fn collate(a: &str, b: &str) -> Ordering {
+///     if IGNORE_CASE.with(Cell::get) {
+///         a.to_lowercase().cmp(&b.to_lowercase())
+///     } else {
+///         a.cmp(b)
+///     }
+/// }
+///
+/// #[derive(CmpBy)]
+/// struct Person {
+///     #[cmp_by(collate_with = "collate")]
+///     name: String,
+/// }
+///
+/// assert_eq!(Person{name: "b".into()}.cmp(&Person{name: "A".into()}), Ordering::Greater);
+/// IGNORE_CASE.with(|ignore_case| ignore_case.set(true));
+/// assert!(Person{name: "a".into()} == Person{name: "A".into()});
+/// ```
+///
+/// The comparison function can also be stored in a field, e.g. a `Box<dyn Fn(&T, &T) -> Ordering>`: a top-level
+/// `#[cmp_by(use_comparator = "cmp_fn", on = "data")]` compares `data` with `(self.cmp_fn)(&self.data, &other.data)`.
+/// Only the comparator of `self` is used, so it must be deterministic and consistent across the instances that are
+/// compared, otherwise `a.cmp(&b)` and `b.cmp(&a)` may disagree. The attribute declares this single item, in the position
+/// of the other top-level items.
+///
+/// ```rust
+/// # use std::cmp::Ordering;
+/// # use cmp_by_derive::CmpBy;
+/// #
+/// #[derive(CmpBy)]
+/// #[cmp_by(use_comparator = "cmp_fn", on = "data")]
+/// struct Sorter {
+///     cmp_fn: fn(&u32, &u32) -> Ordering,
+///     data: u32,
+/// }
+///
+/// let descending = |data| Sorter { cmp_fn: |a, b| b.cmp(a), data };
+/// assert_eq!(descending(1).cmp(&descending(2)), Ordering::Greater);
+/// ```
+///
+/// A field can also be compared by a projection of its value with `#[cmp_by(key = "path::to::function")]`,
+/// where the function has signature `fn(&T) -> K`. The function is called once for each side, and the results are
+/// compared with `Ord::cmp`, so `K` must implement `Ord`.
+///
+/// ```rust
+/// # use std::cmp::Ordering;
+/// # use cmp_by_derive::CmpBy;
+/// #
+/// #[derive(CmpBy)]
+/// struct Label {
+///     #[cmp_by(key = "str::len")]
+///     text: String,
+/// }
+///
+/// assert_eq!(Label{text: "zz".into()}.cmp(&Label{text: "aaa".into()}), Ordering::Less); // shortest first
+/// assert!(Label{text: "ab".into()} == Label{text: "cd".into()}); // same length
+/// ```
+///
+/// Floating point fields can be compared with `#[cmp_by(total)]`, which uses `f32::total_cmp` or `f64::total_cmp`.
+/// Equality is consistent with this total order: `-0.0` is less than `+0.0`, and `NaN` is equal to itself.
+///
+/// ```rust
+/// # use std::cmp::Ordering;
+/// # use cmp_by_derive::CmpBy;
+/// #
+/// #[derive(CmpBy)]
+/// struct Measure {
+///     #[cmp_by(total)]
+///     value: f64,
+///     #[cmp_by(total, reverse)]
+///     weight: f32,
+/// }
+///
+/// assert_eq!(Measure{value: 1.5, weight: 0.0}.cmp(&Measure{value: 2.5, weight: 0.0}), Ordering::Less);
+/// assert_eq!(Measure{value: 1.5, weight: 2.0}.cmp(&Measure{value: 1.5, weight: 1.0}), Ordering::Less);
+/// assert_eq!(Measure{value: -0.0, weight: 0.0}.cmp(&Measure{value: 0.0, weight: 0.0}), Ordering::Less);
+/// assert!(Measure{value: f64::NAN, weight: 0.0} == Measure{value: f64::NAN, weight: 0.0});
+/// ```
+///
+/// The `floats = "total"` flag compares every `f32` and `f64` field with `total_cmp`, without marking each of them.
+/// Floats are recognized by how their type is spelled, so it doesn't see through type aliases, references
+/// or wrappers: those fields still need `total`. Fields compared with `with`, `key`, `map` or other modifiers
+/// replacing their comparison are left alone.
+///
+/// ```rust
+/// # use std::cmp::Ordering;
+/// # use cmp_by_derive::CmpBy;
+/// #
+/// #[derive(CmpBy)]
+/// #[cmp_by(floats = "total")]
+/// struct Measure {
+///     #[cmp_by]
+///     value: f64,
+///     #[cmp_by(reverse)]
+///     weight: f32,
+/// }
+///
+/// assert_eq!(Measure{value: 1.5, weight: 2.0}.cmp(&Measure{value: 1.5, weight: 1.0}), Ordering::Less);
+/// ```
+///
+/// Floats within a tolerance of each other compare equal with `#[cmp_by(epsilon = 0.001)]`, the others are
+/// ordered with `total_cmp`. **This breaks the transitivity `Ord` requires**: `0.0` equals `0.0008` and `0.0008`
+/// equals `0.0016`, but `0.0` is less than `0.0016`. Sorting and searching still terminate, but their results
+/// depend on the order of the values, and `BTreeMap` or `BinaryHeap` may misbehave. Only use it where the values
+/// are compared pairwise, e.g. to cluster them. `HashBy` rejects it, values that are equal couldn't hash the same.
+///
+/// ```rust
+/// # use std::cmp::Ordering;
+/// # use cmp_by_derive::CmpBy;
+/// #
+/// #[derive(CmpBy)]
+/// struct Position {
+///     #[cmp_by(epsilon = 0.001)]
+///     x: f64,
+/// }
+///
+/// assert!(Position{x: 1.0} == Position{x: 1.0005});
+/// assert_eq!(Position{x: 1.0}.cmp(&Position{x: 1.1}), Ordering::Less);
+/// ```
+///
+/// Values that only implement `PartialOrd` but are expected to always be comparable, e.g. floats that are never NaN,
+/// can be compared with `partial_cmp` with `#[cmp_by(assume_total = "...")]`. What happens when the values turn out
+/// not to be comparable must be chosen explicitly: `assume_total = "panic"` panics with a message naming the field,
+/// and `assume_total = "equal"` considers them equal and moves on to the next field.
+/// `assume_total = "debug_checked"` combines both: debug builds panic, to catch incomparable values during tests,
+/// while release builds consider them equal without checking further.
+///
+/// ```rust
+/// # use std::cmp::Ordering;
+/// # use cmp_by_derive::CmpBy;
+/// #
+/// #[derive(CmpBy)]
+/// struct Sample {
+///     #[cmp_by(assume_total = "equal")]
+///     value: f64,
+///     #[cmp_by(assume_total = "panic")]
+///     weight: f64,
+/// }
+///
+/// assert_eq!(Sample{value: 1.0, weight: 0.0}.cmp(&Sample{value: 2.0, weight: 0.0}), Ordering::Less);
+/// assert_eq!(Sample{value: f64::NAN, weight: 1.0}.cmp(&Sample{value: 2.0, weight: 0.0}), Ordering::Greater);
+/// ```
+///
+/// Fields are compared in declaration order, unless they are given an explicit priority with `#[cmp_by(order = n)]`.
+/// Fields with a priority are compared first, from the lowest to the highest, the others follow in declaration order.
+///
+/// ```rust
+/// # use std::cmp::Ordering;
+/// # use cmp_by_derive::CmpBy;
+/// #
+/// #[derive(CmpBy)]
+/// struct Version {
+///     #[cmp_by]
+///     patch: u16,
+///     #[cmp_by(order = 1)]
+///     minor: u16,
+///     #[cmp_by(order = 0)]
+///     major: u16,
+/// }
+///
+/// assert_eq!(Version{major: 1, minor: 0, patch: 9}.cmp(&Version{major: 1, minor: 1, patch: 0}), Ordering::Less);
+/// ```
+///
+/// For structs with many fields, `#[cmp_by(all)]` compares all the fields in declaration order instead,
+/// except those marked with `#[cmp_by(skip)]`. Fields can still be marked to add modifiers to them.
+///
+/// ```rust
+/// # use std::cmp::Ordering;
+/// # use cmp_by_derive::CmpBy;
+/// #
+/// #[derive(CmpBy)]
+/// #[cmp_by(all)]
+/// struct Cached {
+///     name: String,
+///     #[cmp_by(reverse)]
+///     version: u32,
+///     #[cmp_by(skip)]
+///     hits: u64,
+/// }
+///
+/// assert_eq!(Cached{name: "a".into(), version: 1, hits: 0}.cmp(&Cached{name: "a".into(), version: 2, hits: 0}), Ordering::Greater);
+/// assert!(Cached{name: "a".into(), version: 1, hits: 0} == Cached{name: "a".into(), version: 1, hits: 5});
+/// ```
+///
+/// Similarly, `#[cmp_by(prefix = "k_")]` compares the fields whose name starts with `k_`, along with the marked fields,
+/// in declaration order.
+///
+/// ```rust
+/// # use std::cmp::Ordering;
+/// # use cmp_by_derive::CmpBy;
+/// #
+/// #[derive(CmpBy)]
+/// #[cmp_by(prefix = "k_")]
+/// struct Row {
+///     k_table: &'static str,
+///     k_id: u32,
+///     payload: f32,
+/// }
+///
+/// assert_eq!(Row{k_table: "a", k_id: 2, payload: 0.5}.cmp(&Row{k_table: "a", k_id: 3, payload: 0.1}), Ordering::Less);
+/// ```
+///
+/// Newtypes are compared by their single field with `#[cmp_by(transparent)]`, without marking it.
+/// It is an error on structs with more or fewer fields, and alongside top-level items.
+/// The field can still be marked to add modifiers to it, e.g. `total` for a float.
+///
+/// ```rust
+/// # use std::cmp::Ordering;
+/// # use cmp_by_derive::CmpBy;
+/// #
+/// #[derive(CmpBy)]
+/// #[cmp_by(transparent)]
+/// struct Meters(#[cmp_by(total)] f64);
+///
+/// assert_eq!(Meters(1.5).cmp(&Meters(2.0)), Ordering::Less);
+/// ```
+///
+/// Fields configured out by a `#[cfg(...)]` attribute are removed before the derive runs, so they are ignored.
+///
+/// A field can also be compared by one of its subfields, by giving the path to the subfield as a string.
+/// No bound is added for the type of the subfield, and modifiers aren't available with this form.
+///
+/// ```rust
+/// # use std::cmp::Ordering;
+/// # use cmp_by_derive::CmpBy;
+/// #
+/// struct Metadata {
+///     timestamp: u64,
+///     author: String,
+/// }
+///
+/// #[derive(CmpBy)]
+/// struct Post {
+///     #[cmp_by = "meta.timestamp"]
+///     meta: Metadata,
+///     body: String,
+/// }
+///
+/// let post = |timestamp, author: &str| Post{meta: Metadata{timestamp, author: author.into()}, body: String::new()};
+/// assert_eq!(post(1, "b").cmp(&post(2, "a")), Ordering::Less);
+/// assert!(post(1, "b") == post(1, "a"));
+/// ```
+///
+/// A key that is expensive to compute can be cached in a `OnceCell` field marked with `#[cmp_by(cache = "path::to::key")]`.
+/// The key function receives `&self`, and is only called the first time the key is needed.
+/// Marking the field with `#[hash_by(cache = "path::to::key")]` as well shares the cached key with `HashBy`.
+/// The cache isn't invalidated, so the fields the key is computed from must not change afterwards.
+///
+/// ```rust
+/// # use std::cmp::Ordering;
+/// # use std::cell::OnceCell;
+/// # use cmp_by_derive::CmpBy;
+/// #
+/// #[derive(CmpBy)]
+/// struct Document {
+///     text: String,
+///     #[cmp_by(cache = "Self::word_count")]
+///     words: OnceCell<usize>,
+/// }
+///
+/// impl Document {
+///     fn word_count(&self) -> usize {
+///         self.text.split_whitespace().count()
+///     }
+/// }
+///
+/// let document = |text: &str| Document{text: text.into(), words: OnceCell::new()};
+/// assert_eq!(document("a b").cmp(&document("c d e")), Ordering::Less);
+/// ```
+///
+/// Smart pointers and other `Deref` types can be compared by the value they point to with `#[cmp_by(deref)]`,
+/// regardless of how the pointer itself is ordered. Only the target type needs to implement `Ord`.
+///
+/// ```rust
+/// # use std::cmp::Ordering;
+/// # use std::rc::Rc;
+/// # use cmp_by_derive::CmpBy;
+/// #
+/// #[derive(CmpBy)]
+/// struct Node {
+///     #[cmp_by(deref)]
+///     name: Box<str>,
+///     #[cmp_by(deref, reverse)]
+///     weight: Rc<u32>,
+/// }
+///
+/// assert_eq!(Node{name: "a".into(), weight: Rc::new(1)}.cmp(&Node{name: "b".into(), weight: Rc::new(1)}), Ordering::Less);
+/// assert_eq!(Node{name: "a".into(), weight: Rc::new(1)}.cmp(&Node{name: "a".into(), weight: Rc::new(2)}), Ordering::Greater);
+/// ```
+///
+/// String fields can be compared ignoring case with `#[cmp_by(case_insensitive)]`, without allocating lowercase copies.
+/// Only ASCII letters are case folded, other characters are compared as they are.
+/// Use `#[hash_by(case_insensitive)]` on the same field to keep `Hash` consistent with `Eq`.
+///
+/// ```rust
+/// # use std::cmp::Ordering;
+/// # use cmp_by_derive::CmpBy;
+/// #
+/// #[derive(CmpBy)]
+/// struct User {
+///     #[cmp_by(case_insensitive)]
+///     name: String,
+/// }
+///
+/// assert_eq!(User{name: "alice".into()}.cmp(&User{name: "Bob".into()}), Ordering::Less);
+/// assert!(User{name: "ALICE".into()} == User{name: "alice".into()});
+/// ```
+///
+/// For one-off projections, `#[cmp_by(map = |field| ...)]` takes a closure instead of a function.
+/// The closure only receives a reference to the field, `self` can't be used in it.
+///
+/// ```rust
+/// # use std::cmp::Ordering;
+/// # use cmp_by_derive::CmpBy;
+/// #
+/// #[derive(CmpBy)]
+/// struct Tag {
+///     #[cmp_by(map = |name| name.trim())]
+///     name: String,
+/// }
+///
+/// assert!(Tag{name: " rust ".into()} == Tag{name: "rust".into()});
+/// assert_eq!(Tag{name: "  b".into()}.cmp(&Tag{name: "a".into()}), Ordering::Greater);
+/// ```
+///
+/// Arrays, slices and other indexable fields can be compared by one of their elements with `#[cmp_by(index = 0)]`,
+/// or by a subslice with `#[cmp_by(index_range = "0..4")]`. Like normal indexing, an index out of range panics.
+///
+/// ```rust
+/// # use std::cmp::Ordering;
+/// # use cmp_by_derive::CmpBy;
+/// #
+/// #[derive(CmpBy)]
+/// struct Address {
+///     #[cmp_by(index_range = "0..2")]
+///     bytes: [u8; 4],
+/// }
+///
+/// assert!(Address{bytes: [10, 0, 0, 1]} == Address{bytes: [10, 0, 1, 2]});
+/// assert_eq!(Address{bytes: [10, 0, 0, 1]}.cmp(&Address{bytes: [10, 1, 0, 0]}), Ordering::Less);
+/// ```
+///
+/// Collections can be compared by their length with `#[cmp_by(by_len)]`, which calls the `len()` method of the field,
+/// so the elements don't need to implement `Ord`. Use `#[hash_by(by_len)]` on the same field to keep `Hash` consistent with `Eq`.
+///
+/// ```rust
+/// # use std::cmp::Ordering;
+/// # use cmp_by_derive::CmpBy;
+/// #
+/// #[derive(CmpBy)]
+/// struct Samples {
+///     #[cmp_by(by_len)]
+///     values: Vec<f32>,
+///     #[cmp_by]
+///     name: &'static str,
+/// }
+///
+/// assert_eq!(Samples{values: vec![0.5], name: "b"}.cmp(&Samples{values: vec![1.0, 2.0], name: "a"}), Ordering::Less);
+/// assert_eq!(Samples{values: vec![0.5], name: "b"}.cmp(&Samples{values: vec![1.0], name: "a"}), Ordering::Greater);
+/// ```
+///
+/// Types that only implement `Display`, such as some error enums, can be compared by their rendered string with
+/// `#[cmp_by(by_display)]`, e.g. `"connection refused" < "timeout after 10s"`. Both sides are rendered with `to_string()`
+/// on every comparison, equality included, which allocates and formats, so it is much slower than comparing other fields.
+/// It relies on `std`, unlike the rest of the generated code. Use `#[hash_by(by_display)]` on the same field to keep
+/// `Hash` consistent with `Eq`.
+///
+/// ```rust
+/// # use std::cmp::Ordering;
+/// # use std::fmt;
+/// # use cmp_by_derive::CmpBy;
+/// #
+/// enum Failure {
+///     Refused,
+///     Timeout(u32),
+/// }
+///
+/// impl fmt::Display for Failure {
+///     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+///         match self {
+///             Failure::Refused => write!(f, "connection refused"),
+///             Failure::Timeout(secs) => write!(f, "timeout after {secs}s"),
+///         }
+///     }
+/// }
+///
+/// #[derive(CmpBy)]
+/// struct Report {
+///     #[cmp_by(by_display)]
+///     failure: Failure,
+/// }
+///
+/// assert_eq!(Report{failure: Failure::Timeout(1)}.cmp(&Report{failure: Failure::Refused}), Ordering::Greater);
+/// ```
+///
+/// Signed numbers can be compared by their magnitude with `#[cmp_by(abs)]`, which calls the inherent `abs()` method
+/// of the field, so values of opposite signs compare equal and the following fields break the tie. Floats still need
+/// `total` to be ordered. `abs()` of the minimum value of a signed integer overflows, and panics in debug builds.
+/// Use `#[hash_by(abs)]` on the same field to keep `Hash` consistent with `Eq`.
+///
+/// ```rust
+/// # use std::cmp::Ordering;
+/// # use cmp_by_derive::CmpBy;
+/// #
+/// #[derive(CmpBy)]
+/// struct Residual {
+///     #[cmp_by(abs, total)]
+///     error: f64,
+/// }
+///
+/// assert_eq!(Residual{error: -0.5}.cmp(&Residual{error: 0.1}), Ordering::Greater);
+/// ```
+///
+/// Fields can be converted with `as` before being compared with `#[cmp_by(as = "u64")]`, e.g. to compare integers of
+/// different widths on a common type, or field-less enums by their discriminant with `as = "isize"`. The field is
+/// copied to be converted, so its type must be `Copy`. Use `#[hash_by(as = "u64")]` on the same field to keep `Hash`
+/// consistent with `Eq` when the conversion can map different values to the same one.
+///
+/// ```rust
+/// # use std::cmp::Ordering;
+/// # use cmp_by_derive::CmpBy;
+/// #
+/// #[derive(Clone, Copy)]
+/// enum Level {
+///     Low = 1,
+///     High = 10,
+/// }
+///
+/// #[derive(CmpBy)]
+/// struct Alert {
+///     #[cmp_by(as = "isize")]
+///     level: Level,
+/// }
+///
+/// assert_eq!(Alert{level: Level::High}.cmp(&Alert{level: Level::Low}), Ordering::Greater);
+/// ```
+///
+/// `Duration`, `Instant` and `SystemTime` implement `Ord`, so time fields are marked with `#[cmp_by]` like any other.
+/// `SystemTime` is ordered on every platform, times before `UNIX_EPOCH` included, so there's no need to compare
+/// the `duration_since(UNIX_EPOCH)` of such fields, which fails for times before the epoch.
+///
+/// Collections such as `Vec` and slices are ordered lexicographically by default, and when one is a prefix of the other,
+/// the shorter one is less. `#[cmp_by(lex = "prefix")]` compares their items one by one up to the shorter length instead,
+/// and doesn't break ties by length, so `[1, 2]` and `[1, 2, 0]` are equal, which suits version segments.
+/// `#[cmp_by(lex = "suffix")]` does the same from the end of both sides. The field must have an `iter()` method,
+/// double-ended for `suffix`. Equality is no longer transitive, `[1]` being equal to both `[1, 2]` and `[1, 3]`,
+/// so such fields can't be hashed consistently, nor used as keys of a `BTreeMap` holding values of different lengths.
+///
+/// ```rust
+/// # use std::cmp::Ordering;
+/// # use cmp_by_derive::CmpBy;
+/// #
+/// #[derive(CmpBy)]
+/// struct Version {
+///     #[cmp_by(lex = "prefix")]
+///     segments: Vec<u32>,
+/// }
+///
+/// let version = |segments: &[u32]| Version{segments: segments.to_vec()};
+/// assert_eq!(version(&[1, 2]).cmp(&version(&[1, 2, 0])), Ordering::Equal);
+/// assert_eq!(version(&[1, 10]).cmp(&version(&[1, 9, 9])), Ordering::Greater);
+/// ```
+///
+/// Unordered collections such as `HashMap` and `HashSet` can be compared with `#[cmp_by(as_sorted)]`: references to
+/// their items are collected into a `Vec` and sorted, then the two vectors are compared, so the items must implement `Ord`.
+/// This allocates and takes O(n log n) time for each side of every comparison, equality included, which is
+/// much slower than comparing other fields. It relies on `std`, unlike the rest of the generated code.
+/// `#[hash_by(as_sorted)]` hashes the sorted items, consistently with `Eq`.
+///
+/// ```rust
+/// # use std::cmp::Ordering;
+/// # use std::collections::HashSet;
+/// # use cmp_by_derive::CmpBy;
+/// #
+/// #[derive(CmpBy)]
+/// struct Team {
+///     #[cmp_by(as_sorted)]
+///     members: HashSet<&'static str>,
+/// }
+///
+/// let team = |members: &[&'static str]| Team{members: members.iter().copied().collect()};
+/// assert!(team(&["b", "a"]) == team(&["a", "b"]));
+/// assert_eq!(team(&["b", "a"]).cmp(&team(&["c", "a"])), Ordering::Less);
+/// ```
+///
+/// With the `ordered-float` feature, float fields marked with `#[cmp_by(ordered_float)]` are wrapped in
+/// `ordered_float::OrderedFloat` to be compared, giving them a total order. `#[hash_by(ordered_float)]` hashes them
+/// the same way, consistently with `Eq`. The crate using the derive must depend on `ordered-float` itself.
+///
+/// ```rust,ignore
+/// use cmp_by_derive::{CmpBy, HashBy};
+///
+/// #[derive(CmpBy, HashBy)]
+/// struct Point {
+///     #[cmp_by(ordered_float)]
+///     #[hash_by(ordered_float)]
+///     x: f64,
+/// }
+/// ```
+///
+/// With the `indexmap` feature, `indexmap::IndexMap` fields marked with `#[cmp_by(as_pairs)]` compare their `(key, value)`
+/// entries in insertion order, for maps where the order is meaningful. Unlike `as_sorted`, nothing is sorted or allocated,
+/// and maps with the same entries in another order are different, whereas `IndexMap`'s own `PartialEq` ignores the order.
+/// `#[hash_by(as_pairs)]` hashes the entries in the same order, consistently with `Eq`.
+/// The crate using the derive must depend on `indexmap` 2.0 or later itself, the first version with `IndexMap::as_slice`.
+///
+/// ```rust,ignore
+/// use cmp_by_derive::{CmpBy, HashBy};
+/// use indexmap::IndexMap;
+///
+/// #[derive(CmpBy, HashBy)]
+/// struct Pipeline {
+///     #[cmp_by(as_pairs)]
+///     #[hash_by(as_pairs)]
+///     stages: IndexMap<String, u32>,
+/// }
+/// ```
+///
+/// `None` is less than `Some` by default. For `Option` fields, `#[cmp_by(none_last)]` sorts `None` after every `Some`,
+/// even when the field is reversed by `reverse`, `Reverse(_fields)` or `descending`. The top-level declaration accepts
+/// the same modifier by wrapping an item in `NoneLast(...)`.
+///
+/// ```rust
+/// # use std::cmp::Ordering;
+/// # use cmp_by_derive::CmpBy;
+/// #
+/// #[derive(CmpBy)]
+/// #[cmp_by(NoneLast(reviewer()))]
+/// struct Task {
+///     #[cmp_by(none_last)]
+///     deadline: Option<u32>,
+///     reviewers: Vec<&'static str>,
+/// }
+///
+/// impl Task {
+///     fn reviewer(&self) -> Option<&str> {
+///         self.reviewers.first().copied()
+///     }
+/// }
+///
+/// assert_eq!(Task{deadline: Some(3), reviewers: vec![]}.cmp(&Task{deadline: Some(1), reviewers: vec!["bob"]}), Ordering::Greater); // unreviewed last
+/// assert_eq!(Task{deadline: Some(3), reviewers: vec![]}.cmp(&Task{deadline: None, reviewers: vec![]}), Ordering::Less); // no deadline last
+/// assert_eq!(Task{deadline: Some(3), reviewers: vec![]}.cmp(&Task{deadline: Some(1), reviewers: vec![]}), Ordering::Greater);
+/// ```
+///
+/// `Result` fields marked with `#[cmp_by(on_err = "greater")]` are compared by their `Ok` value, and an `Err` is greater
+/// than every `Ok`; `on_err = "less"` makes it less. The top-level declaration accepts the same modifier by wrapping
+/// an item in `ErrGreater(...)` or `ErrLess(...)`, which saves writing an accessor that handles the error.
+/// `reverse` reverses the whole comparison, errors included.
+///
+/// All errors are equal to each other, whatever their value, which keeps the ordering transitive. Values that only
+/// differ by their error are therefore equal: `BTreeSet` or `dedup` keep one of them, and `HashBy` can't hash
+/// the field, since the error it would hash is ignored by `Eq`.
+///
+/// ```rust
+/// # use std::cmp::Ordering;
+/// # use cmp_by_derive::CmpBy;
+/// #
+/// #[derive(CmpBy)]
+/// #[cmp_by(ErrGreater(parsed_version()))]
+/// struct Package {
+///     version: &'static str,
+///     #[cmp_by(on_err = "less")]
+///     size: Result<u32, &'static str>,
+/// }
+///
+/// impl Package {
+///     fn parsed_version(&self) -> Result<u32, std::num::ParseIntError> {
+///         self.version.parse()
+///     }
+/// }
+///
+/// assert_eq!(Package{version: "x", size: Ok(0)}.cmp(&Package{version: "2", size: Ok(0)}), Ordering::Greater); // unparsed last
+/// assert_eq!(Package{version: "2", size: Err("?")}.cmp(&Package{version: "2", size: Ok(0)}), Ordering::Less); // unknown size first
+/// assert!(Package{version: "x", size: Err("?")} == Package{version: "y", size: Err("!")});
+/// ```
+///
+/// `#[cmp_by(both_some_only)]` only compares `Option` fields when both are `Some`, and considers them equal otherwise.
+///
+/// **Warning:** this breaks the transitivity that `Eq` and `Ord` require: `Some(1)` and `Some(2)` both equal `None`,
+/// but not each other. Sorting and searching will return unspecified results, and `BTreeMap` or `HashMap` keys
+/// using it will misbehave. Only use it for orderings that tolerate this, like merging partial records.
+///
+/// ```rust
+/// # use std::cmp::Ordering;
+/// # use cmp_by_derive::CmpBy;
+/// #
+/// #[derive(CmpBy)]
+/// struct Record {
+///     #[cmp_by(both_some_only)]
+///     version: Option<u32>,
+/// }
+///
+/// assert_eq!(Record{version: Some(1)}.cmp(&Record{version: Some(2)}), Ordering::Less);
+/// assert_eq!(Record{version: Some(1)}.cmp(&Record{version: None}), Ordering::Equal);
+/// ```
+///
+/// Fields marked with `#[cmp_by(eq)]` are only compared for equality: `eq` compares them along with the other fields,
+/// but `cmp` leaves them out.
+///
+/// **Warning:** `Ord` requires `cmp` to return `Equal` exactly when `eq` returns `true`. This only holds if values that
+/// order equal also have equal `eq` fields, e.g. when the `eq` field is derived from the ordered ones. Otherwise, `cmp`
+/// can return `Equal` for unequal values, and `BTreeMap` or `binary_search` may treat them as the same.
+///
+/// ```rust
+/// # use std::cmp::Ordering;
+/// # use cmp_by_derive::CmpBy;
+/// #
+/// #[derive(CmpBy)]
+/// struct Blob {
+///     #[cmp_by]
+///     name: String,
+///     #[cmp_by(eq)]
+///     name_len: usize,
+/// }
+///
+/// let blob = |name: &str| Blob{name: name.into(), name_len: name.len()};
+/// assert_eq!(blob("a").cmp(&blob("b")), Ordering::Less);
+/// assert!(blob("a") == blob("a"));
+/// ```
+///
+///
+/// Alternatively to, or in combination with field selectors, a struct-level or enum-level `#[cmp_by(method1(),method2(),attr1,nested.attr)]` can be declared.
+/// The top-level `cmp_by` attribute takes a list of attributes or method calls; items will be prepended with `self.`.
+/// Items can also be written with an explicit `self.`, e.g. `self.inner.field` or `self.method()`, which is the same as leaving it out.
+/// It can be split across several attributes, their items are compared in the order they are declared.
+/// Listing fields there pins the ordering to their names: reordering the field declarations doesn't change it,
+/// and a name that doesn't match a field is reported at the item by the compiler.
+/// Arguments of method calls, e.g. `#[cmp_by(value_in(Unit::Meters))]`, are passed as they are to both sides,
+/// and so are generic arguments, e.g. `#[cmp_by(get::<Position>())]`.
+/// Calls that take `self` as an argument, e.g. `#[cmp_by(utils::weight(self))]`, are free functions:
+/// they aren't prepended with `self.`, and `self` is replaced by `other` for the other side.
+/// Trait methods called without arguments through a path, e.g. `#[cmp_by(<Self as Ranked>::rank())]` or
+/// `#[cmp_by(Ranked::rank())]`, receive `self` as their argument, which disambiguates methods of the same name.
+///
+/// ```rust
+/// # use std::cmp::Ordering;
+/// # use cmp_by_derive::CmpBy;
+/// #
+/// #[derive(CmpBy)]
+/// #[cmp_by(product())]
+/// struct Something {
+///     #[cmp_by]
+///     a: u16,
+///     b: u16,
+/// }
+///
+/// impl Something {
+///     fn product(&self) -> u16 {
+///         self.a * self.b
+///     }
+/// }
+///
+/// assert_eq!(Something{a: 1, b: 1}.cmp(&Something{a: 1, b: 2}), Ordering::Less); // method comparison precedes member comparison
+/// assert_eq!(Something{a: 2, b: 0}.cmp(&Something{a: 1, b: 0}), Ordering::Greater); // method comparison is equal (0 = 0) so fall back to member comparison
+/// ```
+///
+/// Fields of tuple structs are referred to by their index, like `self.0`:
+///
+/// ```rust
+/// # use std::cmp::Ordering;
+/// # use cmp_by_derive::CmpBy;
+/// #
+/// #[derive(CmpBy)]
+/// #[cmp_by(2, 0.len())]
+/// struct Row(&'static str, #[cmp_by] u8, i32);
+///
+/// assert_eq!(Row("b", 0, 1).cmp(&Row("a", 1, 2)), Ordering::Less); // `2` is compared first
+/// assert_eq!(Row("bb", 1, 1).cmp(&Row("a", 0, 1)), Ordering::Greater); // then the length of `0`
+/// ```
+///
+/// Items can also be indexed, e.g. to compare by an entry of a map with `#[cmp_by(config["timeout"])]`.
+/// A missing key panics, like indexing does.
+///
+/// ```rust
+/// # use std::cmp::Ordering;
+/// # use std::collections::BTreeMap;
+/// # use cmp_by_derive::CmpBy;
+/// #
+/// #[derive(CmpBy)]
+/// #[cmp_by(config["timeout"])]
+/// struct Service {
+///     config: BTreeMap<String, u32>,
+/// }
 ///
-/// assert_eq!(Something(1, 0, 1.0).cmp(&Something(1, 0, 2.0)), Ordering::Equal); // Compares only specified fields
-/// assert_eq!(Something(2, 0, 1.0).cmp(&Something(1, 0, 2.0)), Ordering::Greater); // Compares only specified fields
+/// let service = |timeout| Service { config: BTreeMap::from([("timeout".to_string(), timeout)]) };
+/// assert_eq!(service(10).cmp(&service(20)), Ordering::Less);
 /// ```
 ///
+/// By default, this top-level declaration takes precedence, field comparisons will be considered if top-level comparisons are all `eq`.
+/// You can override this evaluation order by inserting the `_fields` reserved keyword for this derive macro: `#[cmp_by(method1(), _fields, method2())]`
+/// `_fields` may appear at most once.
 ///
-/// Alternatively to, or in combination with field selectors, a struct-level or enum-level `#[cmp_by(method1(),method2(),attr1,nested.attr)]` can be declared.
-/// The top-level `cmp_by` attribute takes a list of attributes or method calls; items will be prepended with `self.`.
 ///
 /// ```rust
 /// # use std::cmp::Ordering;
 /// # use cmp_by_derive::CmpBy;
 /// #
 /// #[derive(CmpBy)]
-/// #[cmp_by(product())]
+/// #[cmp_by(_fields, product())]
 /// struct Something {
 ///     #[cmp_by]
 ///     a: u16,
@@ -205,42 +1027,348 @@ mod parsing;
 ///     }
 /// }
 ///
-/// assert_eq!(Something{a: 1, b: 1}.cmp(&Something{a: 1, b: 2}), Ordering::Less); // method comparison precedes member comparison
-/// assert_eq!(Something{a: 2, b: 0}.cmp(&Something{a: 1, b: 0}), Ordering::Greater); // method comparison is equal (0 = 0) so fall back to member comparison
+/// assert_eq!(Something{a: 1, b: 3}.cmp(&Something{a: 1, b: 2}), Ordering::Greater); // member comparison precedes method comparison
+/// assert_eq!(Something{a: 1, b: 0}.cmp(&Something{a: 2, b: 3}), Ordering::Less); // member comparison is equal (1 = 1) so fall back to method comparison
 /// ```
 ///
-/// By default, this top-level declaration takes precedence, field comparisons will be considered if top-level comparisons are all `eq`.
-/// You can override this evaluation order by inserting the `_fields` reserved keyword for this derive macro: `#[cmp_by(method1(), _fields, method2())]`
 ///
+/// The generated methods are marked `#[inline]`. For the hottest comparisons, a top-level `#[cmp_by(inline = "always")]`
+/// marks them `#[inline(always)]` instead.
+///
+/// A value can also be compared to another type, by comparing one of its items to it.
+/// A top-level `#[cmp_by(other = "Type", key = "item")]` implements `PartialEq<Type>` and `PartialOrd<Type>`,
+/// which is useful to search sorted collections without building a full value.
+///
+/// ```rust
+/// # use cmp_by_derive::CmpBy;
+/// #
+/// #[derive(PartialEq, PartialOrd)]
+/// struct Timestamp(u64);
+///
+/// #[derive(CmpBy)]
+/// #[cmp_by(other = "Timestamp", key = "ts()")]
+/// struct Event {
+///     #[cmp_by]
+///     at: u64,
+///     #[cmp_by]
+///     name: String,
+/// }
+///
+/// impl Event {
+///     fn ts(&self) -> Timestamp {
+///         Timestamp(self.at)
+///     }
+/// }
+///
+/// let events = [Event{at: 1, name: "a".into()}, Event{at: 3, name: "b".into()}];
+/// assert_eq!(events.partition_point(|event| *event < Timestamp(2)), 1);
+/// ```
+///
+/// When a single item is compared, `#[cmp_by(borrow_key = "Type")]` compares it to `Type` through `Borrow<Type>`,
+/// like `HashMap` lookups do, so that a `String` key can be compared to a `str`.
+/// It isn't available with several compared items, a borrowed value can only stand for one of them.
+///
+/// ```rust
+/// # use cmp_by_derive::CmpBy;
+/// #
+/// #[derive(CmpBy)]
+/// #[cmp_by(borrow_key = "str")]
+/// struct User {
+///     #[cmp_by]
+///     name: String,
+///     age: u8,
+/// }
+///
+/// let users = [User{name: "alice".into(), age: 30}, User{name: "bob".into(), age: 20}];
+/// assert_eq!(users.binary_search_by(|user| user.partial_cmp("bob").unwrap()), Ok(1));
+/// assert!(users[0] == *"alice");
+/// ```
+///
+/// A top-level `#[cmp_by(gen_key)]` also generates a `cmp_key` method on structs, returning a tuple of the compared items
+/// that orders like `Ord::cmp`. Fields are borrowed rather than cloned, so the key borrows the value: it can be used to
+/// compare, group or deduplicate values without going through `Ord`, but not with `sort_by_key`, whose key can't borrow
+/// from the sorted value. Items compared with `with`, `total` or `case_insensitive` have no such key, and can't be used with it.
+/// When the compared items are all fields marked with `#[cmp_by]`, possibly with `reverse` or `none_last`, the key is
+/// a tuple of references to them, e.g. `(Reverse<&u8>, &String)`, which can be named, destructured and hashed. Top-level
+/// items and fields compared through a projection such as `by_len` or `key` have a type the derive can't see, so the key
+/// is then returned as `impl Ord + '_`, which can only be compared.
+///
+/// ```rust
+/// # use cmp_by_derive::CmpBy;
+/// #
+/// #[derive(CmpBy)]
+/// #[cmp_by(gen_key)]
+/// struct Task {
+///     #[cmp_by(reverse)]
+///     priority: u8,
+///     #[cmp_by]
+///     name: String,
+/// }
+///
+/// let mut tasks = vec![Task{priority: 1, name: "b".into()}, Task{priority: 2, name: "a".into()}];
+/// tasks.sort_by(|a, b| a.cmp_key().cmp(&b.cmp_key()));
+/// assert_eq!(tasks[0].name, "a");
+/// ```
+///
+/// Similarly, `#[cmp_by(gen_min_heap_key)]` generates a `min_heap_key` method returning the same key wrapped in `Reverse`,
+/// so that a `BinaryHeap`, which pops the greatest element first, pops the least value first.
+///
+/// ```rust
+/// # use std::collections::BinaryHeap;
+/// # use cmp_by_derive::CmpBy;
+/// #
+/// #[derive(CmpBy)]
+/// #[cmp_by(gen_min_heap_key)]
+/// struct Event {
+///     #[cmp_by]
+///     time: u64,
+/// }
+///
+/// let events = [Event{time: 3}, Event{time: 1}, Event{time: 2}];
+/// let mut heap: BinaryHeap<_> = events.iter().map(|event| (event.min_heap_key(), event)).collect();
+/// assert_eq!(heap.pop().unwrap().1.time, 1);
+/// ```
+///
+/// For generic code, `#[cmp_by(impl_sort_key)]` implements a `SortKey` trait, which must be in scope with this definition:
+///
+/// ```rust
+/// trait SortKey {
+///     type Key<'a>: Ord
+///     where
+///         Self: 'a;
+///
+///     fn sort_key(&self) -> Self::Key<'_>;
+/// }
+/// ```
+///
+/// `Key` is the tuple of references to the compared fields, wrapped in `Reverse` or paired with `is_none()` like the key
+/// of `gen_key`, and `Key<'a>` borrows the value for `'a`, so nothing is cloned. Its type has to be named, so it only
+/// applies to structs whose compared items are fields marked with `#[cmp_by]`, possibly with `reverse` or `none_last`.
+///
+/// ```rust
+/// # use cmp_by_derive::CmpBy;
+/// # trait SortKey {
+/// #     type Key<'a>: Ord where Self: 'a;
+/// #     fn sort_key(&self) -> Self::Key<'_>;
+/// # }
+/// #
+/// #[derive(CmpBy)]
+/// #[cmp_by(impl_sort_key)]
+/// struct Task {
+///     #[cmp_by(reverse)]
+///     priority: u8,
+///     #[cmp_by]
+///     name: String,
+/// }
+///
+/// fn sort<T: SortKey>(items: &mut [T]) {
+///     items.sort_by(|a, b| a.sort_key().cmp(&b.sort_key()));
+/// }
+///
+/// let mut tasks = vec![Task{priority: 1, name: "b".into()}, Task{priority: 2, name: "a".into()}];
+/// sort(&mut tasks);
+/// assert_eq!(tasks[0].name, "a");
+/// ```
+///
+/// With `#[cmp_by(tuple_cmp)]`, `Ord::cmp` builds that tuple for both values and compares them at once, as in
+/// `(self.a, &self.b).cmp(&(other.a, &other.b))`, instead of chaining the comparisons of the items. It applies to the
+/// same structs and items as `gen_key`. Sorting two million values showed no measurable difference between both forms,
+/// so it is a matter of taste.
+///
+/// ```rust
+/// # use cmp_by_derive::CmpBy;
+/// #
+/// #[derive(CmpBy)]
+/// #[cmp_by(tuple_cmp)]
+/// struct Version {
+///     #[cmp_by]
+///     major: u16,
+///     #[cmp_by]
+///     minor: u16,
+/// }
+///
+/// assert!(Version{major: 1, minor: 2} < Version{major: 2, minor: 0});
+/// ```
+///
+/// `Ord::cmp` can't be called in const contexts, so `#[cmp_by(const_fn)]` also generates a `const_cmp` method comparing
+/// like it, e.g. to sort tables at compile time. It only applies to structs whose compared fields are integers, `bool`
+/// or `char`, possibly converted with `as` or compared with `abs` and `reverse`, since their operators are usable in
+/// const contexts. Other fields and top-level items are reported.
 ///
 /// ```rust
 /// # use std::cmp::Ordering;
 /// # use cmp_by_derive::CmpBy;
 /// #
 /// #[derive(CmpBy)]
-/// #[cmp_by(_fields, product())]
-/// struct Something {
+/// #[cmp_by(const_fn)]
+/// struct Version {
 ///     #[cmp_by]
-///     a: u16,
-///     b: u16,
+///     major: u16,
+///     #[cmp_by]
+///     minor: u16,
 /// }
 ///
-/// impl Something {
-///     fn product(&self) -> u16 {
-///         self.a * self.b
+/// const ORDER: Ordering = Version{major: 1, minor: 2}.const_cmp(&Version{major: 1, minor: 3});
+/// assert_eq!(ORDER, Ordering::Less);
+/// ```
+///
+/// `#[cmp_by(gen_reverse = "RevEvent")]` generates a `RevEvent` tuple struct wrapping the type, with the same visibility,
+/// that is ordered in reverse. It gives a name to the descending order, e.g. for a `BinaryHeap` that pops the least
+/// value first, without wrapping every value in `Reverse`.
+///
+/// ```rust
+/// # use cmp_by_derive::CmpBy;
+/// # use std::collections::BinaryHeap;
+/// #
+/// #[derive(CmpBy)]
+/// #[cmp_by(gen_reverse = "RevEvent")]
+/// struct Event {
+///     #[cmp_by]
+///     time: u64,
+/// }
+///
+/// let mut heap: BinaryHeap<_> = [3, 1, 2].map(|time| RevEvent(Event{time})).into();
+/// assert_eq!(heap.pop().unwrap().0.time, 1);
+/// ```
+///
+/// Items of the top-level declaration can be sorted in descending order by wrapping them in `Reverse(...)`:
+///
+/// ```rust
+/// # use std::cmp::Ordering;
+/// # use cmp_by_derive::CmpBy;
+/// #
+/// #[derive(CmpBy)]
+/// #[cmp_by(Reverse(priority()))]
+/// struct Task {
+///     urgent: bool,
+///     #[cmp_by]
+///     name: &'static str,
+/// }
+///
+/// impl Task {
+///     fn priority(&self) -> u8 {
+///         if self.urgent { 1 } else { 0 }
 ///     }
 /// }
 ///
-/// assert_eq!(Something{a: 1, b: 3}.cmp(&Something{a: 1, b: 2}), Ordering::Greater); // member comparison precedes method comparison
-/// assert_eq!(Something{a: 1, b: 0}.cmp(&Something{a: 2, b: 3}), Ordering::Less); // member comparison is equal (1 = 1) so fall back to method comparison
+/// assert_eq!(Task{urgent: true, name: "b"}.cmp(&Task{urgent: false, name: "a"}), Ordering::Less); // highest priority first
+/// assert_eq!(Task{urgent: true, name: "a"}.cmp(&Task{urgent: true, name: "b"}), Ordering::Less); // then alphabetical
+/// ```
+///
+///
+/// The whole ordering can be reversed with the `descending` flag, it can't be combined with `reverse`:
+///
+/// ```rust
+/// # use std::cmp::Ordering;
+/// # use cmp_by_derive::CmpBy;
+/// #
+/// #[derive(CmpBy)]
+/// #[cmp_by(descending, size())]
+/// struct Job {
+///     #[cmp_by]
+///     priority: u8,
+///     data: Vec<u8>,
+/// }
+///
+/// impl Job {
+///     fn size(&self) -> usize {
+///         self.data.len()
+///     }
+/// }
+///
+/// assert_eq!(Job{priority: 0, data: vec![0, 0]}.cmp(&Job{priority: 9, data: vec![0]}), Ordering::Less); // biggest first
+/// assert_eq!(Job{priority: 9, data: vec![0]}.cmp(&Job{priority: 0, data: vec![0]}), Ordering::Less); // then highest priority
+/// ```
+///
+/// When the type already implements `PartialEq` and `Eq`, the `ord_only` flag only implements `Ord` and `PartialOrd`.
+/// `Ord` requires that `a == b` exactly when `a.cmp(&b)` is `Ordering::Equal`: the existing `eq` must agree with
+/// the derived ordering, otherwise sorting, `BTreeMap` and binary searches misbehave.
+/// Conversely, the `eq_only` flag only implements `Eq` and `PartialEq`, from the same marked fields.
+///
+/// ```rust
+/// # use std::cmp::Ordering;
+/// # use cmp_by_derive::CmpBy;
+/// #
+/// #[derive(CmpBy, PartialEq, Eq)]
+/// #[cmp_by(ord_only)]
+/// struct Version {
+///     #[cmp_by]
+///     major: u16,
+///     #[cmp_by]
+///     minor: u16,
+/// }
+///
+/// assert_eq!(Version{major: 1, minor: 2}.cmp(&Version{major: 2, minor: 0}), Ordering::Less);
 /// ```
 ///
-#[proc_macro_derive(CmpBy, attributes(cmp_by))]
+#[proc_macro_derive(CmpBy, attributes(cmp_by, order_by))]
 pub fn cmp_by_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let ast = parse_macro_input!(input as DeriveInput);
     cmp_by::impl_cmp_by_derive(ast).into()
 }
 
+/// Derives only `PartialEq` and `Eq`, for types where equality makes sense but ordering doesn't.
+///
+/// Fields that should be used for equality are marked with the attribute `#[eq_by]`, other fields will be ignored.
+/// A top-level `#[eq_by(method1(), attr1, nested.attr)]` can also be declared, like for [`CmpBy`](derive.CmpBy.html).
+/// Fields are compared directly with `==`, and must implement `Eq` since `Eq` is implemented as well.
+/// Fields of a generic type only need `PartialEq`, the `Eq` impl is then bounded by `Eq`.
+/// The `with`, `collate_with`, `key` and `total` modifiers of `CmpBy` are supported, equality is then defined as the ordering being equal.
+///
+/// Values of different enum variants are never equal.
+///
+/// ```rust
+/// use cmp_by_derive::EqBy;
+///
+/// #[derive(EqBy)]
+/// struct Document {
+///     #[eq_by]
+///     path: String,
+///     #[eq_by(total)]
+///     score: f32,
+///     cache: Vec<u8>,
+/// }
+///
+/// assert!(Document{path: "a".into(), score: 1.0, cache: vec![1]} == Document{path: "a".into(), score: 1.0, cache: vec![]});
+/// assert!(Document{path: "a".into(), score: 1.0, cache: vec![]} != Document{path: "a".into(), score: 2.0, cache: vec![]});
+/// ```
+///
+#[proc_macro_derive(EqBy, attributes(eq_by))]
+pub fn eq_by_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput);
+    eq_by::impl_eq_by_derive(ast).into()
+}
+
+/// Derives only `PartialOrd`, for types that are only partially orderable, such as types containing floats.
+///
+/// It is configured with the same `#[cmp_by]` attributes as [`CmpBy`](derive.CmpBy.html), but fields are compared
+/// with `PartialOrd::partial_cmp`, so they only need to implement `PartialOrd`.
+/// The comparison stops at the first field that isn't equal, including when it is incomparable (`None`).
+///
+/// `PartialOrd` requires `PartialEq`, which must be implemented separately.
+///
+/// ```rust
+/// # use std::cmp::Ordering;
+/// use cmp_by_derive::PartialOrdBy;
+///
+/// #[derive(PartialOrdBy, PartialEq)]
+/// struct Point {
+///     #[cmp_by]
+///     x: f32,
+///     #[cmp_by]
+///     y: f32,
+/// }
+///
+/// assert_eq!(Point{x: 1.0, y: 2.0}.partial_cmp(&Point{x: 1.0, y: 3.0}), Some(Ordering::Less));
+/// assert_eq!(Point{x: f32::NAN, y: 2.0}.partial_cmp(&Point{x: 1.0, y: 3.0}), None);
+/// ```
+///
+#[proc_macro_derive(PartialOrdBy, attributes(cmp_by, order_by))]
+pub fn partial_ord_by_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput);
+    partial_ord_by::impl_partial_ord_by_derive(ast).into()
+}
+
 /// Fields that should be used for hashing are marked with the attribute `#[hash_by]`.
 /// Other fields will be ignored.
 ///
@@ -272,6 +1400,8 @@ pub fn cmp_by_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream
 ///
 /// Alternatively to, or in combination with field selectors, a struct-level or enum-level `#[hash_by(method1(),method2(),attr1,nested.attr)]` can be declared.
 /// The top-level `hash_by` attribute takes a list of attributes or method calls; items will be prepended with `self.`.
+/// Items can also be written with an explicit `self.`, e.g. `self.inner.field` or `self.method()`, which is the same as leaving it out.
+/// It can be split across several attributes, their items are hashed in the order they are declared.
 ///
 /// ```rust
 /// # use cmp_by_derive::HashBy;
@@ -291,10 +1421,189 @@ pub fn cmp_by_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream
 /// }
 /// ```
 ///
-/// Because hashing is not order dependent, there is no point for the `_fields` reserved keyword for this derive, so it isn't included.
+/// Like for `CmpBy`, `#[hash_by(inline = "always")]` marks `hash` with `#[inline(always)]` instead of `#[inline]`.
+///
+/// Like for `CmpBy`, `#[hash_by(all)]` hashes all the fields except those marked with `#[hash_by(skip)]`.
+/// A field that is compared can be left out of the hash, e.g. a cache derived from the hashed fields: equal values
+/// still hash the same, since they are equal in every hashed field. Values that only differ by that field collide,
+/// which is harmless when it's determined by the hashed fields, but slows hash maps down otherwise.
+/// `#[hash_by(transparent)]` hashes the single field of a newtype.
+///
+/// String fields marked with `#[hash_by(case_insensitive)]` are hashed with their ASCII letters folded to lowercase,
+/// to match fields compared with `#[cmp_by(case_insensitive)]`.
+/// Likewise, fields marked with `#[hash_by(by_len)]` hash their `len()`, to match fields compared with `#[cmp_by(by_len)]`,
+/// and fields marked with `#[hash_by(by_display)]` hash their rendered string, to match fields compared with `#[cmp_by(by_display)]`.
+/// Fields marked with `#[hash_by(as_sorted)]` hash their items once sorted, to match fields compared with `#[cmp_by(as_sorted)]`.
+///
+/// Fields that don't implement `Hash` can be hashed through a function with `#[hash_by(with = "path::to::function")]`,
+/// which receives a reference to the field and returns a hashable value, like `#[cmp_by(with = ...)]` does.
+/// For instance, floats compared with `#[cmp_by(total)]` are equal exactly when their bits are, so they can be hashed
+/// through `f64::to_bits`.
+///
+/// ```rust
+/// # use cmp_by_derive::{CmpBy, HashBy};
+/// #
+/// fn celsius_bits(celsius: &f64) -> u64 {
+///     celsius.to_bits()
+/// }
+///
+/// #[derive(CmpBy, HashBy)]
+/// struct Reading {
+///     #[cmp_by(total)]
+///     #[hash_by(with = "celsius_bits")]
+///     celsius: f64,
+/// }
+/// ```
+///
+/// With the `bytemuck` feature, fields marked with `#[hash_by(bytes)]` are written to the hasher as a single slice
+/// with `bytemuck::bytes_of`, instead of hashing their fields one by one. This is much faster for flat `#[repr(C)]`
+/// structs and arrays of them, e.g. about 15 times for 4096 pixels of 4 `u8`s in `benches/hash_bytes.rs`, but integer
+/// arrays gain nothing, `Hash` already writes them in a single call. The field type must implement `bytemuck::Pod`,
+/// which guarantees it has no padding and that every byte is meaningful; a type that doesn't is reported at the field.
+/// The crate using the derive must depend on `bytemuck` itself.
+/// Hashing bytes is only consistent with `Eq` when equal values have the same bytes, which holds for integers
+/// and floats compared with `#[cmp_by(total)]`, but not for floats compared with `ordered_float`, where `0.0 == -0.0`.
+///
+/// ```rust,ignore
+/// use cmp_by_derive::{CmpBy, HashBy};
+///
+/// #[derive(CmpBy, HashBy)]
+/// struct Frame {
+///     #[cmp_by]
+///     #[hash_by(bytes)]
+///     pixels: [u32; 4096],
+/// }
+/// ```
+///
+/// Integer fields marked with `#[hash_by(raw)]` are written with the `Hasher` method of their type, e.g. `write_u32`,
+/// instead of going through `Hash::hash`. This is a micro-optimization: the hash is the same, since `Hash` calls
+/// that method for integers, but some hashers benefit from the direct call. The type is recognized by its name,
+/// so fields of other types, including aliases of integer types, fall back to `Hash::hash`.
+///
+/// ```rust
+/// # use cmp_by_derive::HashBy;
+/// #
+/// #[derive(HashBy)]
+/// struct Pixel {
+///     #[hash_by(raw)]
+///     x: u16,
+///     #[hash_by(raw)]
+///     y: u16,
+/// }
+/// ```
+///
+/// On enums, the variant is hashed first with `core::mem::discriminant`, like `#[derive(Hash)]` does,
+/// so that values of different variants with the same fields hash differently.
+/// `#[hash_by(no_discriminant)]` leaves the variant out, for hashes that don't depend on it.
+///
+/// `#[hash_by(tag = 0xAB)]` writes a tag to the hasher before anything else, with `write_u64` for an integer,
+/// or as the bytes of a string with `#[hash_by(tag = "Name")]`. Types hashing the same items under different tags
+/// then hash differently, which avoids collisions between values of different types hashed into the same table.
+/// Changing the tag changes every hash of the type.
+///
+/// ```rust
+/// # use cmp_by_derive::HashBy;
+/// #
+/// #[derive(HashBy)]
+/// #[hash_by(tag = "Meters")]
+/// struct Meters(#[hash_by] u32);
+/// ```
+///
+/// The marked fields are hashed after the items of the top-level declaration, unless the `_fields` reserved keyword
+/// places them elsewhere: `#[hash_by(_fields, method())]` hashes the fields first.
+/// Moving `_fields` doesn't change which values are equal, but it does change the hash values, since hashers depend
+/// on the order things are written in.
+///
+/// ```rust
+/// # use cmp_by_derive::HashBy;
+/// #
+/// #[derive(HashBy)]
+/// #[hash_by(_fields, checksum())]
+/// struct Packet {
+///     #[hash_by]
+///     id: u32,
+///     payload: Vec<u8>,
+/// }
+///
+/// impl Packet {
+///     fn checksum(&self) -> u8 {
+///         self.payload.iter().fold(0, |sum, byte| sum.wrapping_add(*byte))
+///     }
+/// }
+/// ```
+///
+/// `Hash` must be consistent with `Eq`: values that are equal must have the same hash, otherwise `HashMap` and `HashSet` misbehave.
+/// This holds as long as every hashed item is also compared for equality, hashing fewer items than are compared is fine.
+/// The `verify_eq` flag checks this at compile time against the items marked with `#[cmp_by]`, or `#[eq_by]` if there are none.
+///
+/// ```rust
+/// # use cmp_by_derive::{CmpBy, HashBy};
+/// #
+/// #[derive(CmpBy, HashBy)]
+/// #[hash_by(verify_eq)]
+/// struct Something {
+///     #[cmp_by]
+///     #[hash_by]
+///     a: u16,
+///     #[cmp_by]
+///     b: u16,
+/// }
+/// ```
+///
+/// Each item that breaks the contract is reported:
+/// - ``HashBy: `b` is hashed but not compared for equality, so equal values could hash differently``
+/// - ``HashBy: `b` is compared with `key`, so equal values could hash differently``, and likewise for `with`
+///   and `collate_with`, since a custom comparison can consider different values equal.
+///
+/// `HashBy` implements `Hash` itself, so it can't be combined with `#[derive(Hash)]`.
+/// A derive only sees the attributes that follow its own `#[derive]`, so `HashBy` reports
+/// ``HashBy: `derive(Hash)` can't be combined with HashBy, both implement `Hash` `` when `Hash` is derived in a later attribute,
+/// while deriving both in the same attribute, or `Hash` in an earlier one, is reported by rustc as conflicting implementations.
 ///
 #[proc_macro_derive(HashBy, attributes(hash_by))]
 pub fn hash_by_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
     let ast = parse_macro_input!(input as DeriveInput);
     hash_by::impl_hash_by_derive(ast).into()
 }
+
+/// `CmpHashBy` derives `Ord`, `PartialOrd`, `Eq` and `PartialEq` like `CmpBy`, and a `Hash` consistent with them,
+/// from the same `#[cmp_by]` spec, so the compared items don't have to be repeated with `#[hash_by]`.
+/// The compared items are hashed in the order they are compared, projected the same way, e.g. by `case_insensitive`
+/// or `as_sorted`, and floats compared with `total` are hashed by their bits.
+///
+/// ```rust
+/// # use std::cmp::Ordering;
+/// # use std::collections::HashSet;
+/// use cmp_by_derive::CmpHashBy;
+///
+/// #[derive(CmpHashBy)]
+/// #[cmp_by(name())]
+/// struct Track {
+///     #[cmp_by(reverse)]
+///     rating: u8,
+///     #[cmp_by(total)]
+///     length: f64,
+///     name: String,
+/// }
+///
+/// impl Track {
+///     fn name(&self) -> &str {
+///         &self.name
+///     }
+/// }
+///
+/// let track = |rating, name: &str| Track { rating, length: 1.5, name: name.to_string() };
+/// assert_eq!(track(2, "a").cmp(&track(1, "a")), Ordering::Less);
+/// assert!(HashSet::from([track(1, "a")]).contains(&track(1, "a")));
+/// ```
+///
+/// Items compared in a way that can consider different values equal can't be hashed consistently, so they are reported,
+/// e.g. ``CmpHashBy: `with` can't be hashed consistently, values it compares equal could hash differently``,
+/// and likewise for `key`, `map`, `use_comparator`, `both_some_only`, `on_err`, `epsilon`, `assume_total` and `lex`.
+/// Other mistakes in the spec are reported as they are by `CmpBy`.
+///
+#[proc_macro_derive(CmpHashBy, attributes(cmp_by, order_by))]
+pub fn cmp_hash_by_derive(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput);
+    cmp_hash_by::impl_cmp_hash_by_derive(ast).into()
+}
@@ -1,47 +1,1438 @@
 #![allow(clippy::manual_try_fold)]
 
-use proc_macro2::{Span, TokenStream};
-use quote::{quote, ToTokens};
+use proc_macro2::{Ident, Punct, Spacing, Span, TokenStream, TokenTree};
+use quote::{format_ident, quote, quote_spanned, ToTokens};
 use syn::{
-    parse2, parse_quote, punctuated::Punctuated, spanned::Spanned, ConstParam, Data, DataEnum,
-    DataStruct, DeriveInput, Error, Expr, Fields, FieldsNamed, FieldsUnnamed, GenericArgument,
-    GenericParam, Generics, Index, LifetimeParam, Token, TypeParam,
+    parse2, parse_quote, spanned::Spanned, Attribute, Data, DataEnum, DataStruct, DataUnion,
+    DeriveInput, Error, Expr, ExprAssign, ExprCall, ExprClosure, ExprField, ExprIndex, ExprLit,
+    ExprMethodCall, ExprPath, Field, Fields, FieldsNamed, FieldsUnnamed, Generics, Index, Lit,
+    LitFloat, LitInt, LitStr, Member, Meta, Path, Type, TypePath,
 };
 
+#[derive(Clone)]
 pub enum ParsedFields {
-    Struct(Vec<Expr>),
-    Enum(Vec<(TokenStream, Vec<Expr>)>),
+    Struct(Vec<Key>),
+    Enum(Vec<Variant>),
+}
+
+impl ParsedFields {
+    /// Iterate over the keys of every field, in all variants for enums.
+    pub fn keys(&self) -> Box<dyn Iterator<Item = &Key> + '_> {
+        match self {
+            ParsedFields::Struct(keys) => Box::new(keys.iter()),
+            ParsedFields::Enum(variants) => {
+                Box::new(variants.iter().flat_map(|variant| &variant.keys))
+            }
+        }
+    }
+
+    /// The fields whose keys satisfy `predicate`, variants are kept even when none of their keys do.
+    pub fn filter(&self, predicate: impl Fn(&Key) -> bool) -> ParsedFields {
+        match self {
+            ParsedFields::Struct(keys) => {
+                ParsedFields::Struct(keys.iter().filter(|key| predicate(key)).cloned().collect())
+            }
+            ParsedFields::Enum(variants) => ParsedFields::Enum(
+                variants
+                    .iter()
+                    .map(|variant| Variant {
+                        keys: variant
+                            .keys
+                            .iter()
+                            .filter(|key| predicate(key))
+                            .cloned()
+                            .collect(),
+                        ..variant.clone()
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// A variant of an enum, along with the keys of its fields.
+#[derive(Clone)]
+pub struct Variant {
+    /// Pattern matching the variant whatever its fields, e.g. `Self::A(..)`.
+    pub pattern: TokenStream,
+    path: TokenStream,
+    pub keys: Vec<Key>,
+}
+
+impl Variant {
+    /// Pattern binding the fields of the keys to the names their accesses from `this` use.
+    pub fn bind(&self, this: &TokenStream) -> TokenStream {
+        let path = &self.path;
+        let fields = self.keys.iter().map(|key| {
+            let (member, binding) = (key.member(), key.binding(this));
+            quote!(#member: #binding)
+        });
+        quote!(#path { #(#fields,)* .. })
+    }
+}
+
+/// A field selected for comparison, along with the modifiers declared in its attribute.
+#[derive(Clone)]
+pub struct Key {
+    pub expr: Expr,
+    /// Type of the field, `None` for items of the top-level attribute.
+    pub ty: Option<Type>,
+    pub modifiers: Modifiers,
+    /// Field of an enum variant, accessed through the binding of [`Variant::bind`] instead of `self`.
+    pub in_variant: bool,
+}
+
+impl Key {
+    /// The compared value, accessed from `this` (`self` or `other`).
+    pub fn value(&self, this: &TokenStream) -> TokenStream {
+        let access = self.access(this);
+        let value = match (self.modifiers.deref, self.is_binding()) {
+            (Some(_), true) => quote!((**#access)),
+            (Some(_), false) => quote!((*#access)),
+            (None, _) => access,
+        };
+        if self.modifiers.by_len.is_some() {
+            quote!(#value.len())
+        } else if self.modifiers.by_display.is_some() {
+            quote!(::std::string::ToString::to_string(&#value))
+        } else if self.modifiers.abs.is_some() {
+            quote!(#value.abs())
+        } else if let Some(ty) = &self.modifiers.cast {
+            // The value is copied out of the binding of variant fields.
+            let value = if self.is_binding() && self.modifiers.deref.is_none() {
+                quote!(*#value)
+            } else {
+                value
+            };
+            quote!((#value as #ty))
+        } else if self.modifiers.ordered_float.is_some() {
+            // The float is copied out of the binding of variant fields.
+            let float = if self.is_binding() && self.modifiers.deref.is_none() {
+                quote!(*#value)
+            } else {
+                value
+            };
+            quote!(::ordered_float::OrderedFloat(#float))
+        } else {
+            value
+        }
+    }
+
+    /// Whether `Err` is greater than every `Ok`, from `on_err = "greater"`.
+    pub fn err_greater(&self) -> bool {
+        self.modifiers
+            .on_err
+            .as_ref()
+            .is_some_and(|on_err| on_err.value() == "greater")
+    }
+
+    /// The pairs of items compared one by one with `lex`, from the end for `lex = "suffix"`.
+    ///
+    /// `zip` stops at the shorter side, so the lengths are never compared.
+    pub fn lex_pairs(&self, this: &TokenStream, other: &TokenStream) -> TokenStream {
+        let suffix = self
+            .modifiers
+            .lex
+            .as_ref()
+            .is_some_and(|lex| lex.value() == "suffix");
+        let [this, other] = [this, other].map(|side| {
+            let value = self.value(side);
+            let items = quote!(#value.iter());
+            if suffix {
+                quote!(::core::iter::Iterator::rev(#items))
+            } else {
+                items
+            }
+        });
+        quote!(::core::iter::Iterator::zip(#this, #other))
+    }
+
+    /// The call to the collation function of `collate_with`, with both sides borrowed as `&str`.
+    pub fn collated(&self, this: &TokenStream, other: &TokenStream) -> TokenStream {
+        let collate = &self.modifiers.collate_with;
+        let [this, other] = [this, other].map(|side| {
+            let reference = self.reference(side);
+            quote!(::core::convert::AsRef::<str>::as_ref(#reference))
+        });
+        quote!(#collate(#this, #other))
+    }
+
+    /// The compared value projected by `key`, `map`, `as_sorted` or `as_pairs` if any, accessed from `this` (`self` or `other`).
+    pub fn projected(&self, this: &TokenStream) -> TokenStream {
+        let reference = self.reference(this);
+        match (&self.modifiers.key, &self.modifiers.map) {
+            (Some(key), _) => quote!(#key(#reference)),
+            // `Option::map` gives its type to the argument of the closure, which can't be inferred when it's called directly.
+            (None, Some(map)) => quote!(::core::option::Option::Some(#reference).map(#map)),
+            // The items are borrowed, only the `Vec` holding them is allocated.
+            // The block is parenthesized to be usable as an operand.
+            (None, None) if self.modifiers.as_sorted.is_some() => quote!(({
+                let mut items: ::std::vec::Vec<_> = ::core::iter::IntoIterator::into_iter(#reference).collect();
+                items.sort_unstable();
+                items
+            })),
+            // The entries of an `IndexMap` are compared in insertion order through its slice, without allocating.
+            (None, None) if self.modifiers.as_pairs.is_some() => {
+                quote!(::indexmap::IndexMap::as_slice(#reference))
+            }
+            (None, None) => self.value(this),
+        }
+    }
+
+    /// The compared value passed by value, copied out of the binding of variant fields.
+    pub fn copied(&self, this: &TokenStream) -> TokenStream {
+        let value = self.value(this);
+        // Computed values are already owned.
+        if self.is_binding() && self.modifiers.deref.is_none() && !self.is_computed() {
+            quote!(*#value)
+        } else {
+            value
+        }
+    }
+
+    /// A reference to the compared value, accessed from `this` (`self` or `other`).
+    pub fn reference(&self, this: &TokenStream) -> TokenStream {
+        if self.is_computed() {
+            let value = self.value(this);
+            return quote!(&#value);
+        }
+        let access = self.access(this);
+        match (self.modifiers.deref, self.is_binding()) {
+            (Some(_), true) => quote!(&**#access),
+            (Some(_), false) => quote!(&*#access),
+            (None, true) => access,
+            (None, false) => quote!(&#access),
+        }
+    }
+
+    /// The ordering of a value compared with `assume_total`, panicking with a message naming the item when it isn't
+    /// comparable for `assume_total = "panic"`, or equal for `assume_total = "equal"`.
+    /// With `assume_total = "debug_checked"`, it panics in debug builds and is equal in release builds.
+    pub fn assumed_total(&self, this: &TokenStream, other: &TokenStream) -> TokenStream {
+        let (this, other) = (self.reference(this), self.reference(other));
+        let ord = quote!(::core::cmp::PartialOrd::partial_cmp(#this, #other));
+        let incomparable = format!(
+            "`{}` isn't comparable, but is compared with `assume_total`",
+            self.expr.to_token_stream()
+        );
+        match self
+            .modifiers
+            .assume_total
+            .as_ref()
+            .map(LitStr::value)
+            .as_deref()
+        {
+            Some("panic") => quote!(#ord.expect(#incomparable)),
+            // The block is parenthesized to be usable as a receiver.
+            Some("debug_checked") => quote!(({
+                let ord = #ord;
+                ::core::debug_assert!(ord.is_some(), "{}", #incomparable);
+                ord.unwrap_or(::core::cmp::Ordering::Equal)
+            })),
+            _ => quote!(#ord.unwrap_or(::core::cmp::Ordering::Equal)),
+        }
+    }
+
+    /// `this.expr`, or `function(this)` for free functions taking `self` as an argument.
+    ///
+    /// Cached fields hold the key computed from `this`, it is computed the first time it is accessed.
+    fn access(&self, this: &TokenStream) -> TokenStream {
+        let expr = &self.expr;
+        let field = if self.in_variant {
+            let binding = self.binding(this);
+            let subfields = expr.to_token_stream().into_iter().skip(1);
+            quote!(#binding #(#subfields)*)
+        } else {
+            quote!(#this.#expr)
+        };
+        let field = match &self.modifiers.index {
+            Some(index) => quote!(#field[#index]),
+            None => field,
+        };
+        if let Some(cache) = &self.modifiers.cache {
+            return quote!(#field.get_or_init(|| #cache(#this)));
+        }
+        match expr {
+            Expr::Call(ExprCall { func, args, .. }) if args.iter().any(is_self) => {
+                let args = args.iter().map(|arg| {
+                    if is_self(arg) {
+                        this.clone()
+                    } else {
+                        arg.to_token_stream()
+                    }
+                });
+                quote!(#func(#(#args),*))
+            }
+            _ => field,
+        }
+    }
+
+    /// Bind the result of a call to a local, so that it is only evaluated once per side.
+    ///
+    /// Returns the statements binding the result for `self` and `other`, and the key accessing the locals.
+    pub fn hoisted(&self) -> Option<(TokenStream, Key)> {
+        if self.in_variant || !matches!(self.expr, Expr::Call(_) | Expr::MethodCall(_)) {
+            return None;
+        }
+        let call = Key {
+            modifiers: Modifiers::default(),
+            ..self.clone()
+        };
+        // Locals are accessed like the bindings of variant fields, as references.
+        let hoisted = Key {
+            expr: parse_quote!(key),
+            in_variant: true,
+            ..self.clone()
+        };
+        let (this, other) = (quote!(self), quote!(other));
+        let (this_call, other_call) = (call.reference(&this), call.reference(&other));
+        let (this_local, other_local) = (hoisted.binding(&this), hoisted.binding(&other));
+        Some((
+            quote! {
+                let #this_local = #this_call;
+                let #other_local = #other_call;
+            },
+            hoisted,
+        ))
+    }
+
+    /// Whether [`Key::value`] computes a new value from the field, rather than accessing it.
+    fn is_computed(&self) -> bool {
+        self.modifiers.by_len.is_some()
+            || self.modifiers.by_display.is_some()
+            || self.modifiers.abs.is_some()
+            || self.modifiers.ordered_float.is_some()
+            || self.modifiers.cast.is_some()
+    }
+
+    /// Whether the access is the binding of a variant field itself, which is already a reference.
+    fn is_binding(&self) -> bool {
+        self.in_variant
+            && self.modifiers.cache.is_none()
+            && self.modifiers.index.is_none()
+            && !matches!(self.expr, Expr::Field(_))
+    }
+
+    /// The field at the root of the key, e.g. `a` for `a.b`.
+    fn member(&self) -> TokenTree {
+        self.expr
+            .to_token_stream()
+            .into_iter()
+            .next()
+            .expect("keys of fields start with their member")
+    }
+
+    /// Name of the variable the field of a variant is bound to when matching `this`, e.g. `other_a`.
+    fn binding(&self, this: &TokenStream) -> Ident {
+        let member = self.member().to_string();
+        format_ident!("{}_{}", this.to_string(), member.trim_start_matches("r#"))
+    }
+}
+
+/// The first `self` in `tokens`, if any.
+fn find_self(tokens: TokenStream) -> Option<Ident> {
+    tokens.into_iter().find_map(|token| match token {
+        TokenTree::Ident(ident) if ident == "self" => Some(ident),
+        TokenTree::Group(group) => find_self(group.stream()),
+        _ => None,
+    })
+}
+
+fn is_self(expr: &Expr) -> bool {
+    is_ident(expr, "self")
+}
+
+/// Modifiers of a field-level attribute, e.g. `#[cmp_by(reverse)]`.
+///
+/// Flags keep the span of their keyword so derives can report misuse precisely.
+#[derive(Default, Clone)]
+pub struct Modifiers {
+    pub reverse: Option<Span>,
+    /// Comparison function, from `with = "path::to::cmp"`.
+    pub with: Option<Path>,
+    /// Collation function receiving the field as `&str`, from `collate_with = "path::to::collate"`.
+    pub collate_with: Option<Path>,
+    /// Key extraction function, from `key = "path::to::key"`.
+    pub key: Option<Path>,
+    /// Key extraction closure, from `map = |value| ...`.
+    pub map: Option<ExprClosure>,
+    pub none_last: Option<Span>,
+    /// Only compare `Option`s when both are `Some`, from `both_some_only`.
+    pub both_some_only: Option<Span>,
+    /// Compare `Result`s by their `Ok` value, `Err` being `"greater"` or `"less"` than every `Ok`,
+    /// from `on_err = "greater"`.
+    pub on_err: Option<LitStr>,
+    /// Only compare the field for equality, not for ordering, from `eq`.
+    pub eq: Option<Span>,
+    /// Compare floats with `total_cmp`, from `total`.
+    pub total: Option<Span>,
+    /// Field of `self` holding the comparison function, from `use_comparator = "field"` at the top level.
+    pub comparator: Option<Expr>,
+    /// Compare floats within the tolerance as equal, from `epsilon = 0.001`.
+    pub epsilon: Option<LitFloat>,
+    /// Compare a `PartialOrd` value with `partial_cmp`, panicking when it isn't comparable from `assume_total = "panic"`,
+    /// considering it equal from `assume_total = "equal"`, or panicking in debug builds only
+    /// from `assume_total = "debug_checked"`.
+    pub assume_total: Option<LitStr>,
+    /// Compare the value the field points to, from `deref`.
+    pub deref: Option<Span>,
+    /// Compare strings ignoring ASCII case, from `case_insensitive`.
+    pub case_insensitive: Option<Span>,
+    /// Compare an element or a subslice of the field, from `index = 0` or `index_range = "0..4"`.
+    pub index: Option<Expr>,
+    /// Compare the `len()` of the field, from `by_len`.
+    pub by_len: Option<Span>,
+    /// Compare the field rendered with `Display`, from `by_display`.
+    pub by_display: Option<Span>,
+    /// Compare the `abs()` of the field, from `abs`.
+    pub abs: Option<Span>,
+    /// Compare the field converted with `as`, from `as = "u64"`.
+    pub cast: Option<Type>,
+    /// Compare the items of a collection one by one up to the shorter length, from the start with `lex = "prefix"`
+    /// or from the end with `lex = "suffix"`.
+    pub lex: Option<LitStr>,
+    /// Compare the items of an unordered collection once sorted, from `as_sorted`.
+    pub as_sorted: Option<Span>,
+    /// Compare the entries of an `indexmap::IndexMap` in insertion order, from `as_pairs`.
+    pub as_pairs: Option<Span>,
+    /// Compare floats wrapped in `ordered_float::OrderedFloat`, from `ordered_float`.
+    pub ordered_float: Option<Span>,
+    /// Hash the bytes of a `bytemuck::Pod` field, from `bytes`.
+    pub bytes: Option<Span>,
+    /// Write integers with the `Hasher::write_*` method of their type, from `raw`.
+    pub raw: Option<Span>,
+    /// Leave the field out when all fields are used, from `skip`.
+    pub skip: Option<Span>,
+    /// Priority of the field, from `order = 0`.
+    pub order: Option<LitInt>,
+    /// Function computing the key stored in a `OnceCell` field, from `cache = "path::to::key"`.
+    pub cache: Option<Path>,
+}
+
+impl Modifiers {
+    fn parse(attr: &Attribute) -> Result<Self, Error> {
+        let mut modifiers = Modifiers::default();
+        match &attr.meta {
+            Meta::Path(_) => {}
+            Meta::List(_) => attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("reverse") {
+                    modifiers.reverse = Some(meta.path.span());
+                    Ok(())
+                } else if meta.path.is_ident("none_last") {
+                    modifiers.none_last = Some(meta.path.span());
+                    Ok(())
+                } else if meta.path.is_ident("on_err") {
+                    let on_err: LitStr = meta.value()?.parse()?;
+                    if !["greater", "less"].contains(&on_err.value().as_str()) {
+                        return Err(Error::new(
+                            on_err.span(),
+                            r#"expected `on_err = "greater"` or `on_err = "less"`"#,
+                        ));
+                    }
+                    modifiers.on_err = Some(on_err);
+                    Ok(())
+                } else if meta.path.is_ident("eq") {
+                    modifiers.eq = Some(meta.path.span());
+                    Ok(())
+                } else if meta.path.is_ident("both_some_only") {
+                    modifiers.both_some_only = Some(meta.path.span());
+                    Ok(())
+                } else if meta.path.is_ident("total") {
+                    modifiers.total = Some(meta.path.span());
+                    Ok(())
+                } else if meta.path.is_ident("epsilon") {
+                    modifiers.epsilon = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("assume_total") {
+                    let assume_total: LitStr = meta.value()?.parse()?;
+                    if !["panic", "equal", "debug_checked"].contains(&assume_total.value().as_str()) {
+                        return Err(Error::new(
+                            assume_total.span(),
+                            r#"expected `assume_total = "panic"`, `assume_total = "equal"` or `assume_total = "debug_checked"`"#,
+                        ));
+                    }
+                    modifiers.assume_total = Some(assume_total);
+                    Ok(())
+                } else if meta.path.is_ident("cache") {
+                    modifiers.cache = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("order") {
+                    modifiers.order = Some(meta.value()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("skip") {
+                    modifiers.skip = Some(meta.path.span());
+                    Ok(())
+                } else if meta.path.is_ident("ordered_float") {
+                    if cfg!(feature = "ordered-float") {
+                        modifiers.ordered_float = Some(meta.path.span());
+                        Ok(())
+                    } else {
+                        Err(meta.error(
+                            "`ordered_float` requires the `ordered-float` feature of cmp_by_derive",
+                        ))
+                    }
+                } else if meta.path.is_ident("as_pairs") {
+                    if cfg!(feature = "indexmap") {
+                        modifiers.as_pairs = Some(meta.path.span());
+                        Ok(())
+                    } else {
+                        Err(meta
+                            .error("`as_pairs` requires the `indexmap` feature of cmp_by_derive"))
+                    }
+                } else if meta.path.is_ident("bytes") {
+                    if cfg!(feature = "bytemuck") {
+                        modifiers.bytes = Some(meta.path.span());
+                        Ok(())
+                    } else {
+                        Err(meta.error("`bytes` requires the `bytemuck` feature of cmp_by_derive"))
+                    }
+                } else if meta.path.is_ident("raw") {
+                    modifiers.raw = Some(meta.path.span());
+                    Ok(())
+                } else if meta.path.is_ident("index") || meta.path.is_ident("index_range") {
+                    if modifiers.index.is_some() {
+                        return Err(meta.error("expected a single `index` or `index_range`"));
+                    }
+                    let value = meta.value()?;
+                    modifiers.index = Some(if meta.path.is_ident("index") {
+                        Expr::Lit(ExprLit {
+                            attrs: Vec::new(),
+                            lit: Lit::Int(value.parse()?),
+                        })
+                    } else {
+                        Expr::Range(value.parse::<LitStr>()?.parse()?)
+                    });
+                    Ok(())
+                } else if meta.path.is_ident("abs") {
+                    modifiers.abs = Some(meta.path.span());
+                    Ok(())
+                } else if meta.path.is_ident("as") {
+                    modifiers.cast = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("by_len") {
+                    modifiers.by_len = Some(meta.path.span());
+                    Ok(())
+                } else if meta.path.is_ident("by_display") {
+                    modifiers.by_display = Some(meta.path.span());
+                    Ok(())
+                } else if meta.path.is_ident("lex") {
+                    let lex: LitStr = meta.value()?.parse()?;
+                    if !["prefix", "suffix"].contains(&lex.value().as_str()) {
+                        return Err(Error::new(
+                            lex.span(),
+                            r#"expected `lex = "prefix"` or `lex = "suffix"`"#,
+                        ));
+                    }
+                    modifiers.lex = Some(lex);
+                    Ok(())
+                } else if meta.path.is_ident("as_sorted") {
+                    modifiers.as_sorted = Some(meta.path.span());
+                    Ok(())
+                } else if meta.path.is_ident("case_insensitive") {
+                    modifiers.case_insensitive = Some(meta.path.span());
+                    Ok(())
+                } else if meta.path.is_ident("deref") {
+                    modifiers.deref = Some(meta.path.span());
+                    Ok(())
+                } else if meta.path.is_ident("with") {
+                    modifiers.with = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("collate_with") {
+                    modifiers.collate_with = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("key") {
+                    modifiers.key = Some(meta.value()?.parse::<LitStr>()?.parse()?);
+                    Ok(())
+                } else if meta.path.is_ident("map") {
+                    let map: ExprClosure = meta.value()?.parse()?;
+                    if let Some(this) = find_self(map.body.to_token_stream()) {
+                        return Err(Error::new(
+                            this.span(),
+                            "`map` only receives the field, `self` can't be used in the closure",
+                        ));
+                    }
+                    modifiers.map = Some(map);
+                    Ok(())
+                } else {
+                    Err(meta.error(format!(
+                        "unsupported modifier `{}`",
+                        meta.path.to_token_stream()
+                    )))
+                }
+            })?,
+            // The nested path of `#[attr = "field.path"]` is parsed with the field.
+            Meta::NameValue(_) => {}
+        }
+        if let (Some(_), Some(key)) = (&modifiers.with, &modifiers.key) {
+            return Err(Error::new(
+                key.span(),
+                "`key` can't be combined with `with`, the comparison function receives the field itself",
+            ));
+        }
+        if let Some(map) = &modifiers.map {
+            let conflict = match (
+                &modifiers.with,
+                &modifiers.key,
+                modifiers.total,
+                modifiers.case_insensitive,
+            ) {
+                (Some(_), _, _, _) => Some("with"),
+                (None, Some(_), _, _) => Some("key"),
+                (None, None, Some(_), _) => Some("total"),
+                (None, None, None, Some(_)) => Some("case_insensitive"),
+                (None, None, None, None) => None,
+            };
+            if let Some(conflict) = conflict {
+                return Err(Error::new(
+                    map.span(),
+                    format!("`map` can't be combined with `{conflict}`, the results of the closure are compared with `Ord`"),
+                ));
+            }
+        }
+        if let (Some(_), Some(total)) = (&modifiers.with, modifiers.total) {
+            return Err(Error::new(
+                total,
+                "`total` can't be combined with `with`, the comparison function is used instead of `total_cmp`",
+            ));
+        }
+        if let Some(skip) = modifiers.skip {
+            let used = [
+                modifiers.reverse,
+                modifiers.none_last,
+                modifiers.both_some_only,
+                modifiers.eq,
+                modifiers.total,
+                modifiers.deref,
+                modifiers.case_insensitive,
+                modifiers.by_len,
+                modifiers.by_display,
+                modifiers.abs,
+                modifiers.ordered_float,
+                modifiers.bytes,
+                modifiers.raw,
+                modifiers.as_sorted,
+                modifiers.as_pairs,
+            ]
+            .iter()
+            .any(Option::is_some)
+                || modifiers.with.is_some()
+                || modifiers.collate_with.is_some()
+                || modifiers.key.is_some()
+                || modifiers.map.is_some()
+                || modifiers.index.is_some()
+                || modifiers.order.is_some()
+                || modifiers.on_err.is_some()
+                || modifiers.epsilon.is_some()
+                || modifiers.assume_total.is_some()
+                || modifiers.cast.is_some()
+                || modifiers.lex.is_some()
+                || modifiers.cache.is_some();
+            if used {
+                return Err(Error::new(
+                    skip,
+                    "`skip` can't be combined with other modifiers, the field isn't used",
+                ));
+            }
+        }
+        if let Some(eq) = modifiers.eq {
+            let conflict = match (modifiers.reverse, modifiers.none_last, &modifiers.order) {
+                (Some(_), _, _) => Some("reverse"),
+                (None, Some(_), _) => Some("none_last"),
+                (None, None, Some(_)) => Some("order"),
+                (None, None, None) => None,
+            };
+            if let Some(conflict) = conflict {
+                return Err(Error::new(
+                    eq,
+                    format!("`eq` can't be combined with `{conflict}`, the field isn't ordered"),
+                ));
+            }
+        }
+        if let (Some(_), Some(both_some_only)) = (modifiers.none_last, modifiers.both_some_only) {
+            return Err(Error::new(
+                both_some_only,
+                "`both_some_only` can't be combined with `none_last`, `None` compares equal to every value",
+            ));
+        }
+        if let Some(case_insensitive) = modifiers.case_insensitive {
+            let conflict = match (&modifiers.with, &modifiers.key, modifiers.total) {
+                (Some(_), _, _) => Some("with"),
+                (None, Some(_), _) => Some("key"),
+                (None, None, Some(_)) => Some("total"),
+                (None, None, None) => None,
+            };
+            if let Some(conflict) = conflict {
+                return Err(Error::new(
+                    case_insensitive,
+                    format!("`case_insensitive` can't be combined with `{conflict}`, it only applies to strings"),
+                ));
+            }
+        }
+        if let Some(abs) = modifiers.abs {
+            let conflict = [
+                (modifiers.cast.as_ref().map(Spanned::span), "as"),
+                (modifiers.case_insensitive, "case_insensitive"),
+                (modifiers.by_len, "by_len"),
+                (modifiers.ordered_float, "ordered_float"),
+                (modifiers.as_sorted, "as_sorted"),
+                (modifiers.bytes, "bytes"),
+            ]
+            .into_iter()
+            .find_map(|(span, name)| span.map(|_| name));
+            if let Some(conflict) = conflict {
+                return Err(Error::new(
+                    abs,
+                    format!("`abs` can't be combined with `{conflict}`, the absolute value is compared instead"),
+                ));
+            }
+        }
+        if let Some(cast) = &modifiers.cast {
+            let conflict = [
+                (modifiers.case_insensitive, "case_insensitive"),
+                (modifiers.by_len, "by_len"),
+                (modifiers.ordered_float, "ordered_float"),
+                (modifiers.as_sorted, "as_sorted"),
+                (modifiers.bytes, "bytes"),
+            ]
+            .into_iter()
+            .find_map(|(span, name)| span.map(|_| name));
+            if let Some(conflict) = conflict {
+                return Err(Error::new(
+                    cast.span(),
+                    format!("`as` can't be combined with `{conflict}`, the converted value is compared instead"),
+                ));
+            }
+        }
+        if let Some(by_len) = modifiers.by_len {
+            let conflict = match (&modifiers.with, modifiers.total, modifiers.case_insensitive) {
+                (Some(_), _, _) => Some("with"),
+                (None, Some(_), _) => Some("total"),
+                (None, None, Some(_)) => Some("case_insensitive"),
+                (None, None, None) => None,
+            };
+            if let Some(conflict) = conflict {
+                return Err(Error::new(
+                    by_len,
+                    format!("`by_len` can't be combined with `{conflict}`, the length is compared instead"),
+                ));
+            }
+        }
+        if let Some(ordered_float) = modifiers.ordered_float {
+            let conflict = match (
+                &modifiers.with,
+                modifiers.total,
+                modifiers.case_insensitive,
+                modifiers.by_len,
+            ) {
+                (Some(_), _, _, _) => Some("with"),
+                (None, Some(_), _, _) => Some("total"),
+                (None, None, Some(_), _) => Some("case_insensitive"),
+                (None, None, None, Some(_)) => Some("by_len"),
+                (None, None, None, None) => None,
+            };
+            if let Some(conflict) = conflict {
+                return Err(Error::new(
+                    ordered_float,
+                    format!("`ordered_float` can't be combined with `{conflict}`, the wrapped float is compared instead"),
+                ));
+            }
+        }
+        if let Some(on_err) = &modifiers.on_err {
+            let conflict = [
+                (modifiers.with.as_ref().map(Spanned::span), "with"),
+                (modifiers.key.as_ref().map(Spanned::span), "key"),
+                (modifiers.map.as_ref().map(Spanned::span), "map"),
+                (modifiers.none_last, "none_last"),
+                (modifiers.both_some_only, "both_some_only"),
+                (modifiers.total, "total"),
+                (modifiers.case_insensitive, "case_insensitive"),
+                (modifiers.by_len, "by_len"),
+                (modifiers.ordered_float, "ordered_float"),
+                (modifiers.as_sorted, "as_sorted"),
+            ]
+            .into_iter()
+            .find_map(|(span, name)| span.map(|_| name));
+            if let Some(conflict) = conflict {
+                return Err(Error::new(
+                    on_err.span(),
+                    format!("`on_err` can't be combined with `{conflict}`, the `Ok` values are compared as they are"),
+                ));
+            }
+        }
+        if let Some(epsilon) = &modifiers.epsilon {
+            let conflict = [
+                (modifiers.with.as_ref().map(Spanned::span), "with"),
+                (modifiers.key.as_ref().map(Spanned::span), "key"),
+                (modifiers.map.as_ref().map(Spanned::span), "map"),
+                (modifiers.on_err.as_ref().map(Spanned::span), "on_err"),
+                (modifiers.none_last, "none_last"),
+                (modifiers.both_some_only, "both_some_only"),
+                (modifiers.total, "total"),
+                (modifiers.case_insensitive, "case_insensitive"),
+                (modifiers.by_len, "by_len"),
+                (modifiers.ordered_float, "ordered_float"),
+                (modifiers.as_sorted, "as_sorted"),
+            ]
+            .into_iter()
+            .find_map(|(span, name)| span.map(|_| name));
+            if let Some(conflict) = conflict {
+                return Err(Error::new(
+                    epsilon.span(),
+                    format!(
+                        "`epsilon` can't be combined with `{conflict}`, it only applies to floats"
+                    ),
+                ));
+            }
+        }
+        if let Some(assume_total) = &modifiers.assume_total {
+            let conflict = [
+                (modifiers.with.as_ref().map(Spanned::span), "with"),
+                (modifiers.key.as_ref().map(Spanned::span), "key"),
+                (modifiers.map.as_ref().map(Spanned::span), "map"),
+                (modifiers.on_err.as_ref().map(Spanned::span), "on_err"),
+                (modifiers.epsilon.as_ref().map(Spanned::span), "epsilon"),
+                (modifiers.total, "total"),
+                (modifiers.case_insensitive, "case_insensitive"),
+                (modifiers.by_len, "by_len"),
+                (modifiers.ordered_float, "ordered_float"),
+                (modifiers.as_sorted, "as_sorted"),
+            ]
+            .into_iter()
+            .find_map(|(span, name)| span.map(|_| name));
+            if let Some(conflict) = conflict {
+                return Err(Error::new(
+                    assume_total.span(),
+                    format!("`assume_total` can't be combined with `{conflict}`, the value is compared with `partial_cmp`"),
+                ));
+            }
+        }
+        if let Some(as_sorted) = modifiers.as_sorted {
+            let conflict = [
+                (modifiers.with.as_ref().map(Spanned::span), "with"),
+                (modifiers.key.as_ref().map(Spanned::span), "key"),
+                (modifiers.map.as_ref().map(Spanned::span), "map"),
+                (modifiers.total, "total"),
+                (modifiers.case_insensitive, "case_insensitive"),
+                (modifiers.by_len, "by_len"),
+                (modifiers.ordered_float, "ordered_float"),
+                (modifiers.bytes, "bytes"),
+            ]
+            .into_iter()
+            .find_map(|(span, name)| span.map(|_| name));
+            if let Some(conflict) = conflict {
+                return Err(Error::new(
+                    as_sorted,
+                    format!("`as_sorted` can't be combined with `{conflict}`, the sorted items are compared instead"),
+                ));
+            }
+        }
+        if let Some(by_display) = modifiers.by_display {
+            let conflict = [
+                (modifiers.with.as_ref().map(Spanned::span), "with"),
+                (modifiers.key.as_ref().map(Spanned::span), "key"),
+                (modifiers.map.as_ref().map(Spanned::span), "map"),
+                (modifiers.cast.as_ref().map(Spanned::span), "as"),
+                (modifiers.on_err.as_ref().map(Spanned::span), "on_err"),
+                (modifiers.epsilon.as_ref().map(Spanned::span), "epsilon"),
+                (
+                    modifiers.assume_total.as_ref().map(Spanned::span),
+                    "assume_total",
+                ),
+                (modifiers.lex.as_ref().map(Spanned::span), "lex"),
+                (modifiers.total, "total"),
+                (modifiers.by_len, "by_len"),
+                (modifiers.abs, "abs"),
+                (modifiers.ordered_float, "ordered_float"),
+                (modifiers.as_sorted, "as_sorted"),
+                (modifiers.as_pairs, "as_pairs"),
+                (modifiers.bytes, "bytes"),
+                (modifiers.raw, "raw"),
+            ]
+            .into_iter()
+            .find_map(|(span, name)| span.map(|_| name));
+            if let Some(conflict) = conflict {
+                return Err(Error::new(
+                    by_display,
+                    format!("`by_display` can't be combined with `{conflict}`, the rendered string is compared instead"),
+                ));
+            }
+        }
+        if let Some(collate_with) = &modifiers.collate_with {
+            let conflict = [
+                (modifiers.with.as_ref().map(Spanned::span), "with"),
+                (modifiers.key.as_ref().map(Spanned::span), "key"),
+                (modifiers.map.as_ref().map(Spanned::span), "map"),
+                (modifiers.cast.as_ref().map(Spanned::span), "as"),
+                (modifiers.on_err.as_ref().map(Spanned::span), "on_err"),
+                (modifiers.epsilon.as_ref().map(Spanned::span), "epsilon"),
+                (
+                    modifiers.assume_total.as_ref().map(Spanned::span),
+                    "assume_total",
+                ),
+                (modifiers.lex.as_ref().map(Spanned::span), "lex"),
+                (modifiers.total, "total"),
+                (modifiers.case_insensitive, "case_insensitive"),
+                (modifiers.by_len, "by_len"),
+                (modifiers.by_display, "by_display"),
+                (modifiers.abs, "abs"),
+                (modifiers.ordered_float, "ordered_float"),
+                (modifiers.as_sorted, "as_sorted"),
+                (modifiers.as_pairs, "as_pairs"),
+                (modifiers.bytes, "bytes"),
+                (modifiers.raw, "raw"),
+            ]
+            .into_iter()
+            .find_map(|(span, name)| span.map(|_| name));
+            if let Some(conflict) = conflict {
+                return Err(Error::new(
+                    collate_with.span(),
+                    format!("`collate_with` can't be combined with `{conflict}`, the strings are compared with the collation function instead"),
+                ));
+            }
+        }
+        if let Some(lex) = &modifiers.lex {
+            let conflict = [
+                (modifiers.with.as_ref().map(Spanned::span), "with"),
+                (modifiers.key.as_ref().map(Spanned::span), "key"),
+                (modifiers.map.as_ref().map(Spanned::span), "map"),
+                (modifiers.cast.as_ref().map(Spanned::span), "as"),
+                (modifiers.on_err.as_ref().map(Spanned::span), "on_err"),
+                (modifiers.epsilon.as_ref().map(Spanned::span), "epsilon"),
+                (
+                    modifiers.assume_total.as_ref().map(Spanned::span),
+                    "assume_total",
+                ),
+                (modifiers.none_last, "none_last"),
+                (modifiers.both_some_only, "both_some_only"),
+                (modifiers.total, "total"),
+                (modifiers.case_insensitive, "case_insensitive"),
+                (modifiers.by_len, "by_len"),
+                (modifiers.abs, "abs"),
+                (modifiers.ordered_float, "ordered_float"),
+                (modifiers.as_sorted, "as_sorted"),
+                (modifiers.as_pairs, "as_pairs"),
+            ]
+            .into_iter()
+            .find_map(|(span, name)| span.map(|_| name));
+            if let Some(conflict) = conflict {
+                return Err(Error::new(
+                    lex.span(),
+                    format!("`lex` can't be combined with `{conflict}`, the items are compared one by one instead"),
+                ));
+            }
+        }
+        if let Some(as_pairs) = modifiers.as_pairs {
+            let conflict = [
+                (modifiers.with.as_ref().map(Spanned::span), "with"),
+                (modifiers.key.as_ref().map(Spanned::span), "key"),
+                (modifiers.map.as_ref().map(Spanned::span), "map"),
+                (modifiers.cast.as_ref().map(Spanned::span), "as"),
+                (modifiers.total, "total"),
+                (modifiers.case_insensitive, "case_insensitive"),
+                (modifiers.by_len, "by_len"),
+                (modifiers.abs, "abs"),
+                (modifiers.ordered_float, "ordered_float"),
+                (modifiers.as_sorted, "as_sorted"),
+                (modifiers.bytes, "bytes"),
+            ]
+            .into_iter()
+            .find_map(|(span, name)| span.map(|_| name));
+            if let Some(conflict) = conflict {
+                return Err(Error::new(
+                    as_pairs,
+                    format!("`as_pairs` can't be combined with `{conflict}`, the entries are compared in insertion order instead"),
+                ));
+            }
+        }
+        if let Some(bytes) = modifiers.bytes {
+            let conflict = match (
+                &modifiers.with,
+                modifiers.case_insensitive,
+                modifiers.by_len,
+                modifiers.ordered_float,
+            ) {
+                (Some(_), _, _, _) => Some("with"),
+                (None, Some(_), _, _) => Some("case_insensitive"),
+                (None, None, Some(_), _) => Some("by_len"),
+                (None, None, None, Some(_)) => Some("ordered_float"),
+                (None, None, None, None) => None,
+            };
+            if let Some(conflict) = conflict {
+                return Err(Error::new(
+                    bytes,
+                    format!("`bytes` can't be combined with `{conflict}`, the bytes of the field are hashed instead"),
+                ));
+            }
+        }
+        if let Some(raw) = modifiers.raw {
+            let conflict = [
+                (modifiers.with.as_ref().map(Spanned::span), "with"),
+                (modifiers.case_insensitive, "case_insensitive"),
+                (modifiers.ordered_float, "ordered_float"),
+                (modifiers.as_sorted, "as_sorted"),
+                (modifiers.bytes, "bytes"),
+            ]
+            .into_iter()
+            .find_map(|(span, name)| span.map(|_| name));
+            if let Some(conflict) = conflict {
+                return Err(Error::new(
+                    raw,
+                    format!("`raw` can't be combined with `{conflict}`, the integer is written to the hasher as it is"),
+                ));
+            }
+        }
+        Ok(modifiers)
+    }
+
+    /// The modifiers that only apply to `HashBy`, with their name.
+    pub fn hash_by_only(&self) -> impl Iterator<Item = (Span, &'static str)> {
+        [(self.bytes, "bytes"), (self.raw, "raw")]
+            .into_iter()
+            .filter_map(|(span, name)| span.map(|span| (span, name)))
+    }
+}
+
+/// Flags of the top-level attribute, e.g. `#[cmp_by(descending)]`.
+#[derive(Default)]
+pub struct Flags {
+    pub descending: Option<Span>,
+    pub verify_eq: Option<Span>,
+    /// Leave the variant out of the hash of enums, from `no_discriminant`.
+    pub no_discriminant: Option<Span>,
+    /// Use all the fields that aren't skipped, from `all`.
+    pub all: Option<Span>,
+    /// Use the single field of a newtype, from `transparent`.
+    pub transparent: Option<Span>,
+    /// Use the fields whose name starts with the prefix, from `prefix = "k_"`.
+    pub prefix: Option<LitStr>,
+    /// Compare the `f32` and `f64` fields with `total_cmp`, from `floats = "total"`.
+    pub floats: Option<LitStr>,
+    /// Generate a `cmp_key` method returning the compared items, from `gen_key`.
+    pub gen_key: Option<Span>,
+    /// Generate a `min_heap_key` method returning the reversed compared items, from `gen_min_heap_key`.
+    pub gen_min_heap_key: Option<Span>,
+    /// Implement the `SortKey` trait in scope, returning references to the compared fields, from `impl_sort_key`.
+    pub impl_sort_key: Option<Span>,
+    /// Compare the tuples of the compared items instead of chaining their comparisons, from `tuple_cmp`.
+    pub tuple_cmp: Option<Span>,
+    /// Generate a `const_cmp` method comparing like `Ord::cmp` in const contexts, from `const_fn`.
+    pub const_fn: Option<Span>,
+    /// Inlining hint of the generated methods, from `inline = "always"`.
+    pub inline: Option<LitStr>,
+    /// Type the single compared item is borrowed as to be compared to it, from `borrow_key = "Type"`.
+    pub borrow_key: Option<LitStr>,
+    /// Only implement `Ord` and `PartialOrd`, from `ord_only`.
+    pub ord_only: Option<Span>,
+    /// Only implement `Eq` and `PartialEq`, from `eq_only`.
+    pub eq_only: Option<Span>,
+    /// Name of a wrapper ordering the type in reverse, from `gen_reverse = "Name"`.
+    pub gen_reverse: Option<LitStr>,
+    /// Written to the hasher before the hashed items, from `tag = 0xAB` or `tag = "Name"`.
+    pub tag: Option<Lit>,
+}
+
+impl Flags {
+    /// Record `elem` if it is a flag, returns whether it was one.
+    fn parse(&mut self, elem: &Expr) -> bool {
+        if let Expr::Assign(ExprAssign { left, right, .. }) = elem {
+            // The kind of literal is checked by `HashBy`.
+            if is_ident(left, "tag") {
+                let Expr::Lit(ExprLit { lit, .. }) = &**right else {
+                    return false;
+                };
+                self.tag = Some(lit.clone());
+                return true;
+            }
+            let option = if is_ident(left, "inline") {
+                &mut self.inline
+            } else if is_ident(left, "borrow_key") {
+                &mut self.borrow_key
+            } else if is_ident(left, "prefix") {
+                &mut self.prefix
+            } else if is_ident(left, "floats") {
+                &mut self.floats
+            } else if is_ident(left, "gen_reverse") {
+                &mut self.gen_reverse
+            } else {
+                return false;
+            };
+            if let Expr::Lit(ExprLit {
+                lit: Lit::Str(value),
+                ..
+            }) = &**right
+            {
+                *option = Some(value.clone());
+                return true;
+            }
+        }
+        let Expr::Path(ExprPath { path, .. }) = elem else {
+            return false;
+        };
+        if path.is_ident("descending") {
+            self.descending = Some(path.span());
+            true
+        } else if path.is_ident("all") {
+            self.all = Some(path.span());
+            true
+        } else if path.is_ident("transparent") {
+            self.transparent = Some(path.span());
+            true
+        } else if path.is_ident("gen_key") {
+            self.gen_key = Some(path.span());
+            true
+        } else if path.is_ident("gen_min_heap_key") {
+            self.gen_min_heap_key = Some(path.span());
+            true
+        } else if path.is_ident("impl_sort_key") {
+            self.impl_sort_key = Some(path.span());
+            true
+        } else if path.is_ident("const_fn") {
+            self.const_fn = Some(path.span());
+            true
+        } else if path.is_ident("tuple_cmp") {
+            self.tuple_cmp = Some(path.span());
+            true
+        } else if path.is_ident("verify_eq") {
+            self.verify_eq = Some(path.span());
+            true
+        } else if path.is_ident("no_discriminant") {
+            self.no_discriminant = Some(path.span());
+            true
+        } else if path.is_ident("ord_only") {
+            self.ord_only = Some(path.span());
+            true
+        } else if path.is_ident("eq_only") {
+            self.eq_only = Some(path.span());
+            true
+        } else {
+            false
+        }
+    }
+
+    /// The flags generating key methods, with their name.
+    pub fn key_methods(&self) -> impl Iterator<Item = (Span, &'static str)> {
+        [
+            (self.gen_key, "gen_key"),
+            (self.gen_min_heap_key, "gen_min_heap_key"),
+        ]
+        .into_iter()
+        .filter_map(|(span, name)| span.map(|span| (span, name)))
+    }
+
+    /// The flags that only apply to `CmpBy`, with their name.
+    pub fn cmp_by_only(&self) -> impl Iterator<Item = (Span, &'static str)> {
+        let gen_reverse = self.gen_reverse.as_ref().map(LitStr::span);
+        self.key_methods().chain(
+            [
+                (self.tuple_cmp, "tuple_cmp"),
+                (self.const_fn, "const_fn"),
+                (self.impl_sort_key, "impl_sort_key"),
+                (self.ord_only, "ord_only"),
+                (self.eq_only, "eq_only"),
+                (gen_reverse, "gen_reverse"),
+            ]
+            .into_iter()
+            .filter_map(|(span, name)| span.map(|span| (span, name))),
+        )
+    }
+
+    /// The flags that only apply to `HashBy`, with their name.
+    pub fn hash_by_only(&self) -> impl Iterator<Item = (Span, &'static str)> {
+        let tag = self.tag.as_ref().map(Lit::span);
+        [
+            (self.verify_eq, "verify_eq"),
+            (self.no_discriminant, "no_discriminant"),
+            (tag, "tag"),
+        ]
+        .into_iter()
+        .filter_map(|(span, name)| span.map(|span| (span, name)))
+    }
+
+    /// The inline attribute of the generated methods, `#[inline(always)]` from `inline = "always"`.
+    pub fn inline(&self) -> Result<TokenStream, Error> {
+        match &self.inline {
+            None => Ok(quote!(#[inline])),
+            Some(value) if value.value() == "always" => Ok(quote!(#[inline(always)])),
+            Some(value) => Err(Error::new(value.span(), r#"expected `inline = "always"`"#)),
+        }
+    }
+}
+
+/// Comparison against another type, from `#[cmp_by(other = "Type", key = "item")]`,
+/// or `#[cmp_by(borrow_key = "Type")]`.
+pub struct OtherComparison {
+    pub ty: Type,
+    /// Item of `self` compared to the value of the other type.
+    pub key: Key,
+    /// Whether the item is borrowed as the other type with `Borrow` to be compared.
+    pub borrow: bool,
+}
+
+impl OtherComparison {
+    /// The value compared to `other`, accessed from `self`.
+    pub fn value(&self) -> TokenStream {
+        if self.borrow {
+            let (ty, reference) = (&self.ty, self.key.reference(&quote!(self)));
+            quote!(::core::borrow::Borrow::<#ty>::borrow(#reference))
+        } else {
+            self.key.value(&quote!(self))
+        }
+    }
+
+    /// Compare the single compared item to `ty` by borrowing it, from `borrow_key = "ty"`.
+    fn borrow(ty: &LitStr, expressions: &[Key], fields: &ParsedFields) -> Result<Self, Error> {
+        let mut keys = expressions
+            .iter()
+            .filter(|key| key.expr.to_token_stream().to_string() != "_fields")
+            .chain(fields.keys());
+        match (keys.next(), keys.next()) {
+            (Some(key), None) if !key.in_variant => Ok(OtherComparison {
+                ty: ty.parse()?,
+                key: key.clone(),
+                borrow: true,
+            }),
+            _ => Err(Error::new(
+                ty.span(),
+                "`borrow_key` requires a single compared item, the borrowed value can't be compared to several items",
+            )),
+        }
+    }
+
+    fn parse(span: Span, elems: Vec<Expr>) -> Result<Self, Error> {
+        let (mut ty, mut key) = (None, None);
+        for elem in elems {
+            let Expr::Assign(ExprAssign { left, right, .. }) = &elem else {
+                return Err(Error::new(
+                    elem.span(),
+                    r#"expected `other = "Type"` or `key = "item"`"#,
+                ));
+            };
+            let value = match &**right {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Str(value),
+                    ..
+                }) => value,
+                _ => return Err(Error::new(right.span(), "expected a string literal")),
+            };
+            match &**left {
+                Expr::Path(ExprPath { path, .. }) if path.is_ident("other") => {
+                    ty = Some(value.parse()?);
+                }
+                Expr::Path(ExprPath { path, .. }) if path.is_ident("key") => {
+                    key = Some(parse_expression(value.parse()?).map_err(|err| match err {
+                        ParsingError::Error(err) => err,
+                        _ => unreachable!("parse_expression() only returns errors"),
+                    })?);
+                }
+                _ => {
+                    return Err(Error::new(
+                        left.span(),
+                        format!("unsupported option `{}`", left.to_token_stream()),
+                    ))
+                }
+            }
+        }
+        match (ty, key) {
+            (Some(ty), Some(key)) => Ok(OtherComparison {
+                ty,
+                key,
+                borrow: false,
+            }),
+            (_, None) => Err(Error::new(
+                span,
+                r#"expected the item compared to the other type: `key = "item"`"#,
+            )),
+            (None, Some(_)) => unreachable!("only parsed when `other` is declared"),
+        }
+    }
 }
 
 pub struct ParsedInput {
-    pub expressions: Vec<Expr>,
+    pub flags: Flags,
+    pub expressions: Vec<Key>,
     pub fields: ParsedFields,
+    pub others: Vec<OtherComparison>,
     pub generics: Generics,
-    pub generic_arguments: Vec<GenericArgument>,
+}
+
+/// Split the arguments of `attr` on commas and parse each of them as an expression.
+///
+/// Errors span the whole item that failed to parse, rather than the token where parsing stopped.
+fn parse_items(attr: &Attribute) -> Result<Vec<Expr>, Error> {
+    let tokens = attr.meta.require_list()?.tokens.clone();
+    // Nothing is compared through an empty attribute, which is most likely unfinished.
+    if tokens.is_empty() {
+        let name = attr.path().to_token_stream();
+        return Err(Error::new_spanned(
+            attr,
+            format!("empty `{name}` attribute, list the items to compare on, e.g. `#[{name}(field, method())]`, or remove it"),
+        ));
+    }
+    let mut items = vec![TokenStream::new()];
+    let mut commas = Vec::new();
+    // Commas between the generic arguments of a turbofish, e.g. `get::<u8, u16>()`, don't split items.
+    let mut generics_depth = 0usize;
+    let mut previous: [Option<Punct>; 2] = [None, None];
+    for token in tokens {
+        match &token {
+            TokenTree::Punct(punct) if punct.as_char() == ',' && generics_depth == 0 => {
+                commas.push(punct.span());
+                items.push(TokenStream::new());
+                previous = [None, None];
+                continue;
+            }
+            TokenTree::Punct(punct) if punct.as_char() == '<' => {
+                let turbofish = previous
+                    .iter()
+                    .all(|punct| punct.as_ref().is_some_and(|punct| punct.as_char() == ':'));
+                if generics_depth > 0 || turbofish {
+                    generics_depth += 1;
+                }
+            }
+            // The `>` of a `->` return type doesn't close the generics.
+            TokenTree::Punct(punct) if punct.as_char() == '>' && generics_depth > 0 => {
+                let arrow = previous[1].as_ref().is_some_and(|punct| {
+                    punct.as_char() == '-' && punct.spacing() == Spacing::Joint
+                });
+                if !arrow {
+                    generics_depth -= 1;
+                }
+            }
+            _ => {}
+        }
+        previous = match &token {
+            TokenTree::Punct(punct) => [previous[1].take(), Some(punct.clone())],
+            _ => [None, None],
+        };
+        items.last_mut().unwrap().extend([token]);
+    }
+    // A trailing comma is allowed.
+    if items.len() > 1 && items.last().is_some_and(TokenStream::is_empty) {
+        items.pop();
+    }
+    items
+        .into_iter()
+        .enumerate()
+        .map(|(i, item)| {
+            if item.is_empty() {
+                let span = commas.get(i).or(commas.last()).copied();
+                return Err(Error::new(
+                    span.unwrap_or_else(|| attr.span()),
+                    "expected an item before `,`",
+                ));
+            }
+            parse2::<Expr>(item.clone()).map_err(|err| Error::new_spanned(item, err))
+        })
+        .fold(Ok(vec![]), |acc, res| match (acc, res) {
+            (Ok(mut acc), Ok(res)) => {
+                acc.push(res);
+                Ok(acc)
+            }
+            (Err(mut acc), Err(err)) => {
+                acc.combine(err);
+                Err(acc)
+            }
+            (Ok(_), Err(err)) | (Err(err), Ok(_)) => Err(err),
+        })
+}
+
+/// Whether `elem` is `other = "Type"`, which makes its attribute a comparison against another type.
+fn is_other(elem: &Expr) -> bool {
+    matches!(elem, Expr::Assign(ExprAssign { left, .. }) if is_ident(left, "other"))
+}
+
+/// Whether `elem` is `use_comparator = "field"`, which makes its attribute an item compared with that field.
+fn is_comparator(elem: &Expr) -> bool {
+    matches!(elem, Expr::Assign(ExprAssign { left, .. }) if is_ident(left, "use_comparator"))
+}
+
+/// Parse the item compared with the comparator stored in a field of `self`,
+/// from `#[cmp_by(use_comparator = "field", on = "item")]`.
+fn parse_comparator(span: Span, elems: Vec<Expr>) -> Result<Key, Error> {
+    let (mut comparator, mut key) = (None, None);
+    for elem in elems {
+        let Expr::Assign(ExprAssign { left, right, .. }) = &elem else {
+            return Err(Error::new(
+                elem.span(),
+                r#"expected `use_comparator = "field"` or `on = "item"`"#,
+            ));
+        };
+        let value = match &**right {
+            Expr::Lit(ExprLit {
+                lit: Lit::Str(value),
+                ..
+            }) => value,
+            _ => return Err(Error::new(right.span(), "expected a string literal")),
+        };
+        if is_ident(left, "use_comparator") {
+            let field: Expr = value.parse()?;
+            if !matches!(field, Expr::Path(_) | Expr::Field(_)) {
+                return Err(Error::new(
+                    value.span(),
+                    "expected the field of `self` holding the comparator",
+                ));
+            }
+            comparator = Some(field);
+        } else if is_ident(left, "on") {
+            let item = parse_expression(value.parse()?).map_err(|err| match err {
+                ParsingError::Error(err) => err,
+                _ => unreachable!("parse_expression() only returns errors"),
+            })?;
+            if item.expr.to_token_stream().to_string() == "_fields" {
+                return Err(Error::new(
+                    value.span(),
+                    "`_fields` can't be compared with a comparator, mark the fields instead",
+                ));
+            }
+            key = Some(item);
+        } else {
+            return Err(Error::new(
+                left.span(),
+                format!("unsupported option `{}`", left.to_token_stream()),
+            ));
+        }
+    }
+    match (comparator, key) {
+        (Some(comparator), Some(mut key)) => {
+            key.modifiers.comparator = Some(comparator);
+            Ok(key)
+        }
+        (_, None) => Err(Error::new(
+            span,
+            r#"expected the item compared with the comparator: `on = "item"`"#,
+        )),
+        (None, Some(_)) => unreachable!("only parsed when `use_comparator` is declared"),
+    }
+}
+
+fn is_ident(expr: &Expr, ident: &str) -> bool {
+    matches!(expr, Expr::Path(ExprPath { path, .. }) if path.is_ident(ident))
 }
 
 pub(crate) fn parse_input(input: DeriveInput, attr: &str) -> Result<ParsedInput, ParsingError> {
     // println!("Entered parse_input()");
+    let mut flags = Flags::default();
+    let mut others = Vec::new();
     let expressions = input
         .attrs
         .iter()
-        .filter(|i| i.path().get_ident().map_or(false, |i| {
-            i == attr
-        }))
+        .filter(|i| is_attr(i, attr))
         .map(|attr| {
-            attr.parse_args_with(Punctuated::<Expr, Token![,]>::parse_terminated)?.into_iter().map(|elem| {
-                match elem {
-                    Expr::Call(_) | Expr::Field(_) | Expr::Path(_) | Expr::MethodCall(_) => {
-                        // TODO: test paths with lengths other than 1
-                        Ok(elem)
-                    }
-                    _ => {
-                        Err(ParsingError::Error(Error::new(elem.span(), format!("Invalid form: `{}`.\nAllowed forms: `field`, `method()`, `inner.field`, `inner.method()", elem.to_token_stream()))))
-                    }
-                }
-            }).fold(Ok(vec![]), fold_token_errors)
-        }).fold(Ok(vec![]), |acc, res| {
-        match (acc, res) {
+            let elems = parse_items(attr)?;
+            if elems.iter().any(is_other) {
+                others.push(OtherComparison::parse(attr.span(), elems)?);
+                return Ok(vec![]);
+            }
+            if elems.iter().any(is_comparator) {
+                return Ok(vec![parse_comparator(attr.span(), elems)?]);
+            }
+            elems
+                .into_iter()
+                .filter(|elem| !flags.parse(elem))
+                .map(parse_expression)
+                .fold(Ok(vec![]), fold_token_errors)
+        })
+        .fold(Ok(vec![]), |acc, res| match (acc, res) {
             (Ok(mut acc), Ok(res)) => {
                 acc.extend(res);
                 Ok(acc)
@@ -51,29 +1442,99 @@ pub(crate) fn parse_input(input: DeriveInput, attr: &str) -> Result<ParsedInput,
                 Err(acc)
             }
             (Ok(_), Err(err)) | (Err(err), Ok(_)) => Err(err),
-        }
-    })?;
+        })?;
     // println!("Successfully parsed expressions");
+    // The fields are only compared once, at the first `_fields`.
+    if let Some(duplicate) = expressions
+        .iter()
+        .filter(|key| key.expr.to_token_stream().to_string() == "_fields")
+        .nth(1)
+    {
+        return Err(ParsingError::Error(Error::new(
+            duplicate.expr.span(),
+            "`_fields` may appear at most once",
+        )));
+    }
+    if let Some(floats) = flags
+        .floats
+        .as_ref()
+        .filter(|floats| floats.value() != "total")
+    {
+        return Err(ParsingError::Error(Error::new(
+            floats.span(),
+            r#"expected `floats = "total"`"#,
+        )));
+    }
+
+    if let Some(transparent) = flags.transparent {
+        let single_field = match &input.data {
+            Data::Struct(DataStruct { fields, .. }) => fields.len() == 1,
+            _ => false,
+        };
+        if !single_field {
+            return Err(ParsingError::Error(Error::new(
+                transparent,
+                "`transparent` only applies to structs with a single field, it compares by that field",
+            )));
+        }
+        if let Some(key) = expressions.first() {
+            return Err(ParsingError::Error(Error::new(
+                key.expr.span(),
+                "`transparent` can't be combined with top-level items, it only compares by the single field",
+            )));
+        }
+    }
 
     let fields = match input.data {
-        Data::Struct(DataStruct {
-            fields: fields @ (Fields::Unnamed(..) | Fields::Named(..)),
-            ..
-        }) => {
+        Data::Struct(DataStruct { fields, .. }) => {
             // println!("Parsing struct fields");
-            ParsedFields::Struct(parse_fields(&fields, attr)?)
+            match parse_fields(&fields, attr, &flags, &[]) {
+                // Top-level items are enough to compare on.
+                Err(ParsingError::NoField(_)) if !expressions.is_empty() => {
+                    ParsedFields::Struct(Vec::new())
+                }
+                Ok(keys) if keys.is_empty() && expressions.is_empty() => {
+                    return Err(ParsingError::NoField(input.ident.span()));
+                }
+                result => ParsedFields::Struct(result?),
+            }
         }
         Data::Enum(DataEnum { variants, .. }) => {
             // println!("Parsing enum fields");
+            // Variants don't share their positional fields, `self.0` doesn't exist on an enum.
+            if let Some(index) = expressions.iter().find(|key| {
+                matches!(
+                    key.expr,
+                    Expr::Lit(ExprLit {
+                        lit: Lit::Int(_),
+                        ..
+                    })
+                )
+            }) {
+                return Err(ParsingError::Error(Error::new(
+                    index.expr.span(),
+                    "tuple indices can't be used on enums, mark the fields of the variants instead",
+                )));
+            }
             ParsedFields::Enum(
                 variants
                     .into_iter()
                     .map(|variant| -> Result<_, ParsingError> {
-                        let result = match parse_fields(&variant.fields, attr) {
+                        let positions =
+                            parse_positions(&variant, attr).map_err(ParsingError::Error)?;
+                        let mut keys = match parse_fields(&variant.fields, attr, &flags, &positions)
+                        {
                             Ok(f) => f,
                             Err(ParsingError::NoField(_)) => Vec::new(),
                             Err(e) => return Err(e),
                         };
+                        // Listed positions are compared in the order they are listed.
+                        keys.sort_by_key(|key| {
+                            let member = key.member().to_string();
+                            positions
+                                .iter()
+                                .position(|position| position.to_string() == member)
+                        });
                         let field_pat = match variant.fields {
                             Fields::Named(_) => {
                                 quote!({ .. })
@@ -86,40 +1547,278 @@ pub(crate) fn parse_input(input: DeriveInput, attr: &str) -> Result<ParsedInput,
                             }
                         };
 
+                        for key in &mut keys {
+                            key.in_variant = true;
+                        }
                         let ident = variant.ident;
-                        Ok((quote!(Self::#ident #field_pat), result))
+                        Ok(Variant {
+                            pattern: quote!(Self::#ident #field_pat),
+                            path: quote!(Self::#ident),
+                            keys,
+                        })
                     })
                     .fold(Ok(vec![]), fold_token_errors)?,
             )
         }
-        _ => {
-            return Err(ParsingError::Error(Error::new(
-                input.span(),
-                r#"expected an enum or a non-unit struct"#,
-            )));
+        Data::Union(DataUnion { union_token, .. }) => {
+            return Err(ParsingError::Union(union_token.span()));
         }
     };
     // println!("Successfully parsed fields");
-    let generic_arguments = input
-        .generics
-        .params
-        .iter()
-        .flat_map(|p| match p {
-            GenericParam::Type(TypeParam { ident, .. })
-            | GenericParam::Const(ConstParam { ident, .. }) => Some(parse_quote!(#ident)),
-            GenericParam::Lifetime(LifetimeParam { lifetime, .. }) => Some(parse_quote!(#lifetime)),
-        })
-        .collect::<Vec<_>>();
+    if let Some(ty) = &flags.borrow_key {
+        others.push(OtherComparison::borrow(ty, &expressions, &fields)?);
+    }
     let generics = input.generics;
     Ok(ParsedInput {
+        flags,
         expressions,
         fields,
+        others,
         generics,
-        generic_arguments,
     })
 }
 
-fn fold_token_errors<T, E>(acc: Result<Vec<T>, E>, res: Result<T, E>) -> Result<Vec<T>, E>
+/// Bound the types of `keys` that depend on a type parameter of `generics` with `bound`.
+///
+/// Only the types of the fields actually used are bounded, so that ignored fields don't constrain the impl.
+pub(crate) fn add_bounds<'a>(
+    generics: &mut Generics,
+    keys: impl Iterator<Item = &'a Key>,
+    bound: TokenStream,
+) {
+    let type_params = type_params(generics);
+    let mut bounded = Vec::<String>::new();
+    for ty in keys.filter_map(bounded_type) {
+        let ty_str = ty.to_token_stream().to_string();
+        if mentions_any(ty.to_token_stream(), &type_params) && !bounded.contains(&ty_str) {
+            bounded.push(ty_str);
+            generics
+                .make_where_clause()
+                .predicates
+                .push(parse_quote!(#ty: #bound));
+        }
+    }
+}
+
+/// Assert that the types of `keys` that don't depend on a type parameter of `generics` implement `bound`.
+///
+/// Those types aren't bounded, so without the assertion a type missing `bound` is reported deep inside
+/// the generated methods, rather than at the field.
+pub(crate) fn assert_bounds<'a>(
+    struct_name: &Ident,
+    generics: &Generics,
+    keys: impl Iterator<Item = &'a Key>,
+    bound: TokenStream,
+) -> TokenStream {
+    let type_params = type_params(generics);
+    let mut asserted = Vec::<String>::new();
+    let assertions = keys
+        .filter_map(|key| Some((key.ty.as_ref()?.span(), bounded_type(key)?)))
+        .filter(|(_, ty)| {
+            let ty_str = ty.to_token_stream().to_string();
+            let new =
+                !mentions_any(ty.to_token_stream(), &type_params) && !asserted.contains(&ty_str);
+            asserted.push(ty_str);
+            new
+        })
+        .map(|(span, ty)| quote_spanned!(span => assert_bound::<#ty>();))
+        .collect::<Vec<_>>();
+    if assertions.is_empty() {
+        return TokenStream::new();
+    }
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    // The argument gives the function the bounds implied by the type, e.g. `T: 'a` for a `&'a T` field.
+    quote! {
+        const _: () = {
+            fn assert_bound<T: ?::core::marker::Sized + #bound>() {}
+            #[allow(dead_code)]
+            fn assert_fields #impl_generics (_: &#struct_name #ty_generics) #where_clause {
+                #(#assertions)*
+            }
+        };
+    }
+}
+
+fn type_params(generics: &Generics) -> Vec<Ident> {
+    generics
+        .type_params()
+        .map(|param| param.ident.clone())
+        .collect()
+}
+
+/// The type of `key` that must implement the compared trait, if any.
+fn bounded_type(key: &Key) -> Option<Type> {
+    let ty = key.ty.as_ref()?;
+    // Fields compared through `Deref` only need the target to implement the trait.
+    let ty: Type = if key.modifiers.deref.is_some() {
+        parse_quote!(<#ty as ::core::ops::Deref>::Target)
+    } else {
+        ty.clone()
+    };
+    // `PhantomData<T>` implements the comparison and hashing traits for any `T`.
+    (!is_phantom(&ty)).then_some(ty)
+}
+
+fn is_phantom(ty: &Type) -> bool {
+    match ty {
+        Type::Path(TypePath { qself: None, path }) => path
+            .segments
+            .last()
+            .is_some_and(|segment| segment.ident == "PhantomData"),
+        _ => false,
+    }
+}
+
+fn mentions_any(tokens: TokenStream, idents: &[Ident]) -> bool {
+    tokens.into_iter().any(|token| match token {
+        TokenTree::Ident(ident) => idents.contains(&ident),
+        TokenTree::Group(group) => mentions_any(group.stream(), idents),
+        TokenTree::Punct(_) | TokenTree::Literal(_) => false,
+    })
+}
+
+/// Parse an item of the top-level attribute.
+///
+/// Items can be wrapped to apply a modifier to them:
+/// - `Reverse(item)` sorts `item` in descending order, like `#[cmp_by(reverse)]` does for fields.
+/// - `NoneLast(item)` sorts `None` after `Some`, like `#[cmp_by(none_last)]` does for fields.
+fn parse_expression(elem: Expr) -> Result<Key, ParsingError> {
+    match without_self(elem) {
+        Expr::Call(ExprCall { func, args, .. }) if wrapper_name(&func).is_some() => {
+            let name = wrapper_name(&func).unwrap();
+            if args.len() != 1 {
+                return Err(ParsingError::Error(Error::new(
+                    args.span(),
+                    format!("expected a single item: `{name}(item)`"),
+                )));
+            }
+            let mut key = parse_expression(args.into_iter().next().unwrap())?;
+            let span = Some(func.span());
+            match name {
+                "Reverse" => key.modifiers.reverse = span,
+                "NoneLast" => key.modifiers.none_last = span,
+                "ErrGreater" => key.modifiers.on_err = Some(LitStr::new("greater", func.span())),
+                "ErrLess" => key.modifiers.on_err = Some(LitStr::new("less", func.span())),
+                _ => unreachable!("wrapper_name() only returns known wrappers"),
+            }
+            if let (Some(_), Some(on_err)) = (key.modifiers.none_last, &key.modifiers.on_err) {
+                return Err(ParsingError::Error(Error::new(
+                    on_err.span(),
+                    "`NoneLast` can't be combined with `ErrGreater` or `ErrLess`, the item is either an `Option` or a `Result`",
+                )));
+            }
+            Ok(key)
+        }
+        // `Trait::method()` and `<Self as Trait>::method()` can't be called on `self.`, they take it as argument.
+        Expr::Call(ExprCall {
+            attrs,
+            func,
+            paren_token,
+            args,
+        }) if args.is_empty() && is_path_call(&func) => parse_expression(Expr::Call(ExprCall {
+            attrs,
+            func,
+            paren_token,
+            args: parse_quote!(self),
+        })),
+        Expr::Index(ExprIndex { expr: ref base, .. }) if !is_indexable(base) => {
+            Err(ParsingError::Error(Error::new(
+                base.span(),
+                "expected a field, a method call or an index before `[...]`",
+            )))
+        }
+        elem @ (Expr::Call(_)
+        | Expr::Field(_)
+        | Expr::Path(_)
+        | Expr::MethodCall(_)
+        | Expr::Index(_)
+        | Expr::Lit(ExprLit {
+            lit: Lit::Int(_), ..
+        })) => {
+            // TODO: test paths with lengths other than 1
+            Ok(Key {
+                expr: elem,
+                ty: None,
+                modifiers: Modifiers::default(),
+                in_variant: false,
+            })
+        }
+        elem => Err(ParsingError::Error(Error::new(
+            elem.span(),
+            format!(
+                "Invalid form: `{}`.\nAllowed forms: `field`, `0`, `method()`, `inner.field`, `inner.method()`, `map[key]`, `function(self)`, `Trait::method()`, `Reverse(item)`, `NoneLast(item)`, `ErrGreater(item)`, `ErrLess(item)`",
+                elem.to_token_stream()
+            ),
+        ))),
+    }
+}
+
+/// `expr` without its explicit `self.` receiver, since `self.` is prepended to items anyway, e.g. `a.b()` for `self.a.b()`.
+fn without_self(expr: Expr) -> Expr {
+    match expr {
+        Expr::Field(ExprField { base, member, .. }) if is_self(&base) => match member {
+            Member::Named(ident) => parse_quote!(#ident),
+            Member::Unnamed(index) => parse_quote!(#index),
+        },
+        Expr::MethodCall(ExprMethodCall {
+            attrs,
+            receiver,
+            method,
+            turbofish,
+            paren_token,
+            args,
+            ..
+        }) if is_self(&receiver) => Expr::Call(ExprCall {
+            attrs,
+            func: parse_quote!(#method #turbofish),
+            paren_token,
+            args,
+        }),
+        Expr::Field(mut field) => {
+            *field.base = without_self(*field.base);
+            Expr::Field(field)
+        }
+        Expr::MethodCall(mut call) => {
+            *call.receiver = without_self(*call.receiver);
+            Expr::MethodCall(call)
+        }
+        Expr::Index(mut index) => {
+            *index.expr = without_self(*index.expr);
+            Expr::Index(index)
+        }
+        expr => expr,
+    }
+}
+
+/// Whether `base` can be indexed once prefixed with `self.`, e.g. `map` in `map["key"]`.
+fn is_indexable(base: &Expr) -> bool {
+    match base {
+        Expr::Path(_) | Expr::Field(_) | Expr::MethodCall(_) => true,
+        // `method()` is called on `self.`, unlike functions taking `self` and trait methods.
+        Expr::Call(ExprCall { func, args, .. }) => args.is_empty() && !is_path_call(func),
+        Expr::Index(ExprIndex { expr, .. }) => is_indexable(expr),
+        _ => false,
+    }
+}
+
+/// Whether `func` is a qualified path or has several segments, which can't be a method of `self`.
+fn is_path_call(func: &Expr) -> bool {
+    matches!(func, Expr::Path(ExprPath { qself, path, .. }) if qself.is_some() || path.segments.len() > 1)
+}
+
+fn wrapper_name(func: &Expr) -> Option<&'static str> {
+    let Expr::Path(ExprPath { path, .. }) = func else {
+        return None;
+    };
+    ["Reverse", "NoneLast", "ErrGreater", "ErrLess"]
+        .into_iter()
+        .find(|name| path.is_ident(name))
+}
+
+pub(crate) fn fold_token_errors<T, E>(
+    acc: Result<Vec<T>, E>,
+    res: Result<T, E>,
+) -> Result<Vec<T>, E>
 where
     E: Extend<Error> + IntoIterator<Item = Error>,
 {
@@ -136,16 +1835,144 @@ where
     }
 }
 
+/// Parse the positions of the fields to compare on listed by the attribute of a tuple variant, e.g. `#[cmp_by(1, 0)]`.
+fn parse_positions(variant: &syn::Variant, attr: &str) -> Result<Vec<usize>, Error> {
+    let Some(variant_attr) = variant
+        .attrs
+        .iter()
+        .find(|attribute| is_attr(attribute, attr))
+    else {
+        return Ok(Vec::new());
+    };
+    let Fields::Unnamed(FieldsUnnamed { unnamed, .. }) = &variant.fields else {
+        return Err(Error::new_spanned(
+            variant_attr,
+            format!("`{attr}` on a variant lists the positions of its fields, mark the fields of variants that aren't tuples instead"),
+        ));
+    };
+    if let Some(marked) = unnamed
+        .iter()
+        .find(|field| field.attrs.iter().any(|attribute| is_attr(attribute, attr)))
+    {
+        return Err(Error::new(
+            marked.span(),
+            format!("the positions to compare on are already listed by the `{attr}` attribute of the variant, list this field there instead"),
+        ));
+    }
+    let mut positions = Vec::new();
+    for item in parse_items(variant_attr)? {
+        let Expr::Lit(ExprLit {
+            lit: Lit::Int(lit), ..
+        }) = &item
+        else {
+            return Err(Error::new(
+                item.span(),
+                format!("expected the position of a field, e.g. `#[{attr}(0)]`"),
+            ));
+        };
+        let position = lit.base10_parse::<usize>()?;
+        if position >= unnamed.len() {
+            return Err(Error::new(
+                lit.span(),
+                format!("the variant has no field at position {position}"),
+            ));
+        }
+        if positions.contains(&position) {
+            return Err(Error::new(
+                lit.span(),
+                format!("position {position} is already listed"),
+            ));
+        }
+        positions.push(position);
+    }
+    Ok(positions)
+}
+
+/// Sort the fields with an explicit `order` first, the others keep their relative position after them.
+fn sort_by_order(keys: Vec<Key>) -> Result<Vec<Key>, Error> {
+    let mut ordered = Vec::with_capacity(keys.len());
+    let mut errors = Vec::new();
+    for key in keys {
+        let order = match key
+            .modifiers
+            .order
+            .as_ref()
+            .map(LitInt::base10_parse::<u32>)
+        {
+            Some(Ok(order)) => Some(order),
+            Some(Err(err)) => {
+                errors.push(err);
+                None
+            }
+            None => None,
+        };
+        if let (Some(order), Some(lit)) = (order, &key.modifiers.order) {
+            if ordered.iter().any(|(other, _)| *other == Some(order)) {
+                errors.push(Error::new(
+                    lit.span(),
+                    format!("`order = {order}` is already used by another field"),
+                ));
+            }
+        }
+        ordered.push((order, key));
+    }
+    if let Some(err) = errors.into_iter().reduce(|mut acc, err| {
+        acc.combine(err);
+        acc
+    }) {
+        return Err(err);
+    }
+    // The sort is stable, so fields without `order` stay in declaration order.
+    ordered.sort_by_key(|(order, _)| (order.is_none(), *order));
+    Ok(ordered.into_iter().map(|(_, key)| key).collect())
+}
+
+/// Parse the path to a subfield of `member` from `#[attr = "member.subfield"]`.
+fn parse_nested_path(value: &Expr, member: &TokenStream) -> Result<Expr, Error> {
+    let Expr::Lit(ExprLit {
+        lit: Lit::Str(path),
+        ..
+    }) = value
+    else {
+        return Err(Error::new(
+            value.span(),
+            format!(r#"expected a string literal, e.g. `"{member}.subfield"`"#),
+        ));
+    };
+    let expr: Expr = path.parse()?;
+    let mut root = &expr;
+    while let Expr::Field(ExprField { base, .. }) = root {
+        root = base;
+    }
+    let root = match root {
+        Expr::Path(ExprPath { path, .. }) => path.get_ident().map(ToTokens::to_token_stream),
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(index),
+            ..
+        }) => Some(index.to_token_stream()),
+        _ => None,
+    };
+    if root.is_some_and(|root| root.to_string() == member.to_string()) {
+        Ok(expr)
+    } else {
+        Err(Error::new(
+            path.span(),
+            format!("expected a path to a subfield of `{member}`, e.g. `\"{member}.subfield\"`"),
+        ))
+    }
+}
+
 #[derive(Debug)]
 pub(crate) enum ParsingError {
     NoField(Span),
+    Union(Span),
     Error(Error),
 }
 
 impl Extend<Error> for ParsingError {
     fn extend<T: IntoIterator<Item = Error>>(&mut self, iter: T) {
         match self {
-            ParsingError::NoField(_) => {
+            ParsingError::NoField(_) | ParsingError::Union(_) => {
                 unreachable!("When there are no fields, it should never be extended.")
             }
             ParsingError::Error(err) => {
@@ -161,7 +1988,7 @@ impl IntoIterator for ParsingError {
 
     fn into_iter(self) -> Self::IntoIter {
         match self {
-            ParsingError::NoField(_) => {
+            ParsingError::NoField(_) | ParsingError::Union(_) => {
                 unreachable!("When there are no fields, there are no more errors that can originate from parse_fields().")
             }
             ParsingError::Error(err) => err.into_iter(),
@@ -175,7 +2002,39 @@ impl From<Error> for ParsingError {
     }
 }
 
-fn parse_fields(fields: &Fields, attr: &str) -> Result<Vec<Expr>, ParsingError> {
+/// Whether `ty` is spelled `f32` or `f64`, type aliases of floats aren't recognized.
+fn is_float(ty: &Type) -> bool {
+    matches!(ty, Type::Path(TypePath { qself: None, path }) if path.is_ident("f32") || path.is_ident("f64"))
+}
+
+/// Whether `attribute` is `attr`, `order_by` being read in place of `cmp_by` for crates where another derive claims it.
+fn is_attr(attribute: &Attribute, attr: &str) -> bool {
+    attribute
+        .path()
+        .get_ident()
+        .is_some_and(|ident| ident == attr || (attr == "cmp_by" && ident == "order_by"))
+}
+
+/// Parse the fields of a struct or variant marked with `attr`, along with the unmarked fields selected by the `all`,
+/// `transparent` or `prefix` flags or by their index in `positions`.
+fn parse_fields(
+    fields: &Fields,
+    attr: &str,
+    flags: &Flags,
+    positions: &[usize],
+) -> Result<Vec<Key>, ParsingError> {
+    let prefix = flags.prefix.as_ref().map(LitStr::value);
+    let selected = |i: usize, field: &Field| {
+        flags.all.is_some()
+            || flags.transparent.is_some()
+            || positions.contains(&i)
+            || prefix.as_ref().is_some_and(|prefix| {
+                field
+                    .ident
+                    .as_ref()
+                    .is_some_and(|ident| ident.to_string().starts_with(prefix))
+            })
+    };
     // println!("Entered parse_fields");
     match fields {
         Fields::Named(FieldsNamed { named: fields, .. })
@@ -186,28 +2045,84 @@ fn parse_fields(fields: &Fields, attr: &str) -> Result<Vec<Expr>, ParsingError>
             let mut cmp_fields = fields
                 .into_iter()
                 .enumerate()
-                .filter_map(|(i, field)| -> Option<Result<Expr, ParsingError>> {
+                .filter_map(|(i, field)| -> Option<Result<Key, ParsingError>> {
                     let span = field.span();
                     let mut attrs = field
                         .attrs
                         .iter()
-                        .filter(|i| i.path().get_ident().map_or(false, |i| i == attr));
-                    attrs.next()?;
+                        .filter(|i| is_attr(i, attr));
+                    let field_attr = attrs.next();
                     if attrs.next().is_some() {
                         return Some(Err(ParsingError::Error(Error::new(
                             span,
                             format!(r#"expected at most one `{attr}` attribute"#),
                         ))));
                     }
+                    let mut modifiers = match field_attr.map(Modifiers::parse) {
+                        Some(Ok(modifiers)) => modifiers,
+                        Some(Err(err)) => return Some(Err(ParsingError::Error(err))),
+                        None if selected(i, field) => Modifiers::default(),
+                        None => return None,
+                    };
+                    if let Some(skip) = modifiers.skip {
+                        return if flags.all.is_some() || flags.prefix.is_some() {
+                            None
+                        } else {
+                            Some(Err(ParsingError::Error(Error::new(
+                                skip,
+                                format!("`skip` only has an effect with `#[{attr}(all)]` or `#[{attr}(prefix = \"...\")]`, unmarked fields are already ignored"),
+                            ))))
+                        };
+                    }
+                    // Fields compared through something other than their value are left alone.
+                    if let Some(floats) = &flags.floats {
+                        let compared_as_is = [
+                            modifiers.total,
+                            modifiers.by_len,
+                            modifiers.by_display,
+                            modifiers.ordered_float,
+                            modifiers.case_insensitive,
+                        ]
+                        .iter()
+                        .all(Option::is_none)
+                            && modifiers.with.is_none()
+                            && modifiers.collate_with.is_none()
+                            && modifiers.epsilon.is_none()
+                            && modifiers.assume_total.is_none()
+                            && modifiers.key.is_none()
+                            && modifiers.map.is_none()
+                            && modifiers.cache.is_none()
+                            && !matches!(field_attr.map(|attr| &attr.meta), Some(Meta::NameValue(_)));
+                        if compared_as_is && is_float(&field.ty) {
+                            modifiers.total = Some(floats.span());
+                        }
+                    }
                     // println!("Attempting to generate field exprs");
+                    let member = if let Some(ident) = &field.ident {
+                        // println!("Generating named field");
+                        ident.to_token_stream()
+                    } else {
+                        // println!("Generating unnamed field");
+                        Index::from(i).to_token_stream()
+                    };
                     Some(
-                        parse2(if let Some(ident) = &field.ident {
-                            // println!("Generating named field");
-                            ident.to_token_stream()
-                        } else {
-                            // println!("Generating unnamed field");
-                            Index::from(i).to_token_stream()
-                        })
+                        match field_attr.map(|field_attr| &field_attr.meta) {
+                            // The type of a subfield is unknown, so it can't be bounded.
+                            Some(Meta::NameValue(meta)) => {
+                                parse_nested_path(&meta.value, &member).map(|expr| Key {
+                                    expr,
+                                    ty: None,
+                                    modifiers,
+                                    in_variant: false,
+                                })
+                            }
+                            _ => parse2(member).map(|expr| Key {
+                                expr,
+                                ty: Some(field.ty.clone()),
+                                modifiers,
+                                in_variant: false,
+                            }),
+                        }
                         .map_err(ParsingError::Error),
                     )
                 })
@@ -215,7 +2130,9 @@ fn parse_fields(fields: &Fields, attr: &str) -> Result<Vec<Expr>, ParsingError>
             if cmp_fields.peek().is_none() {
                 return Err(ParsingError::NoField(field_span));
             }
-            cmp_fields.fold(Ok(vec![]), fold_token_errors)
+            Ok(sort_by_order(
+                cmp_fields.fold(Ok(vec![]), fold_token_errors)?,
+            )?)
         }
         Fields::Unit => {
             // println!("Parsed unit field");
@@ -223,3 +2140,590 @@ fn parse_fields(fields: &Fields, attr: &str) -> Result<Vec<Expr>, ParsingError>
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(input: TokenStream) -> ParsedInput {
+        parse_input(parse2(input).unwrap(), "cmp_by")
+            .unwrap_or_else(|_| panic!("input should parse"))
+    }
+
+    fn exprs<'a>(keys: impl IntoIterator<Item = &'a Key>) -> Vec<String> {
+        keys.into_iter()
+            .map(|key| key.expr.to_token_stream().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_items() {
+        let parsed = parse(quote! {
+            #[cmp_by(field, 0, method(), inner.field, inner.method(), function(self), _fields)]
+            struct Toto {
+                #[cmp_by]
+                a: u8,
+            }
+        });
+        assert_eq!(
+            exprs(&parsed.expressions),
+            [
+                "field",
+                "0",
+                "method ()",
+                "inner . field",
+                "inner . method ()",
+                "function (self)",
+                "_fields",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_method_chain_items() {
+        let parsed = parse(quote! {
+            #[cmp_by(payload().checksum(), inner.payload().checksum().len())]
+            struct Toto;
+        });
+        assert_eq!(
+            exprs(&parsed.expressions),
+            [
+                "payload () . checksum ()",
+                "inner . payload () . checksum () . len ()",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_explicit_self_items() {
+        let parsed = parse(quote! {
+            #[cmp_by(self.field, self.0, self.method(), inner.field, self.inner.field, self.inner.method(), self.get::<u8>(), self.rows[0].id, Reverse(self.a), Self::rank())]
+            struct Toto;
+        });
+        assert_eq!(
+            exprs(&parsed.expressions),
+            [
+                "field",
+                "0",
+                "method ()",
+                "inner . field",
+                "inner . field",
+                "inner . method ()",
+                "get :: < u8 > ()",
+                "rows [0] . id",
+                "a",
+                "Self :: rank (self)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_trait_method_items() {
+        let parsed = parse(quote! {
+            #[cmp_by(<Self as Ranked>::rank(), Ranked::rank(), <Self as Ranked>::rank(self, 1), Reverse(Ranked::rank()))]
+            struct Toto;
+        });
+        assert_eq!(
+            exprs(&parsed.expressions),
+            [
+                "< Self as Ranked > :: rank (self)",
+                "Ranked :: rank (self)",
+                "< Self as Ranked > :: rank (self , 1)",
+                "Ranked :: rank (self)",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_index_items() {
+        let parsed = parse(quote! {
+            #[cmp_by(config["timeout"], rows[0].id, items()[1], grid[0][1])]
+            struct Toto;
+        });
+        assert_eq!(
+            exprs(&parsed.expressions),
+            [
+                "config [\"timeout\"]",
+                "rows [0] . id",
+                "items () [1]",
+                "grid [0] [1]"
+            ]
+        );
+
+        let input = parse2(quote! {
+            #[cmp_by((a + b)[0])]
+            struct Toto;
+        })
+        .unwrap();
+        match parse_input(input, "cmp_by") {
+            Err(ParsingError::Error(err)) => assert_eq!(
+                err.to_string(),
+                "expected a field, a method call or an index before `[...]`"
+            ),
+            _ => panic!("expected an invalid index error"),
+        }
+    }
+
+    #[test]
+    fn test_wrapped_items() {
+        let parsed = parse(quote! {
+            #[cmp_by(Reverse(a), NoneLast(b()), Reverse(NoneLast(c)))]
+            struct Toto;
+        });
+        assert_eq!(exprs(&parsed.expressions), ["a", "b ()", "c"]);
+        let modifiers = |i: usize| &parsed.expressions[i].modifiers;
+        assert!(modifiers(0).reverse.is_some() && modifiers(0).none_last.is_none());
+        assert!(modifiers(1).reverse.is_none() && modifiers(1).none_last.is_some());
+        assert!(modifiers(2).reverse.is_some() && modifiers(2).none_last.is_some());
+    }
+
+    #[test]
+    fn test_fields() {
+        let parsed = parse(quote! {
+            struct Toto {
+                #[cmp_by]
+                a: u8,
+                b: u8,
+                #[cmp_by = "c.inner"]
+                c: Inner,
+            }
+        });
+        let ParsedFields::Struct(keys) = &parsed.fields else {
+            panic!("expected struct fields");
+        };
+        assert_eq!(exprs(keys), ["a", "c . inner"]);
+        assert!(keys[0].ty.is_some() && keys[1].ty.is_none());
+    }
+
+    #[test]
+    fn test_unrelated_attributes() {
+        let parsed = parse(quote! {
+            /// Documented.
+            #[serde(rename_all = "camelCase")]
+            #[cmp_by(b)]
+            #[doc = "cmp_by"]
+            struct Toto {
+                /// Documented.
+                #[serde(skip)]
+                #[cmp_by]
+                #[hash_by(skip)]
+                a: u8,
+                #[serde(rename = "cmp_by")]
+                #[cmp_by::cmp_by]
+                b: u8,
+                #[doc = "c"]
+                #[cmp_by(reverse)]
+                #[serde(default)]
+                c: u8,
+            }
+        });
+        assert_eq!(exprs(&parsed.expressions), ["b"]);
+        let ParsedFields::Struct(keys) = &parsed.fields else {
+            panic!("expected struct fields");
+        };
+        assert_eq!(exprs(keys), ["a", "c"]);
+        assert!(keys[0].modifiers.skip.is_none() && keys[1].modifiers.reverse.is_some());
+    }
+
+    #[test]
+    fn test_floats() {
+        let parsed = parse(quote! {
+            #[cmp_by(floats = "total")]
+            struct Toto {
+                #[cmp_by]
+                a: f32,
+                #[cmp_by]
+                b: f64,
+                #[cmp_by]
+                c: Float,
+                #[cmp_by]
+                d: &'static f64,
+                #[cmp_by = "e.0"]
+                e: (f64,),
+                #[cmp_by(assume_total = "equal")]
+                f: f32,
+            }
+        });
+        let ParsedFields::Struct(keys) = &parsed.fields else {
+            panic!("expected struct fields");
+        };
+        let total: Vec<bool> = keys
+            .iter()
+            .map(|key| key.modifiers.total.is_some())
+            .collect();
+        assert_eq!(total, [true, true, false, false, false, false]);
+
+        let input = parse2(quote! {
+            #[cmp_by(floats = "partial")]
+            struct Toto {
+                #[cmp_by]
+                a: f32,
+            }
+        })
+        .unwrap();
+        match parse_input(input, "cmp_by") {
+            Err(ParsingError::Error(err)) => {
+                assert_eq!(err.to_string(), r#"expected `floats = "total"`"#)
+            }
+            _ => panic!("expected an invalid `floats` error"),
+        }
+    }
+
+    #[test]
+    fn test_assume_total() {
+        let parsed = parse(quote! {
+            #[cmp_by(floats = "total")]
+            struct Toto {
+                #[cmp_by(assume_total = "equal")]
+                a: f32,
+            }
+        });
+        let ParsedFields::Struct(keys) = &parsed.fields else {
+            panic!("expected struct fields");
+        };
+        // The float is compared with `partial_cmp`, not `total_cmp`.
+        assert!(keys[0].modifiers.total.is_none());
+
+        let errors = [
+            (
+                quote!(#[cmp_by(assume_total = "unchecked")]),
+                r#"expected `assume_total = "panic"`, `assume_total = "equal"` or `assume_total = "debug_checked"`"#,
+            ),
+            (
+                quote!(#[cmp_by(assume_total = "panic", epsilon = 0.1)]),
+                "`assume_total` can't be combined with `epsilon`, the value is compared with `partial_cmp`",
+            ),
+        ];
+        for (attr, message) in errors {
+            let input = parse2(quote! {
+                struct Toto {
+                    #attr
+                    a: f32,
+                }
+            })
+            .unwrap();
+            match parse_input(input, "cmp_by") {
+                Err(ParsingError::Error(err)) => assert_eq!(err.to_string(), message),
+                _ => panic!("expected an error: {message}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_lex() {
+        let errors = [
+            (
+                quote!(#[cmp_by(lex = "infix")]),
+                r#"expected `lex = "prefix"` or `lex = "suffix"`"#,
+            ),
+            (
+                quote!(#[cmp_by(lex = "prefix", by_len)]),
+                "`lex` can't be combined with `by_len`, the items are compared one by one instead",
+            ),
+        ];
+        for (attr, message) in errors {
+            let input = parse2(quote! {
+                struct Toto {
+                    #attr
+                    a: Vec<u8>,
+                }
+            })
+            .unwrap();
+            match parse_input(input, "cmp_by") {
+                Err(ParsingError::Error(err)) => assert_eq!(err.to_string(), message),
+                _ => panic!("expected an error: {message}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_collate_with() {
+        let errors = [
+            (
+                quote!(#[cmp_by(collate_with = "collate", case_insensitive)]),
+                "`collate_with` can't be combined with `case_insensitive`, the strings are compared with the collation function instead",
+            ),
+            (
+                quote!(#[cmp_by(collate_with = "collate", with = "cmp")]),
+                "`collate_with` can't be combined with `with`, the strings are compared with the collation function instead",
+            ),
+        ];
+        for (attr, message) in errors {
+            let input = parse2(quote! {
+                struct Toto {
+                    #attr
+                    a: String,
+                }
+            })
+            .unwrap();
+            match parse_input(input, "cmp_by") {
+                Err(ParsingError::Error(err)) => assert_eq!(err.to_string(), message),
+                _ => panic!("expected an error: {message}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_prefix() {
+        let parsed = parse(quote! {
+            #[cmp_by(prefix = "k_")]
+            struct Toto {
+                k_a: u8,
+                b: u8,
+                #[cmp_by]
+                c: u8,
+                #[cmp_by(skip)]
+                k_d: u8,
+                k_e: u8,
+            }
+        });
+        assert_eq!(exprs(parsed.fields.keys()), ["k_a", "c", "k_e"]);
+    }
+
+    #[test]
+    fn test_transparent() {
+        let parsed = parse(quote! {
+            #[cmp_by(transparent)]
+            struct Meters(f64);
+        });
+        assert_eq!(exprs(parsed.fields.keys()), ["0"]);
+
+        let parsed = parse(quote! {
+            #[cmp_by(transparent)]
+            struct Meters(#[cmp_by(total)] f64);
+        });
+        assert!(parsed
+            .fields
+            .keys()
+            .all(|key| key.modifiers.total.is_some()));
+
+        let input = parse2(quote! {
+            #[cmp_by(transparent)]
+            struct Point(f64, f64);
+        })
+        .unwrap();
+        match parse_input(input, "cmp_by") {
+            Err(ParsingError::Error(err)) => assert_eq!(
+                err.to_string(),
+                "`transparent` only applies to structs with a single field, it compares by that field"
+            ),
+            _ => panic!("expected a `transparent` error"),
+        }
+    }
+
+    #[test]
+    fn test_order_by() {
+        let parsed = parse(quote! {
+            #[order_by(a)]
+            struct Toto {
+                a: u8,
+                #[order_by(reverse)]
+                b: u8,
+                #[cmp_by]
+                c: u8,
+            }
+        });
+        assert_eq!(exprs(&parsed.expressions), ["a"]);
+        assert_eq!(exprs(parsed.fields.keys()), ["b", "c"]);
+
+        let parsed = parse_input(
+            parse2(quote! {
+                #[eq_by(a)]
+                struct Toto {
+                    a: u8,
+                    #[order_by]
+                    b: u8,
+                }
+            })
+            .unwrap(),
+            "eq_by",
+        )
+        .unwrap_or_else(|_| panic!("input should parse"));
+        assert_eq!(parsed.fields.keys().count(), 0);
+    }
+
+    #[test]
+    fn test_comparator() {
+        let parsed = parse(quote! {
+            #[cmp_by(a)]
+            #[cmp_by(use_comparator = "sorter.cmp_fn", on = "data")]
+            struct Toto;
+        });
+        assert_eq!(exprs(&parsed.expressions), ["a", "data"]);
+        assert!(parsed.expressions[0].modifiers.comparator.is_none());
+        let comparator = parsed.expressions[1].modifiers.comparator.as_ref();
+        assert_eq!(
+            comparator.map(|comparator| comparator.to_token_stream().to_string()),
+            Some("sorter . cmp_fn".to_string())
+        );
+
+        let input = parse2(quote! {
+            #[cmp_by(use_comparator = "cmp_fn")]
+            struct Toto;
+        })
+        .unwrap();
+        match parse_input(input, "cmp_by") {
+            Err(ParsingError::Error(err)) => assert_eq!(
+                err.to_string(),
+                r#"expected the item compared with the comparator: `on = "item"`"#
+            ),
+            _ => panic!("expected a missing item error"),
+        }
+    }
+
+    #[test]
+    fn test_tuple_fields() {
+        let parsed = parse(quote! {
+            struct Toto(u8, #[cmp_by] u8, #[cmp_by = "2.inner"] Inner);
+        });
+        assert_eq!(exprs(parsed.fields.keys()), ["1", "2 . inner"]);
+    }
+
+    #[test]
+    fn test_variant_fields() {
+        let parsed = parse(quote! {
+            enum Toto {
+                A(#[cmp_by] u8),
+                B { #[cmp_by] b: u8, c: u8 },
+                C,
+            }
+        });
+        let ParsedFields::Enum(variants) = &parsed.fields else {
+            panic!("expected enum variants");
+        };
+        let patterns = variants
+            .iter()
+            .map(|variant| variant.pattern.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            patterns,
+            ["Self :: A (..)", "Self :: B { .. }", "Self :: C"]
+        );
+        assert_eq!(exprs(parsed.fields.keys()), ["0", "b"]);
+        assert!(parsed.fields.keys().all(|key| key.in_variant));
+        assert_eq!(
+            variants[1].bind(&quote!(other)).to_string(),
+            "Self :: B { b : other_b , .. }"
+        );
+    }
+
+    #[test]
+    fn test_variant_positions() {
+        let parsed = parse(quote! {
+            enum Toto {
+                #[cmp_by(2, 0)]
+                A(u8, u8, u8),
+                #[cmp_by(0)]
+                B(u8),
+                C,
+            }
+        });
+        assert_eq!(exprs(parsed.fields.keys()), ["2", "0", "0"]);
+
+        let errors = [
+            (
+                quote! {
+                    enum Toto {
+                        #[cmp_by(a)]
+                        A { a: u8 },
+                    }
+                },
+                "`cmp_by` on a variant lists the positions of its fields, mark the fields of variants that aren't tuples instead",
+            ),
+            (
+                quote! {
+                    enum Toto {
+                        #[cmp_by(0)]
+                        A(u8, #[cmp_by] u8),
+                    }
+                },
+                "the positions to compare on are already listed by the `cmp_by` attribute of the variant, list this field there instead",
+            ),
+            (
+                quote! {
+                    enum Toto {
+                        #[cmp_by(len())]
+                        A(Vec<u8>),
+                    }
+                },
+                "expected the position of a field, e.g. `#[cmp_by(0)]`",
+            ),
+            (
+                quote! {
+                    enum Toto {
+                        #[cmp_by(1)]
+                        A(u8),
+                    }
+                },
+                "the variant has no field at position 1",
+            ),
+            (
+                quote! {
+                    enum Toto {
+                        #[cmp_by(0, 0)]
+                        A(u8),
+                    }
+                },
+                "position 0 is already listed",
+            ),
+        ];
+        for (input, message) in errors {
+            match parse_input(parse2(input).unwrap(), "cmp_by") {
+                Err(ParsingError::Error(err)) => assert_eq!(err.to_string(), message),
+                _ => panic!("expected an error: {message}"),
+            }
+        }
+    }
+
+    #[test]
+    fn test_invalid_item() {
+        let input = parse2(quote! {
+            #[cmp_by(a + b)]
+            struct Toto;
+        })
+        .unwrap();
+        match parse_input(input, "cmp_by") {
+            Err(ParsingError::Error(err)) => {
+                assert!(err.to_string().starts_with("Invalid form: `a + b`"))
+            }
+            _ => panic!("expected an invalid form error"),
+        }
+    }
+
+    #[test]
+    fn test_empty_attribute() {
+        let input = parse2(quote! {
+            #[cmp_by()]
+            struct Toto {
+                #[cmp_by]
+                a: u8,
+            }
+        })
+        .unwrap();
+        match parse_input(input, "cmp_by") {
+            Err(ParsingError::Error(err)) => {
+                assert!(err.to_string().starts_with("empty `cmp_by` attribute"))
+            }
+            _ => panic!("expected an empty attribute error"),
+        }
+    }
+
+    #[test]
+    fn test_duplicate_fields() {
+        let input = parse2(quote! {
+            #[cmp_by(a(), _fields, b())]
+            #[cmp_by(_fields)]
+            struct Toto {
+                #[cmp_by]
+                c: u8,
+            }
+        })
+        .unwrap();
+        match parse_input(input, "cmp_by") {
+            Err(ParsingError::Error(err)) => {
+                assert_eq!(err.to_string(), "`_fields` may appear at most once")
+            }
+            _ => panic!("expected a duplicate `_fields` error"),
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use crate::cmp_by::{gen_ord_expression, gen_other_partial_ord, needs_bound, Comparison};
+use crate::parsing::{add_bounds, assert_bounds, parse_input, ParsedInput, ParsingError};
+use proc_macro2::TokenStream;
+use quote::{quote, quote_spanned};
+use syn::{spanned::Spanned, DeriveInput, Error};
+
+pub fn impl_partial_ord_by_derive(input: DeriveInput) -> TokenStream {
+    let input_span = input.span();
+    let struct_name = input.ident.clone();
+
+    let ParsedInput {
+        flags,
+        expressions: sortable_expressions,
+        fields: sortable_fields,
+        others,
+        mut generics,
+    } = match parse_input(input, "cmp_by") {
+        Ok(value) => value,
+        Err(err) => return match err {
+            ParsingError::Error(err) => err,
+            ParsingError::Union(span) => Error::new(span, "PartialOrdBy: unions are not supported"),
+            ParsingError::NoField(span) => Error::new(
+                span,
+                "PartialOrdBy: no field to compare on. Mark fields to compare on with #[cmp_by]",
+            ),
+        }
+        .into_compile_error(),
+    };
+
+    let ord_expression = match gen_ord_expression(
+        "PartialOrdBy",
+        input_span,
+        &flags,
+        &sortable_expressions,
+        &sortable_fields,
+        Comparison::Partial,
+    ) {
+        Ok(ord_expression) => ord_expression,
+        Err(err) => return err.into_compile_error(),
+    };
+
+    let inline = match flags.inline() {
+        Ok(inline) => inline,
+        Err(err) => return err.into_compile_error(),
+    };
+    let other_partial_ord = gen_other_partial_ord(&struct_name, &generics, &others, &inline);
+
+    let bounded_keys = || sortable_fields.keys().filter(|key| needs_bound(key));
+    add_bounds(
+        &mut generics,
+        bounded_keys(),
+        quote!(::core::cmp::PartialOrd),
+    );
+    let bound_assertions = assert_bounds(
+        &struct_name,
+        &generics,
+        bounded_keys(),
+        quote!(::core::cmp::PartialOrd),
+    );
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    quote_spanned! {input_span =>
+        #[automatically_derived]
+        impl #impl_generics ::core::cmp::PartialOrd<Self> for #struct_name #ty_generics #where_clause {
+            #inline
+            fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+                #ord_expression
+            }
+        }
+
+        #other_partial_ord
+        #bound_assertions
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::assert_rust_eq;
+
+    #[test]
+    fn test_struct() {
+        let input = syn::parse_quote! {
+            #[cmp_by(area())]
+            struct Shape {
+                #[cmp_by]
+                width: f32,
+                #[cmp_by(reverse)]
+                height: f32,
+                #[cmp_by(total)]
+                depth: f64,
+                #[cmp_by(with = "cmp_names")]
+                name: String,
+            }
+        };
+
+        let output = impl_partial_ord_by_derive(syn::parse2(input).unwrap());
+        assert_rust_eq!(
+            output.to_string(),
+            r#"#[automatically_derived]
+impl ::core::cmp::PartialOrd<Self> for Shape {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> ::core::option::Option<::core::cmp::Ordering> {
+        match self.area().partial_cmp(&other.area()) {
+            ::core::option::Option::Some(::core::cmp::Ordering::Equal) => {
+                match self.width.partial_cmp(&other.width) {
+                    ::core::option::Option::Some(::core::cmp::Ordering::Equal) => match other
+                        .height
+                        .partial_cmp(&self.height)
+                    {
+                        ::core::option::Option::Some(::core::cmp::Ordering::Equal) => {
+                            match ::core::option::Option::Some(self.depth.total_cmp(&other.depth)) {
+                                ::core::option::Option::Some(::core::cmp::Ordering::Equal) => {
+                                    ::core::option::Option::Some(cmp_names(&self.name, &other.name))
+                                }
+                                ord => ord,
+                            }
+                        }
+                        ord => ord,
+                    },
+                    ord => ord,
+                }
+            }
+            ord => ord,
+        }
+    }
+}
+const _: () = {
+    fn assert_bound<T: ?::core::marker::Sized + ::core::cmp::PartialOrd>() {}
+    #[allow(dead_code)]
+    fn assert_fields(_: &Shape) {
+        assert_bound::<f32>();
+    }
+};
+"#
+        );
+    }
+}
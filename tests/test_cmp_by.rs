@@ -11,6 +11,7 @@ fn test_cmp_by() {
 
     #[derive(CmpBy, Debug)]
     #[cmp_by(channel(), pitch(), _fields)]
+    #[allow(dead_code, clippy::enum_variant_names)]
     enum Note {
         NoteOn { pitch: u8, channel: u8 },
         NoteOff { pitch: u8, channel: u8 },
@@ -122,3 +123,4514 @@ fn test_cmp_by() {
         Ordering::Less
     );
 }
+
+#[test]
+fn test_generic_bounds() {
+    use cmp_by_derive::HashBy;
+    use std::collections::HashSet;
+
+    struct NotOrd;
+
+    #[derive(CmpBy, HashBy)]
+    struct Wrapper<T, U> {
+        #[cmp_by]
+        #[hash_by]
+        inner: T,
+        #[allow(dead_code)]
+        ignored: U,
+    }
+
+    assert_eq!(
+        Wrapper {
+            inner: 1,
+            ignored: NotOrd
+        }
+        .cmp(&Wrapper {
+            inner: 2,
+            ignored: NotOrd
+        }),
+        Ordering::Less
+    );
+    assert!(HashSet::from([Wrapper {
+        inner: 1,
+        ignored: NotOrd
+    }])
+    .contains(&Wrapper {
+        inner: 1,
+        ignored: NotOrd
+    }));
+}
+
+#[test]
+fn test_lifetimes() {
+    use cmp_by_derive::HashBy;
+    use std::collections::HashSet;
+
+    #[derive(CmpBy, HashBy, Debug)]
+    struct View<'a> {
+        #[cmp_by]
+        #[hash_by]
+        slice: &'a [u8],
+    }
+
+    #[derive(CmpBy, HashBy)]
+    #[cmp_by(len())]
+    #[hash_by(len())]
+    struct Pair<'a, 'b: 'a, T> {
+        #[cmp_by]
+        #[hash_by]
+        first: &'a T,
+        #[cmp_by(reverse)]
+        second: &'b str,
+    }
+
+    impl<T> Pair<'_, '_, T> {
+        fn len(&self) -> usize {
+            self.second.len()
+        }
+    }
+
+    let data = [1, 2, 3];
+    assert_eq!(
+        View { slice: &data[0..2] }.cmp(&View { slice: &data[1..3] }),
+        Ordering::Less
+    );
+    assert!(HashSet::from([View { slice: &data[0..2] }]).contains(&View { slice: &data[0..2] }));
+
+    let (one, two) = (1, 2);
+    let second = String::from("b");
+    assert_eq!(
+        Pair {
+            first: &two,
+            second: "a"
+        }
+        .cmp(&Pair {
+            first: &one,
+            second: "a"
+        }),
+        Ordering::Greater
+    );
+    assert_eq!(
+        Pair {
+            first: &one,
+            second: "a"
+        }
+        .cmp(&Pair {
+            first: &one,
+            second: &second
+        }),
+        Ordering::Greater
+    );
+    assert_eq!(
+        Pair {
+            first: &one,
+            second: "ab"
+        }
+        .cmp(&Pair {
+            first: &two,
+            second: "a"
+        }),
+        Ordering::Greater
+    );
+    assert!(HashSet::from([Pair {
+        first: &one,
+        second: "b"
+    }])
+    .contains(&Pair {
+        first: &1,
+        second: &second
+    }));
+}
+
+#[test]
+fn test_const_generics() {
+    use cmp_by_derive::{EqBy, HashBy, PartialOrdBy};
+    use std::collections::HashSet;
+
+    #[derive(CmpBy, HashBy, Debug)]
+    struct Matrix<const N: usize> {
+        #[cmp_by]
+        #[hash_by]
+        data: [u8; N],
+        #[allow(dead_code)]
+        scale: f32,
+    }
+
+    #[derive(EqBy, PartialOrdBy)]
+    struct Weights<T, const N: usize> {
+        #[eq_by]
+        #[cmp_by]
+        values: [T; N],
+    }
+
+    assert_eq!(
+        Matrix {
+            data: [1, 2],
+            scale: 0.0
+        }
+        .cmp(&Matrix {
+            data: [1, 3],
+            scale: 0.0
+        }),
+        Ordering::Less
+    );
+    assert!(HashSet::from([Matrix {
+        data: [1, 2],
+        scale: 0.0
+    }])
+    .contains(&Matrix {
+        data: [1, 2],
+        scale: 1.0
+    }));
+    assert_eq!(
+        Matrix::<0> {
+            data: [],
+            scale: 1.0
+        }
+        .cmp(&Matrix {
+            data: [],
+            scale: 2.0
+        }),
+        Ordering::Equal
+    );
+    assert_eq!(
+        Weights { values: [0.5, 1.0] }.partial_cmp(&Weights { values: [0.5, 2.0] }),
+        Some(Ordering::Less)
+    );
+    assert!(Weights { values: [1.0] } == Weights { values: [1.0] });
+}
+
+#[test]
+fn test_phantom_data() {
+    use cmp_by_derive::HashBy;
+    use std::collections::HashSet;
+    use std::marker::PhantomData;
+
+    struct NotOrd;
+
+    #[derive(CmpBy, HashBy)]
+    struct S<T> {
+        #[cmp_by]
+        #[hash_by]
+        id: u32,
+        _marker: PhantomData<T>,
+    }
+
+    #[derive(CmpBy, HashBy)]
+    #[cmp_by(all)]
+    #[hash_by(all)]
+    struct Marked<T> {
+        id: u32,
+        _marker: PhantomData<T>,
+    }
+
+    assert_eq!(
+        S::<NotOrd> {
+            id: 1,
+            _marker: PhantomData
+        }
+        .cmp(&S {
+            id: 2,
+            _marker: PhantomData
+        }),
+        Ordering::Less
+    );
+    assert!(HashSet::from([S::<NotOrd> {
+        id: 1,
+        _marker: PhantomData
+    }])
+    .contains(&S {
+        id: 1,
+        _marker: PhantomData
+    }));
+    assert_eq!(
+        Marked::<NotOrd> {
+            id: 2,
+            _marker: PhantomData
+        }
+        .cmp(&Marked {
+            id: 1,
+            _marker: PhantomData
+        }),
+        Ordering::Greater
+    );
+    assert!(HashSet::from([Marked::<NotOrd> {
+        id: 1,
+        _marker: PhantomData
+    }])
+    .contains(&Marked {
+        id: 1,
+        _marker: PhantomData
+    }));
+}
+
+#[test]
+fn test_deref() {
+    use std::ops::Deref;
+    use std::sync::Arc;
+
+    /// Orders by length, but derefs to the text itself.
+    #[derive(PartialEq, Eq, PartialOrd, Ord)]
+    struct Label {
+        len: usize,
+        text: String,
+    }
+
+    impl Label {
+        fn new(text: &str) -> Self {
+            Label {
+                len: text.len(),
+                text: text.to_owned(),
+            }
+        }
+    }
+
+    impl Deref for Label {
+        type Target = str;
+
+        fn deref(&self) -> &str {
+            &self.text
+        }
+    }
+
+    #[derive(CmpBy)]
+    struct Entry<T> {
+        #[cmp_by(deref)]
+        name: Box<str>,
+        #[cmp_by(deref, reverse)]
+        inner: Arc<T>,
+        #[cmp_by(deref)]
+        label: Label,
+    }
+
+    assert_eq!(
+        Entry {
+            name: "a".into(),
+            inner: Arc::new(0),
+            label: Label::new("")
+        }
+        .cmp(&Entry {
+            name: "b".into(),
+            inner: Arc::new(0),
+            label: Label::new("")
+        }),
+        Ordering::Less
+    );
+    assert_eq!(
+        Entry {
+            name: "a".into(),
+            inner: Arc::new(1),
+            label: Label::new("")
+        }
+        .cmp(&Entry {
+            name: "a".into(),
+            inner: Arc::new(2),
+            label: Label::new("")
+        }),
+        Ordering::Greater
+    );
+    // Compared as `str`, not by the length first like `Label` itself.
+    assert_eq!(
+        Entry {
+            name: "a".into(),
+            inner: Arc::new(1),
+            label: Label::new("zz")
+        }
+        .cmp(&Entry {
+            name: "a".into(),
+            inner: Arc::new(1),
+            label: Label::new("zzz")
+        }),
+        Ordering::Less
+    );
+    assert_eq!(
+        Entry {
+            name: "a".into(),
+            inner: Arc::new(1),
+            label: Label::new("b")
+        }
+        .cmp(&Entry {
+            name: "a".into(),
+            inner: Arc::new(1),
+            label: Label::new("aa")
+        }),
+        Ordering::Greater
+    );
+    assert!(
+        Entry {
+            name: "a".into(),
+            inner: Arc::new(1),
+            label: Label::new("b")
+        } == Entry {
+            name: "a".into(),
+            inner: Arc::new(1),
+            label: Label::new("b")
+        }
+    );
+}
+
+#[test]
+fn test_case_insensitive() {
+    use cmp_by_derive::HashBy;
+    use std::collections::HashSet;
+
+    #[derive(CmpBy, HashBy)]
+    #[hash_by(verify_eq)]
+    struct User {
+        #[cmp_by(case_insensitive)]
+        #[hash_by(case_insensitive)]
+        name: String,
+        #[cmp_by(case_insensitive)]
+        handle: &'static str,
+    }
+
+    assert_eq!(
+        User {
+            name: "alice".to_owned(),
+            handle: ""
+        }
+        .cmp(&User {
+            name: "Bob".to_owned(),
+            handle: ""
+        }),
+        Ordering::Less
+    );
+    assert_eq!(
+        User {
+            name: "Alice".to_owned(),
+            handle: "a"
+        }
+        .cmp(&User {
+            name: "alice".to_owned(),
+            handle: "B"
+        }),
+        Ordering::Less
+    );
+    assert_eq!(
+        User {
+            name: "ALICE".to_owned(),
+            handle: "x"
+        }
+        .cmp(&User {
+            name: "alice".to_owned(),
+            handle: "X"
+        }),
+        Ordering::Equal
+    );
+    assert!(
+        User {
+            name: "ALICE".to_owned(),
+            handle: "x"
+        } == User {
+            name: "alice".to_owned(),
+            handle: "X"
+        }
+    );
+    assert!(
+        User {
+            name: "alicea".to_owned(),
+            handle: "x"
+        } > User {
+            name: "alice".to_owned(),
+            handle: "X"
+        }
+    );
+    assert!(HashSet::from([User {
+        name: "ALICE".to_owned(),
+        handle: "x"
+    }])
+    .contains(&User {
+        name: "alice".to_owned(),
+        handle: "X"
+    }));
+}
+
+#[test]
+fn test_nested_field() {
+    struct Metadata {
+        timestamp: u64,
+        #[allow(dead_code)]
+        author: &'static str,
+    }
+
+    #[derive(CmpBy)]
+    struct Post {
+        #[cmp_by = "meta.timestamp"]
+        meta: Metadata,
+        #[cmp_by]
+        title: &'static str,
+    }
+
+    assert_eq!(
+        Post {
+            meta: Metadata {
+                timestamp: 1,
+                author: "b"
+            },
+            title: "b"
+        }
+        .cmp(&Post {
+            meta: Metadata {
+                timestamp: 2,
+                author: "a"
+            },
+            title: "a"
+        }),
+        Ordering::Less
+    );
+    assert_eq!(
+        Post {
+            meta: Metadata {
+                timestamp: 1,
+                author: "b"
+            },
+            title: "a"
+        }
+        .cmp(&Post {
+            meta: Metadata {
+                timestamp: 1,
+                author: "a"
+            },
+            title: "b"
+        }),
+        Ordering::Less
+    );
+    assert!(
+        Post {
+            meta: Metadata {
+                timestamp: 1,
+                author: "b"
+            },
+            title: "a"
+        } == Post {
+            meta: Metadata {
+                timestamp: 1,
+                author: "a"
+            },
+            title: "a"
+        }
+    );
+}
+
+#[test]
+fn test_tuple_indices() {
+    #[derive(CmpBy)]
+    #[cmp_by(2, 0.len())]
+    struct Row(&'static str, #[cmp_by] u8, i32);
+
+    assert_eq!(Row("b", 0, 1).cmp(&Row("a", 1, 2)), Ordering::Less);
+    assert_eq!(Row("bb", 1, 1).cmp(&Row("a", 0, 1)), Ordering::Greater);
+    assert_eq!(Row("b", 0, 1).cmp(&Row("a", 1, 1)), Ordering::Less);
+    assert!(Row("b", 0, 1) == Row("a", 0, 1));
+}
+
+#[test]
+fn test_call_arguments() {
+    enum Unit {
+        Meters,
+        Kilometers,
+    }
+
+    #[derive(CmpBy)]
+    #[cmp_by(value_in(Unit::Kilometers), digit(0))]
+    struct Distance {
+        meters: u32,
+    }
+
+    impl Distance {
+        fn value_in(&self, unit: Unit) -> u32 {
+            match unit {
+                Unit::Meters => self.meters,
+                Unit::Kilometers => self.meters / 1000,
+            }
+        }
+
+        fn digit(&self, position: u32) -> u32 {
+            self.value_in(Unit::Meters) / 10u32.pow(position) % 10
+        }
+    }
+
+    assert_eq!(
+        Distance { meters: 1500 }.cmp(&Distance { meters: 2100 }),
+        Ordering::Less
+    );
+    assert_eq!(
+        Distance { meters: 1509 }.cmp(&Distance { meters: 1500 }),
+        Ordering::Greater
+    );
+    assert!(Distance { meters: 1509 } == Distance { meters: 1019 });
+}
+
+#[test]
+fn test_turbofish() {
+    use std::any::Any;
+
+    #[derive(PartialEq, Eq, PartialOrd, Ord)]
+    struct Position(i32);
+
+    #[derive(PartialEq, Eq, PartialOrd, Ord)]
+    struct Health(u8);
+
+    #[derive(CmpBy)]
+    #[cmp_by(get::<Position>(), get::<Health>())]
+    struct Entity {
+        components: Vec<Box<dyn Any>>,
+    }
+
+    impl Entity {
+        fn get<T: Any>(&self) -> Option<&T> {
+            self.components
+                .iter()
+                .find_map(|component| component.downcast_ref())
+        }
+    }
+
+    assert_eq!(
+        Entity {
+            components: vec![Box::new(Health(9)), Box::new(Position(1))]
+        }
+        .cmp(&Entity {
+            components: vec![Box::new(Health(0)), Box::new(Position(2))]
+        }),
+        Ordering::Less
+    );
+    assert_eq!(
+        Entity {
+            components: vec![Box::new(Health(9)), Box::new(Position(1))]
+        }
+        .cmp(&Entity {
+            components: vec![Box::new(Position(1)), Box::new(Health(0))]
+        }),
+        Ordering::Greater
+    );
+    assert!(
+        Entity {
+            components: vec![Box::new(Health(9)), Box::new(Position(1))]
+        } == Entity {
+            components: vec![Box::new(Position(1)), Box::new(Health(9))]
+        }
+    );
+}
+
+#[test]
+fn test_turbofish_several_arguments() {
+    #[derive(CmpBy)]
+    #[cmp_by(bits::<u8, u16>(), bits::<u16, u8>())]
+    struct Reading {
+        raw: u32,
+    }
+
+    impl Reading {
+        fn bits<Low: TryFrom<u32>, High>(&self) -> Option<Low> {
+            Low::try_from(self.raw >> (8 * std::mem::size_of::<High>())).ok()
+        }
+    }
+
+    assert_eq!(
+        Reading { raw: 0x1_0000 }.cmp(&Reading { raw: 0xff_ffff }),
+        Ordering::Less
+    );
+    assert_eq!(
+        Reading { raw: 0x100 }.cmp(&Reading { raw: 0x1ff }),
+        Ordering::Equal
+    );
+}
+
+#[test]
+fn test_free_function() {
+    fn weight(parcel: &Parcel) -> u32 {
+        parcel.items.iter().sum()
+    }
+
+    fn heaviest(parcel: &Parcel, at_least: u32) -> u32 {
+        parcel.items.iter().copied().fold(at_least, u32::max)
+    }
+
+    #[derive(CmpBy)]
+    #[cmp_by(weight(self), Reverse(heaviest(self, 2)))]
+    struct Parcel {
+        items: Vec<u32>,
+    }
+
+    assert_eq!(
+        Parcel { items: vec![1, 2] }.cmp(&Parcel { items: vec![4] }),
+        Ordering::Less
+    );
+    assert_eq!(
+        Parcel { items: vec![1, 3] }.cmp(&Parcel { items: vec![4] }),
+        Ordering::Greater
+    );
+    assert_eq!(
+        Parcel { items: vec![1, 1] }.cmp(&Parcel { items: vec![2] }),
+        Ordering::Equal
+    );
+    assert!(Parcel { items: vec![1, 2] } == Parcel { items: vec![2, 1] });
+}
+
+#[test]
+fn test_all() {
+    use cmp_by_derive::HashBy;
+    use std::collections::HashSet;
+
+    #[derive(CmpBy, HashBy)]
+    #[cmp_by(all)]
+    #[hash_by(all, verify_eq)]
+    struct Cached {
+        name: &'static str,
+        #[cmp_by(reverse)]
+        version: u32,
+        #[cmp_by(skip)]
+        #[hash_by(skip)]
+        #[allow(dead_code)]
+        hits: u64,
+    }
+
+    assert_eq!(
+        Cached {
+            name: "a",
+            version: 1,
+            hits: 0
+        }
+        .cmp(&Cached {
+            name: "b",
+            version: 1,
+            hits: 0
+        }),
+        Ordering::Less
+    );
+    assert_eq!(
+        Cached {
+            name: "a",
+            version: 1,
+            hits: 0
+        }
+        .cmp(&Cached {
+            name: "a",
+            version: 2,
+            hits: 0
+        }),
+        Ordering::Greater
+    );
+    assert!(
+        Cached {
+            name: "a",
+            version: 1,
+            hits: 0
+        } == Cached {
+            name: "a",
+            version: 1,
+            hits: 9
+        }
+    );
+    assert!(HashSet::from([Cached {
+        name: "a",
+        version: 1,
+        hits: 0
+    }])
+    .contains(&Cached {
+        name: "a",
+        version: 1,
+        hits: 9
+    }));
+}
+
+#[test]
+fn test_mixed_directions() {
+    // Every combination of directions for three fields, checked against `Ordering::then` on all pairs of values.
+    // Reversed fields compare `other` with `self`, which must still fall through to the next field on ties.
+    macro_rules! check_directions {
+        ($($name:ident: [$($a:ident)?] [$($b:ident)?] [$($c:ident)?];)*) => {$({
+            #[derive(CmpBy, Debug)]
+            struct $name {
+                #[cmp_by$(($a))?]
+                a: u8,
+                #[cmp_by$(($b))?]
+                b: u8,
+                #[cmp_by$(($c))?]
+                c: u8,
+            }
+
+            let reversed = [
+                stringify!($($a)?) == "reverse",
+                stringify!($($b)?) == "reverse",
+                stringify!($($c)?) == "reverse",
+            ];
+            let values: Vec<[u8; 3]> = (0..27).map(|i| [i / 9, i / 3 % 3, i % 3]).collect();
+            for x in &values {
+                for y in &values {
+                    let expected = (0..3)
+                        .map(|i| {
+                            let ord = x[i].cmp(&y[i]);
+                            if reversed[i] { ord.reverse() } else { ord }
+                        })
+                        .fold(Ordering::Equal, Ordering::then);
+                    let (this, other) = (
+                        $name { a: x[0], b: x[1], c: x[2] },
+                        $name { a: y[0], b: y[1], c: y[2] },
+                    );
+                    assert_eq!(this.cmp(&other), expected, "{} {x:?} {y:?}", stringify!($name));
+                    assert_eq!(this.partial_cmp(&other), Some(expected));
+                    assert_eq!(this == other, x == y);
+                }
+            }
+        })*};
+    }
+
+    check_directions! {
+        AscAscAsc: [] [] [];
+        AscAscDesc: [] [] [reverse];
+        AscDescAsc: [] [reverse] [];
+        AscDescDesc: [] [reverse] [reverse];
+        DescAscAsc: [reverse] [] [];
+        DescAscDesc: [reverse] [] [reverse];
+        DescDescAsc: [reverse] [reverse] [];
+        DescDescDesc: [reverse] [reverse] [reverse];
+    }
+}
+
+#[test]
+fn test_hash_skip_compared_field() {
+    use cmp_by_derive::HashBy;
+    use std::collections::HashSet;
+
+    #[derive(CmpBy, HashBy, Debug)]
+    #[cmp_by(all)]
+    #[hash_by(all, verify_eq)]
+    struct Rect {
+        width: u32,
+        height: u32,
+        // Derived from the hashed fields.
+        #[hash_by(skip)]
+        area: u32,
+    }
+
+    assert_eq!(
+        Rect {
+            width: 2,
+            height: 3,
+            area: 6
+        }
+        .cmp(&Rect {
+            width: 2,
+            height: 4,
+            area: 8
+        }),
+        Ordering::Less
+    );
+    assert!(
+        Rect {
+            width: 2,
+            height: 3,
+            area: 6
+        } == Rect {
+            width: 2,
+            height: 3,
+            area: 6
+        }
+    );
+    let set = HashSet::from([
+        Rect {
+            width: 2,
+            height: 3,
+            area: 6,
+        },
+        Rect {
+            width: 3,
+            height: 2,
+            area: 6,
+        },
+    ]);
+    assert!(set.contains(&Rect {
+        width: 3,
+        height: 2,
+        area: 6
+    }));
+    assert_eq!(set.len(), 2);
+}
+
+#[test]
+fn test_cache() {
+    use cmp_by_derive::HashBy;
+    use std::cell::{Cell, OnceCell};
+    use std::collections::HashSet;
+
+    thread_local! {
+        static COMPUTED: Cell<usize> = const { Cell::new(0) };
+    }
+
+    #[derive(CmpBy, HashBy)]
+    #[hash_by(verify_eq)]
+    struct Document {
+        text: String,
+        #[cmp_by(cache = "Self::word_count")]
+        #[hash_by(cache = "Self::word_count")]
+        words: OnceCell<usize>,
+    }
+
+    impl Document {
+        fn new(text: &str) -> Self {
+            Document {
+                text: text.to_owned(),
+                words: OnceCell::new(),
+            }
+        }
+
+        fn word_count(&self) -> usize {
+            COMPUTED.set(COMPUTED.get() + 1);
+            self.text.split_whitespace().count()
+        }
+    }
+
+    let short = Document::new("a b");
+    let long = Document::new("a b c");
+    assert_eq!(short.cmp(&long), Ordering::Less);
+    assert_eq!(long.cmp(&short), Ordering::Greater);
+    assert!(short == Document::new("c d"));
+    assert!(HashSet::from([short]).contains(&Document::new("e f")));
+    // Once per document, whatever the number of comparisons.
+    assert_eq!(COMPUTED.get(), 4);
+}
+
+#[test]
+fn test_other() {
+    #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+    struct Timestamp(u64);
+
+    #[derive(CmpBy)]
+    #[cmp_by(other = "Timestamp", key = "ts()")]
+    struct Event {
+        #[cmp_by]
+        at: u64,
+        #[cmp_by]
+        name: &'static str,
+    }
+
+    impl Event {
+        fn ts(&self) -> Timestamp {
+            Timestamp(self.at)
+        }
+    }
+
+    let events = [
+        Event { at: 1, name: "a" },
+        Event { at: 3, name: "b" },
+        Event { at: 3, name: "c" },
+        Event { at: 7, name: "d" },
+    ];
+    assert!(events[1] == Timestamp(3));
+    assert!(events[0] < Timestamp(3));
+    assert_eq!(events.partition_point(|event| *event < Timestamp(3)), 1);
+    assert_eq!(events.partition_point(|event| *event <= Timestamp(3)), 3);
+}
+
+#[test]
+fn test_variant_fields() {
+    use cmp_by_derive::HashBy;
+    use std::collections::HashSet;
+
+    #[derive(CmpBy, HashBy, Debug)]
+    #[allow(dead_code)]
+    enum Shape {
+        Circle(
+            #[cmp_by]
+            #[hash_by]
+            u32,
+            f32,
+        ),
+        Rect {
+            #[cmp_by(reverse)]
+            #[hash_by]
+            width: u32,
+            #[cmp_by]
+            #[hash_by]
+            height: u32,
+            color: &'static str,
+        },
+        Empty,
+    }
+
+    assert_eq!(
+        Shape::Circle(1, 0.5).cmp(&Shape::Circle(2, 0.1)),
+        Ordering::Less
+    );
+    assert!(Shape::Circle(1, 0.5) == Shape::Circle(1, 0.1));
+    assert_eq!(
+        Shape::Rect {
+            width: 2,
+            height: 1,
+            color: "red"
+        }
+        .cmp(&Shape::Rect {
+            width: 1,
+            height: 2,
+            color: "red"
+        }),
+        Ordering::Less
+    );
+    assert_eq!(
+        Shape::Rect {
+            width: 1,
+            height: 1,
+            color: "red"
+        }
+        .cmp(&Shape::Rect {
+            width: 1,
+            height: 2,
+            color: "blue"
+        }),
+        Ordering::Less
+    );
+    assert!(
+        Shape::Rect {
+            width: 1,
+            height: 2,
+            color: "red"
+        } == Shape::Rect {
+            width: 1,
+            height: 2,
+            color: "blue"
+        }
+    );
+    assert!(HashSet::from([Shape::Rect {
+        width: 1,
+        height: 2,
+        color: "red"
+    }])
+    .contains(&Shape::Rect {
+        width: 1,
+        height: 2,
+        color: "blue"
+    }));
+    // Different variants are ordered by declaration, whatever their fields.
+    assert_eq!(
+        Shape::Circle(9, 0.).cmp(&Shape::Rect {
+            width: 0,
+            height: 0,
+            color: "red"
+        }),
+        Ordering::Less
+    );
+    assert_eq!(
+        Shape::Rect {
+            width: 9,
+            height: 9,
+            color: "red"
+        }
+        .cmp(&Shape::Empty),
+        Ordering::Less
+    );
+    assert_eq!(Shape::Empty.cmp(&Shape::Circle(0, 0.)), Ordering::Greater);
+    assert!(Shape::Empty == Shape::Empty);
+    assert!(
+        Shape::Circle(0, 0.)
+            != Shape::Rect {
+                width: 0,
+                height: 0,
+                color: "red"
+            }
+    );
+}
+
+#[test]
+fn test_variant_positions() {
+    use cmp_by_derive::HashBy;
+    use std::collections::HashSet;
+
+    #[derive(CmpBy, HashBy, Debug)]
+    #[allow(dead_code)]
+    enum Pair {
+        #[cmp_by(1, 0)]
+        #[hash_by(1, 0)]
+        A(u8, u8, f32),
+        #[cmp_by(0)]
+        #[hash_by(0)]
+        B(u8),
+        C,
+    }
+
+    assert_eq!(Pair::A(2, 1, 0.).cmp(&Pair::A(1, 2, 0.)), Ordering::Less);
+    assert_eq!(Pair::A(2, 1, 0.).cmp(&Pair::A(1, 1, 0.)), Ordering::Greater);
+    assert!(Pair::A(1, 1, 0.) == Pair::A(1, 1, 0.5));
+    assert!(HashSet::from([Pair::A(1, 1, 0.)]).contains(&Pair::A(1, 1, 0.5)));
+    assert_eq!(Pair::B(1).cmp(&Pair::B(2)), Ordering::Less);
+    // Different variants are ordered by declaration, whatever their positions.
+    assert_eq!(Pair::A(9, 9, 9.).cmp(&Pair::B(0)), Ordering::Less);
+    assert_eq!(Pair::C.cmp(&Pair::B(9)), Ordering::Greater);
+    assert!(Pair::A(0, 0, 0.) != Pair::B(0));
+}
+
+#[test]
+fn test_non_exhaustive() {
+    use cmp_by_derive::HashBy;
+    use std::collections::HashSet;
+
+    #[derive(CmpBy, HashBy, Debug)]
+    #[allow(dead_code)]
+    #[non_exhaustive]
+    enum Event {
+        Click(
+            #[cmp_by]
+            #[hash_by]
+            u32,
+        ),
+        #[non_exhaustive]
+        Scroll {
+            #[cmp_by]
+            #[hash_by]
+            delta: i32,
+            speed: f32,
+        },
+        #[non_exhaustive]
+        Close,
+    }
+
+    assert_eq!(Event::Click(2).cmp(&Event::Click(1)), Ordering::Greater);
+    assert!(
+        Event::Scroll {
+            delta: 1,
+            speed: 0.5
+        } == Event::Scroll {
+            delta: 1,
+            speed: 2.0
+        }
+    );
+    // Different variants are ordered by declaration.
+    assert_eq!(
+        Event::Scroll {
+            delta: 0,
+            speed: 0.
+        }
+        .cmp(&Event::Click(9)),
+        Ordering::Greater
+    );
+    assert_eq!(
+        Event::Close.cmp(&Event::Scroll {
+            delta: 9,
+            speed: 0.
+        }),
+        Ordering::Greater
+    );
+    assert!(HashSet::from([Event::Close]).contains(&Event::Close));
+}
+
+#[test]
+fn test_variant_order_last() {
+    #[derive(CmpBy, Debug)]
+    #[cmp_by(weight())]
+    enum Fruit {
+        Apple(u32),
+        Pear(u32),
+    }
+
+    impl Fruit {
+        fn weight(&self) -> u32 {
+            match self {
+                Fruit::Apple(weight) | Fruit::Pear(weight) => *weight,
+            }
+        }
+    }
+
+    assert_eq!(Fruit::Pear(1).cmp(&Fruit::Apple(2)), Ordering::Less);
+    // Equal weights are ordered by variant, they don't compare equal.
+    assert_eq!(Fruit::Apple(1).cmp(&Fruit::Pear(1)), Ordering::Less);
+    assert_eq!(Fruit::Pear(1).cmp(&Fruit::Apple(1)), Ordering::Greater);
+    assert!(Fruit::Apple(1) != Fruit::Pear(1));
+    assert!(Fruit::Apple(1) == Fruit::Apple(1));
+}
+
+#[test]
+fn test_hash_discriminant() {
+    use cmp_by_derive::HashBy;
+    use std::hash::{BuildHasher, RandomState};
+
+    #[derive(HashBy)]
+    enum Shape {
+        Circle(#[hash_by] u32),
+        Square(#[hash_by] u32),
+    }
+
+    #[derive(HashBy)]
+    #[hash_by(no_discriminant)]
+    enum Size {
+        Small(#[hash_by] u32),
+        Large(#[hash_by] u32),
+    }
+
+    let state = RandomState::new();
+    assert_ne!(
+        state.hash_one(Shape::Circle(1)),
+        state.hash_one(Shape::Square(1))
+    );
+    assert_eq!(
+        state.hash_one(Shape::Circle(1)),
+        state.hash_one(Shape::Circle(1))
+    );
+    assert_eq!(
+        state.hash_one(Size::Small(1)),
+        state.hash_one(Size::Large(1))
+    );
+}
+
+#[test]
+fn test_assume_total_equal() {
+    #[derive(CmpBy, Debug)]
+    struct Sample {
+        #[cmp_by(assume_total = "equal")]
+        value: f64,
+        #[cmp_by(assume_total = "equal", reverse)]
+        weight: f32,
+    }
+
+    assert_eq!(
+        Sample {
+            value: 1.0,
+            weight: 0.0
+        }
+        .cmp(&Sample {
+            value: 2.0,
+            weight: 0.0
+        }),
+        Ordering::Less
+    );
+    assert_eq!(
+        Sample {
+            value: 1.0,
+            weight: 1.0
+        }
+        .cmp(&Sample {
+            value: 1.0,
+            weight: 2.0
+        }),
+        Ordering::Greater
+    );
+    // Incomparable values are equal, the following fields are still compared.
+    assert_eq!(
+        Sample {
+            value: f64::NAN,
+            weight: 0.0
+        }
+        .cmp(&Sample {
+            value: 1.0,
+            weight: 0.0
+        }),
+        Ordering::Equal
+    );
+    assert_eq!(
+        Sample {
+            value: f64::NAN,
+            weight: 1.0
+        }
+        .cmp(&Sample {
+            value: 1.0,
+            weight: 2.0
+        }),
+        Ordering::Greater
+    );
+    assert!(
+        Sample {
+            value: f64::NAN,
+            weight: 0.0
+        } == Sample {
+            value: 1.0,
+            weight: 0.0
+        }
+    );
+}
+
+#[test]
+fn test_assume_total_panic() {
+    use std::panic::catch_unwind;
+
+    #[derive(CmpBy, Debug)]
+    struct Strict {
+        #[cmp_by(assume_total = "panic")]
+        value: f64,
+    }
+
+    assert_eq!(
+        Strict { value: 1.0 }.cmp(&Strict { value: 2.0 }),
+        Ordering::Less
+    );
+    assert!(Strict { value: -0.0 } == Strict { value: 0.0 });
+    let nan = catch_unwind(|| Strict { value: f64::NAN }.cmp(&Strict { value: 1.0 }));
+    let message = nan.unwrap_err();
+    assert_eq!(
+        message.downcast_ref::<String>().map(String::as_str),
+        Some("`value` isn't comparable, but is compared with `assume_total`")
+    );
+    assert!(catch_unwind(|| Strict { value: f64::NAN } == Strict { value: 1.0 }).is_err());
+}
+
+#[test]
+fn test_assume_total_debug_checked() {
+    use std::panic::catch_unwind;
+
+    #[derive(CmpBy, Debug)]
+    struct Sample {
+        #[cmp_by(assume_total = "debug_checked")]
+        value: f64,
+        #[cmp_by(assume_total = "debug_checked", reverse)]
+        weight: f32,
+    }
+
+    assert_eq!(
+        Sample {
+            value: 1.0,
+            weight: 0.0
+        }
+        .cmp(&Sample {
+            value: 2.0,
+            weight: 0.0
+        }),
+        Ordering::Less
+    );
+    assert_eq!(
+        Sample {
+            value: 1.0,
+            weight: 1.0
+        }
+        .cmp(&Sample {
+            value: 1.0,
+            weight: 2.0
+        }),
+        Ordering::Greater
+    );
+    assert_eq!(
+        Sample {
+            value: -0.0,
+            weight: 0.0
+        },
+        Sample {
+            value: 0.0,
+            weight: 0.0
+        }
+    );
+
+    let nan = catch_unwind(|| {
+        Sample {
+            value: f64::NAN,
+            weight: 0.0,
+        }
+        .cmp(&Sample {
+            value: 1.0,
+            weight: 0.0,
+        })
+    });
+    let nan_eq = catch_unwind(|| {
+        Sample {
+            value: f64::NAN,
+            weight: 0.0,
+        } == Sample {
+            value: 1.0,
+            weight: 0.0,
+        }
+    });
+    if cfg!(debug_assertions) {
+        assert!(nan.is_err());
+        assert!(nan_eq.is_err());
+    } else {
+        // Incomparable values are equal, the following fields are still compared.
+        assert_eq!(nan.unwrap(), Ordering::Equal);
+        assert!(nan_eq.unwrap());
+        assert_eq!(
+            Sample {
+                value: f64::NAN,
+                weight: 1.0
+            }
+            .cmp(&Sample {
+                value: 1.0,
+                weight: 2.0
+            }),
+            Ordering::Greater
+        );
+    }
+}
+
+#[test]
+fn test_by_len() {
+    use cmp_by_derive::HashBy;
+    use std::collections::HashSet;
+
+    #[derive(CmpBy, HashBy)]
+    #[hash_by(verify_eq)]
+    struct Playlist {
+        #[cmp_by(by_len, reverse)]
+        #[hash_by(by_len)]
+        tracks: Vec<f32>,
+        #[cmp_by(by_len)]
+        #[hash_by(by_len)]
+        name: String,
+    }
+
+    assert_eq!(
+        Playlist {
+            tracks: vec![1.0, 2.0],
+            name: "b".into()
+        }
+        .cmp(&Playlist {
+            tracks: vec![3.0],
+            name: "a".into()
+        }),
+        Ordering::Less
+    );
+    assert_eq!(
+        Playlist {
+            tracks: vec![1.0],
+            name: "ab".into()
+        }
+        .cmp(&Playlist {
+            tracks: vec![3.0],
+            name: "a".into()
+        }),
+        Ordering::Greater
+    );
+    assert!(
+        Playlist {
+            tracks: vec![1.0],
+            name: "a".into()
+        } == Playlist {
+            tracks: vec![2.0],
+            name: "b".into()
+        }
+    );
+    assert!(HashSet::from([Playlist {
+        tracks: vec![1.0],
+        name: "a".into()
+    }])
+    .contains(&Playlist {
+        tracks: vec![2.0],
+        name: "b".into()
+    }));
+}
+
+#[test]
+fn test_by_display() {
+    use cmp_by_derive::HashBy;
+    use std::collections::HashSet;
+    use std::fmt;
+
+    // Only implements `Display`, not `Ord` nor `Hash`.
+    #[derive(Debug)]
+    enum Failure {
+        Timeout(u32),
+        Refused,
+    }
+
+    impl fmt::Display for Failure {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                Failure::Timeout(secs) => write!(f, "timeout after {secs}s"),
+                Failure::Refused => write!(f, "connection refused"),
+            }
+        }
+    }
+
+    #[derive(CmpBy, HashBy, Debug)]
+    #[hash_by(verify_eq)]
+    struct Report {
+        #[cmp_by(by_display)]
+        #[hash_by(by_display)]
+        failure: Failure,
+    }
+
+    #[derive(CmpBy, Debug)]
+    enum Log {
+        Entry(#[cmp_by(by_display, reverse)] Failure),
+    }
+
+    assert_eq!(
+        Report {
+            failure: Failure::Refused
+        }
+        .cmp(&Report {
+            failure: Failure::Timeout(1)
+        }),
+        Ordering::Less
+    );
+    // Rendered strings are compared, not numbers.
+    assert_eq!(
+        Report {
+            failure: Failure::Timeout(10)
+        }
+        .cmp(&Report {
+            failure: Failure::Timeout(9)
+        }),
+        Ordering::Less
+    );
+    assert!(
+        Report {
+            failure: Failure::Timeout(3)
+        } == Report {
+            failure: Failure::Timeout(3)
+        }
+    );
+    assert!(HashSet::from([Report {
+        failure: Failure::Refused
+    }])
+    .contains(&Report {
+        failure: Failure::Refused
+    }));
+    assert_eq!(
+        Log::Entry(Failure::Refused).cmp(&Log::Entry(Failure::Timeout(1))),
+        Ordering::Greater
+    );
+}
+
+#[test]
+fn test_collate_with() {
+    use std::cell::Cell;
+
+    thread_local! {
+        static LOCALE: Cell<&'static str> = const { Cell::new("C") };
+    }
+
+    // Stub of a collator: byte order in the "C" locale, ignoring case and accents in the "fr" locale.
+    fn collate(a: &str, b: &str) -> Ordering {
+        match LOCALE.with(Cell::get) {
+            "fr" => {
+                let fold = |s: &str| {
+                    s.chars()
+                        .map(|c| match c {
+                            'é' | 'è' | 'É' | 'È' => 'e',
+                            c => c.to_ascii_lowercase(),
+                        })
+                        .collect::<String>()
+                };
+                fold(a).cmp(&fold(b))
+            }
+            _ => a.cmp(b),
+        }
+    }
+
+    #[derive(CmpBy, Debug)]
+    struct Person<'a> {
+        #[cmp_by(collate_with = "collate")]
+        last: String,
+        #[cmp_by(collate_with = "collate", reverse)]
+        first: &'a str,
+    }
+
+    #[derive(CmpBy, Debug)]
+    enum Contact {
+        Named(#[cmp_by(collate_with = "collate")] Box<str>),
+    }
+
+    let mut people = [
+        Person {
+            last: "Émile".into(),
+            first: "b",
+        },
+        Person {
+            last: "eve".into(),
+            first: "a",
+        },
+        Person {
+            last: "Ève".into(),
+            first: "c",
+        },
+    ];
+    people.sort();
+    assert_eq!(
+        people.iter().map(|p| p.last.as_str()).collect::<Vec<_>>(),
+        ["eve", "Ève", "Émile"]
+    );
+
+    LOCALE.with(|locale| locale.set("fr"));
+    people.sort();
+    assert_eq!(
+        people.iter().map(|p| p.last.as_str()).collect::<Vec<_>>(),
+        ["Émile", "Ève", "eve"]
+    );
+    assert!(
+        Person {
+            last: "Ève".into(),
+            first: "a"
+        } == Person {
+            last: "eve".into(),
+            first: "a"
+        }
+    );
+    assert_eq!(
+        Person {
+            last: "eve".into(),
+            first: "a"
+        }
+        .cmp(&Person {
+            last: "eve".into(),
+            first: "b"
+        }),
+        Ordering::Greater
+    );
+    assert!(Contact::Named("Élan".into()) == Contact::Named("elan".into()));
+    LOCALE.with(|locale| locale.set("C"));
+}
+
+#[test]
+fn test_abs() {
+    use cmp_by_derive::HashBy;
+    use std::collections::HashSet;
+
+    #[derive(CmpBy, HashBy, Debug)]
+    #[hash_by(verify_eq)]
+    struct Residual {
+        #[cmp_by(abs)]
+        #[hash_by(abs)]
+        offset: i32,
+        #[cmp_by]
+        #[hash_by]
+        id: u8,
+    }
+
+    #[derive(CmpBy, Debug)]
+    struct Error {
+        #[cmp_by(abs, total, reverse)]
+        value: f64,
+    }
+
+    #[derive(CmpBy, Debug)]
+    enum Drift {
+        Offset(#[cmp_by(epsilon = 0.1, abs)] f64),
+    }
+
+    let mut residuals = [
+        Residual { offset: 3, id: 0 },
+        Residual { offset: -1, id: 1 },
+        Residual { offset: -3, id: 1 },
+        Residual { offset: 2, id: 0 },
+    ];
+    residuals.sort();
+    assert_eq!(residuals.map(|residual| residual.offset), [-1, 2, 3, -3]);
+    assert!(Residual { offset: -2, id: 0 } == Residual { offset: 2, id: 0 });
+    assert!(
+        HashSet::from([Residual { offset: -2, id: 0 }]).contains(&Residual { offset: 2, id: 0 })
+    );
+
+    let mut errors = [
+        Error { value: 0.5 },
+        Error { value: -2.0 },
+        Error { value: -0.1 },
+        Error { value: 1.0 },
+    ];
+    errors.sort();
+    assert_eq!(errors.map(|error| error.value), [-2.0, 1.0, 0.5, -0.1]);
+
+    assert_eq!(Drift::Offset(-1.0), Drift::Offset(1.05));
+    assert_eq!(
+        Drift::Offset(-2.0).cmp(&Drift::Offset(1.0)),
+        Ordering::Greater
+    );
+}
+
+#[test]
+fn test_lex() {
+    use cmp_by_derive::{EqBy, PartialOrdBy};
+
+    #[derive(CmpBy, Debug)]
+    struct Version {
+        #[cmp_by(lex = "prefix")]
+        segments: Vec<u32>,
+    }
+
+    #[derive(CmpBy, Debug)]
+    enum Name {
+        Domain(#[cmp_by(lex = "suffix", reverse)] Vec<&'static str>),
+    }
+
+    #[derive(PartialOrdBy, PartialEq)]
+    struct Samples {
+        #[cmp_by(lex = "prefix")]
+        values: Vec<f64>,
+    }
+
+    #[derive(EqBy)]
+    struct Tail<'a> {
+        #[eq_by(lex = "suffix")]
+        bytes: &'a [u8],
+    }
+
+    // The default slice ordering breaks ties by length, `lex` stops at the shorter side.
+    assert_eq!(vec![1, 2].cmp(&vec![1, 2, 0]), Ordering::Less);
+    assert_eq!(
+        Version {
+            segments: vec![1, 2]
+        }
+        .cmp(&Version {
+            segments: vec![1, 2, 0]
+        }),
+        Ordering::Equal
+    );
+    assert!(
+        Version {
+            segments: vec![1, 2]
+        } == Version {
+            segments: vec![1, 2, 7]
+        }
+    );
+    assert_eq!(
+        Version {
+            segments: vec![1, 10]
+        }
+        .cmp(&Version {
+            segments: vec![1, 9, 9]
+        }),
+        Ordering::Greater
+    );
+    assert_eq!(
+        Version { segments: vec![] }.cmp(&Version { segments: vec![3] }),
+        Ordering::Equal
+    );
+
+    assert_eq!(
+        Name::Domain(vec!["www", "rs"]).cmp(&Name::Domain(vec!["rs"])),
+        Ordering::Equal
+    );
+    assert_eq!(
+        Name::Domain(vec!["a", "rs"]).cmp(&Name::Domain(vec!["b", "rs"])),
+        Ordering::Greater
+    );
+
+    assert_eq!(
+        Samples { values: vec![1.0] }.partial_cmp(&Samples {
+            values: vec![1.0, f64::NAN]
+        }),
+        Some(Ordering::Equal)
+    );
+    assert_eq!(
+        Samples {
+            values: vec![f64::NAN]
+        }
+        .partial_cmp(&Samples { values: vec![1.0] }),
+        None
+    );
+
+    assert!(Tail { bytes: &[1, 2, 3] } == Tail { bytes: &[2, 3] });
+    assert!(Tail { bytes: &[1, 2, 3] } != Tail { bytes: &[1, 2] });
+}
+
+#[test]
+fn test_time() {
+    use cmp_by_derive::HashBy;
+    use std::collections::HashSet;
+    use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+    #[derive(CmpBy, HashBy, Debug)]
+    #[hash_by(verify_eq)]
+    struct Event {
+        #[cmp_by]
+        #[hash_by]
+        at: SystemTime,
+        #[cmp_by(reverse)]
+        #[hash_by]
+        timeout: Duration,
+        #[cmp_by]
+        #[hash_by]
+        seen: Instant,
+    }
+
+    let now = Instant::now();
+    // Times before the epoch are ordered like the others, `SystemTime` is `Ord` on every platform.
+    let second = Duration::from_secs(1);
+    let (before, after) = (UNIX_EPOCH - second, UNIX_EPOCH + second);
+    assert_eq!(
+        Event {
+            at: before,
+            timeout: Duration::ZERO,
+            seen: now
+        }
+        .cmp(&Event {
+            at: UNIX_EPOCH,
+            timeout: Duration::ZERO,
+            seen: now
+        }),
+        Ordering::Less
+    );
+    assert_eq!(
+        Event {
+            at: before,
+            timeout: Duration::ZERO,
+            seen: now
+        }
+        .cmp(&Event {
+            at: after,
+            timeout: Duration::ZERO,
+            seen: now
+        }),
+        Ordering::Less
+    );
+    assert_eq!(
+        Event {
+            at: before - second,
+            timeout: Duration::ZERO,
+            seen: now
+        }
+        .cmp(&Event {
+            at: before,
+            timeout: Duration::ZERO,
+            seen: now
+        }),
+        Ordering::Less
+    );
+    assert_eq!(
+        Event {
+            at: after,
+            timeout: Duration::ZERO,
+            seen: now
+        }
+        .cmp(&Event {
+            at: UNIX_EPOCH,
+            timeout: Duration::ZERO,
+            seen: now
+        }),
+        Ordering::Greater
+    );
+    assert_eq!(
+        Event {
+            at: UNIX_EPOCH,
+            timeout: Duration::from_millis(5),
+            seen: now
+        }
+        .cmp(&Event {
+            at: UNIX_EPOCH,
+            timeout: Duration::from_millis(10),
+            seen: now
+        }),
+        Ordering::Greater
+    );
+    assert!(HashSet::from([Event {
+        at: before,
+        timeout: second,
+        seen: now
+    }])
+    .contains(&Event {
+        at: before,
+        timeout: second,
+        seen: now
+    }));
+    assert_eq!(
+        Event {
+            at: after,
+            timeout: Duration::ZERO,
+            seen: now
+        }
+        .cmp(&Event {
+            at: after,
+            timeout: Duration::ZERO,
+            seen: now + second
+        }),
+        Ordering::Less
+    );
+}
+
+#[test]
+fn test_cast() {
+    use cmp_by_derive::HashBy;
+    use std::collections::HashSet;
+
+    #[derive(Clone, Copy, Debug)]
+    enum Priority {
+        Low = 2,
+        High = -1,
+    }
+
+    #[derive(CmpBy, HashBy, Debug)]
+    #[hash_by(verify_eq)]
+    struct Task {
+        #[cmp_by(as = "isize")]
+        #[hash_by(as = "isize")]
+        priority: Priority,
+        #[cmp_by(as = "u64")]
+        #[hash_by(as = "u64", raw)]
+        size: u32,
+    }
+
+    #[derive(CmpBy, Debug)]
+    enum Sample {
+        Byte(#[cmp_by(as = "u8")] u32),
+    }
+
+    #[derive(CmpBy, HashBy, Debug)]
+    #[hash_by(verify_eq)]
+    enum Size {
+        Bytes(
+            #[cmp_by(as = "u64")]
+            #[hash_by(raw, as = "u64")]
+            u32,
+        ),
+    }
+
+    assert_eq!(
+        Task {
+            priority: Priority::High,
+            size: 1
+        }
+        .cmp(&Task {
+            priority: Priority::Low,
+            size: 0
+        }),
+        Ordering::Less
+    );
+    assert_eq!(
+        Task {
+            priority: Priority::Low,
+            size: 2
+        }
+        .cmp(&Task {
+            priority: Priority::Low,
+            size: 1
+        }),
+        Ordering::Greater
+    );
+    assert!(HashSet::from([Task {
+        priority: Priority::Low,
+        size: 1
+    }])
+    .contains(&Task {
+        priority: Priority::Low,
+        size: 1
+    }));
+    // Narrowing keeps the low byte only.
+    assert_eq!(Sample::Byte(0x101), Sample::Byte(1));
+    assert_eq!(Sample::Byte(0x1ff).cmp(&Sample::Byte(2)), Ordering::Greater);
+    assert!(HashSet::from([Size::Bytes(1)]).contains(&Size::Bytes(1)));
+    assert!(!HashSet::from([Size::Bytes(1)]).contains(&Size::Bytes(2)));
+}
+
+#[test]
+fn test_borrow_key() {
+    #[derive(CmpBy)]
+    #[cmp_by(borrow_key = "str")]
+    struct Entry {
+        #[cmp_by]
+        name: String,
+        #[allow(dead_code)]
+        value: f32,
+    }
+
+    let entries = [
+        Entry {
+            name: "a".into(),
+            value: 0.5,
+        },
+        Entry {
+            name: "c".into(),
+            value: 0.1,
+        },
+    ];
+    assert!(entries[0] == *"a");
+    assert!(entries[0] < *"b");
+    assert!(entries[1] > *"b");
+    assert_eq!(entries.partition_point(|entry| *entry < *"b"), 1);
+}
+
+#[test]
+fn test_hash_with() {
+    use cmp_by_derive::HashBy;
+    use std::collections::HashSet;
+    use std::path::{Component, Path, PathBuf};
+
+    fn celsius_bits(celsius: &f64) -> u64 {
+        celsius.to_bits()
+    }
+
+    // `PathBuf` isn't `Copy`, the function borrows it.
+    fn components(path: &Path) -> Vec<Component<'_>> {
+        path.components().collect()
+    }
+
+    #[derive(CmpBy, HashBy, Debug)]
+    #[hash_by(verify_eq)]
+    struct Reading {
+        #[cmp_by(total)]
+        #[hash_by(with = "celsius_bits")]
+        celsius: f64,
+    }
+
+    #[derive(CmpBy, HashBy, Debug)]
+    struct Route {
+        #[cmp_by]
+        #[hash_by(with = "components")]
+        path: PathBuf,
+    }
+
+    let set = HashSet::from([Reading { celsius: 21.5 }, Reading { celsius: -0.0 }]);
+    assert!(set.contains(&Reading { celsius: 21.5 }));
+    assert!(set.contains(&Reading { celsius: -0.0 }));
+    assert!(!set.contains(&Reading { celsius: 0.0 }));
+
+    let routes = HashSet::from([Route {
+        path: PathBuf::from("usr/bin"),
+    }]);
+    // Paths are equal by their components, whatever their separators.
+    assert!(routes.contains(&Route {
+        path: PathBuf::from("usr//bin/")
+    }));
+    assert!(!routes.contains(&Route {
+        path: PathBuf::from("usr/lib")
+    }));
+}
+
+#[test]
+fn test_hash_top_level_and_fields() {
+    use cmp_by_derive::HashBy;
+    use std::collections::HashSet;
+    use std::hash::{BuildHasher, Hash, Hasher, RandomState};
+
+    #[derive(CmpBy, HashBy, Debug)]
+    #[cmp_by(key())]
+    #[hash_by(verify_eq, key())]
+    struct Entry {
+        #[cmp_by]
+        #[hash_by]
+        id: u32,
+        #[cmp_by]
+        #[hash_by]
+        name: &'static str,
+        text: String,
+    }
+
+    impl Entry {
+        fn key(&self) -> usize {
+            self.text.len()
+        }
+    }
+
+    let state = RandomState::new();
+    let mut hasher = state.build_hasher();
+    3usize.hash(&mut hasher);
+    1u32.hash(&mut hasher);
+    "a".hash(&mut hasher);
+    assert_eq!(
+        state.hash_one(Entry {
+            id: 1,
+            name: "a",
+            text: "abc".into()
+        }),
+        hasher.finish()
+    );
+
+    let set = HashSet::from([Entry {
+        id: 1,
+        name: "a",
+        text: "abc".into(),
+    }]);
+    assert!(set.contains(&Entry {
+        id: 1,
+        name: "a",
+        text: "xyz".into()
+    }));
+    assert!(!set.contains(&Entry {
+        id: 1,
+        name: "a",
+        text: "ab".into()
+    }));
+    assert!(!set.contains(&Entry {
+        id: 2,
+        name: "a",
+        text: "abc".into()
+    }));
+}
+
+#[test]
+fn test_hash_fields_position() {
+    use cmp_by_derive::HashBy;
+    use std::hash::{BuildHasher, Hash, Hasher, RandomState};
+
+    #[derive(HashBy)]
+    #[hash_by(_fields, kind())]
+    struct Fields {
+        #[hash_by]
+        id: u32,
+    }
+
+    impl Fields {
+        fn kind(&self) -> u8 {
+            7
+        }
+    }
+
+    #[derive(HashBy)]
+    #[hash_by(kind())]
+    struct Items {
+        #[hash_by]
+        id: u32,
+    }
+
+    impl Items {
+        fn kind(&self) -> u8 {
+            7
+        }
+    }
+
+    let state = RandomState::new();
+    let mut fields_first = state.build_hasher();
+    1u32.hash(&mut fields_first);
+    7u8.hash(&mut fields_first);
+    let mut items_first = state.build_hasher();
+    7u8.hash(&mut items_first);
+    1u32.hash(&mut items_first);
+    assert_eq!(state.hash_one(Fields { id: 1 }), fields_first.finish());
+    assert_eq!(state.hash_one(Items { id: 1 }), items_first.finish());
+}
+
+#[test]
+fn test_map() {
+    #[derive(CmpBy, Debug)]
+    struct Tag {
+        #[cmp_by(map = |name| name.trim(), reverse)]
+        name: String,
+        #[cmp_by(map = |weight: &f32| *weight as i32)]
+        weight: f32,
+    }
+
+    assert_eq!(
+        Tag {
+            name: " b".into(),
+            weight: 1.0
+        }
+        .cmp(&Tag {
+            name: "a ".into(),
+            weight: 0.0
+        }),
+        Ordering::Less
+    );
+    assert_eq!(
+        Tag {
+            name: " a".into(),
+            weight: 1.9
+        }
+        .cmp(&Tag {
+            name: "a ".into(),
+            weight: 2.1
+        }),
+        Ordering::Less
+    );
+    assert!(
+        Tag {
+            name: " a ".into(),
+            weight: 1.5
+        } == Tag {
+            name: "a".into(),
+            weight: 1.2
+        }
+    );
+}
+
+#[test]
+fn test_index() {
+    #[derive(CmpBy, Debug)]
+    struct Packet {
+        #[cmp_by(index = 0)]
+        header: Vec<u8>,
+        #[cmp_by(index_range = "1..3")]
+        payload: [u8; 4],
+    }
+
+    assert_eq!(
+        Packet {
+            header: vec![1, 9],
+            payload: [0; 4]
+        }
+        .cmp(&Packet {
+            header: vec![2, 0],
+            payload: [0; 4]
+        }),
+        Ordering::Less
+    );
+    assert_eq!(
+        Packet {
+            header: vec![1],
+            payload: [9, 1, 2, 0]
+        }
+        .cmp(&Packet {
+            header: vec![1, 5],
+            payload: [0, 1, 3, 0]
+        }),
+        Ordering::Less
+    );
+    assert!(
+        Packet {
+            header: vec![1, 2],
+            payload: [9, 1, 2, 9]
+        } == Packet {
+            header: vec![1],
+            payload: [0, 1, 2, 0]
+        }
+    );
+}
+
+#[test]
+#[should_panic]
+fn test_index_out_of_range() {
+    #[derive(CmpBy)]
+    struct Packet {
+        #[cmp_by(index = 2)]
+        header: Vec<u8>,
+    }
+
+    let _ = Packet { header: vec![1] }.cmp(&Packet { header: vec![1] });
+}
+
+#[test]
+fn test_none_last_direction() {
+    #[derive(CmpBy, Debug)]
+    struct Reversed {
+        #[cmp_by(none_last, reverse)]
+        value: Option<u8>,
+    }
+
+    #[derive(CmpBy, Debug)]
+    #[cmp_by(descending)]
+    struct Descending {
+        #[cmp_by(none_last)]
+        value: Option<u8>,
+    }
+
+    #[derive(CmpBy, Debug)]
+    #[cmp_by(descending, NoneLast(first()))]
+    struct DescendingItem {
+        values: Vec<u8>,
+    }
+
+    impl DescendingItem {
+        fn first(&self) -> Option<u8> {
+            self.values.first().copied()
+        }
+    }
+
+    #[derive(CmpBy, Debug)]
+    #[cmp_by(Reverse(_fields))]
+    struct ReversedFields {
+        #[cmp_by(none_last)]
+        value: Option<u8>,
+    }
+
+    // `None` is sorted last whichever way the values are reversed.
+    let values = [Some(1), None, Some(3)];
+    let mut reversed = values.map(|value| Reversed { value });
+    reversed.sort();
+    assert_eq!(reversed.map(|r| r.value), [Some(3), Some(1), None]);
+    let mut descending = values.map(|value| Descending { value });
+    descending.sort();
+    assert_eq!(descending.map(|d| d.value), [Some(3), Some(1), None]);
+    let mut descending = values.map(|value| DescendingItem {
+        values: value.into_iter().collect(),
+    });
+    descending.sort();
+    assert_eq!(descending.map(|d| d.first()), [Some(3), Some(1), None]);
+    let mut reversed = values.map(|value| ReversedFields { value });
+    reversed.sort();
+    assert_eq!(reversed.map(|r| r.value), [Some(3), Some(1), None]);
+}
+
+#[test]
+fn test_gen_key() {
+    #[derive(CmpBy, Debug, Clone)]
+    #[cmp_by(gen_key, descending, initial())]
+    struct Task {
+        #[cmp_by(none_last)]
+        due: Option<u32>,
+        #[cmp_by(by_len)]
+        name: String,
+    }
+
+    impl Task {
+        fn initial(&self) -> Option<char> {
+            self.name.chars().next()
+        }
+    }
+
+    let tasks = [
+        Task {
+            due: Some(2),
+            name: "ab".into(),
+        },
+        Task {
+            due: None,
+            name: "a".into(),
+        },
+        Task {
+            due: Some(1),
+            name: "abc".into(),
+        },
+        Task {
+            due: Some(1),
+            name: "b".into(),
+        },
+        Task {
+            due: None,
+            name: "bc".into(),
+        },
+        Task {
+            due: Some(1),
+            name: "a".into(),
+        },
+    ];
+    let mut by_ord = tasks.to_vec();
+    by_ord.sort();
+    let mut by_key = tasks.to_vec();
+    by_key.sort_by(|a, b| a.cmp_key().cmp(&b.cmp_key()));
+    assert_eq!(
+        by_ord.iter().map(|task| &task.name).collect::<Vec<_>>(),
+        by_key.iter().map(|task| &task.name).collect::<Vec<_>>()
+    );
+    for (a, b) in tasks.iter().zip(&tasks[1..]) {
+        assert_eq!(a.cmp(b), a.cmp_key().cmp(&b.cmp_key()));
+    }
+}
+
+#[test]
+fn test_gen_key_fields() {
+    use std::cmp::Reverse;
+    use std::collections::{HashMap, HashSet};
+
+    #[derive(CmpBy, Debug)]
+    #[cmp_by(gen_key, gen_min_heap_key)]
+    struct Task {
+        #[cmp_by(reverse)]
+        priority: u8,
+        #[cmp_by(none_last)]
+        owner: Option<&'static str>,
+        #[allow(dead_code)]
+        note: &'static str,
+    }
+
+    let tasks = [
+        Task {
+            priority: 1,
+            owner: Some("bob"),
+            note: "a",
+        },
+        Task {
+            priority: 2,
+            owner: None,
+            note: "b",
+        },
+        Task {
+            priority: 1,
+            owner: Some("bob"),
+            note: "c",
+        },
+    ];
+    // The key is a tuple of references to the fields, which can be named, destructured and hashed.
+    let key: (Reverse<&u8>, (bool, &Option<&str>)) = tasks[0].cmp_key();
+    let (Reverse(priority), (_, owner)) = key;
+    assert_eq!((*priority, *owner), (1, Some("bob")));
+    let distinct: HashSet<_> = tasks.iter().map(Task::cmp_key).collect();
+    assert_eq!(distinct.len(), 2);
+    let mut groups: HashMap<_, Vec<&str>> = HashMap::new();
+    for task in &tasks {
+        groups.entry(task.cmp_key()).or_default().push(task.note);
+    }
+    assert_eq!(groups[&tasks[0].cmp_key()], ["a", "c"]);
+    assert_eq!(
+        tasks[1].min_heap_key(),
+        Reverse((Reverse(&2), (true, &None)))
+    );
+    assert_eq!(
+        tasks[0].cmp(&tasks[1]),
+        tasks[0].cmp_key().cmp(&tasks[1].cmp_key())
+    );
+}
+
+#[test]
+fn test_gen_key_reverse_none_last() {
+    use std::cmp::Reverse;
+
+    #[derive(CmpBy, Debug)]
+    #[cmp_by(gen_key)]
+    struct Bid {
+        #[cmp_by(reverse, none_last)]
+        price: Option<u32>,
+    }
+
+    #[derive(CmpBy, Debug)]
+    #[cmp_by(gen_key, Reverse(NoneLast(price())))]
+    struct Offer {
+        prices: Vec<u32>,
+    }
+
+    impl Offer {
+        fn price(&self) -> Option<u32> {
+            self.prices.first().copied()
+        }
+    }
+
+    let bids = [None, Some(1), Some(3)].map(|price| Bid { price });
+    let offers = [vec![], vec![1], vec![3]].map(|prices| Offer { prices });
+    // `None` stays last, only the value is reversed.
+    let key: ((bool, Reverse<&Option<u32>>),) = bids[0].cmp_key();
+    assert_eq!(key, ((true, Reverse(&None)),));
+    for (a, b) in bids.iter().flat_map(|a| bids.iter().map(move |b| (a, b))) {
+        assert_eq!(a.cmp(b), a.cmp_key().cmp(&b.cmp_key()));
+    }
+    for (a, b) in offers
+        .iter()
+        .flat_map(|a| offers.iter().map(move |b| (a, b)))
+    {
+        assert_eq!(a.cmp(b), a.cmp_key().cmp(&b.cmp_key()));
+    }
+    assert!(bids[2].cmp_key() < bids[1].cmp_key() && bids[1].cmp_key() < bids[0].cmp_key());
+}
+
+#[test]
+fn test_gen_key_projected_fields() {
+    #[derive(CmpBy, Debug)]
+    #[cmp_by(gen_key)]
+    struct Player {
+        #[cmp_by(key = "str::len")]
+        name: String,
+        #[cmp_by(map = |scores: &Vec<u32>| scores.iter().sum::<u32>(), reverse)]
+        scores: Vec<u32>,
+    }
+
+    let players = [
+        Player {
+            name: "bob".into(),
+            scores: vec![1, 2],
+        },
+        Player {
+            name: "eve".into(),
+            scores: vec![4],
+        },
+        Player {
+            name: "alice".into(),
+            scores: vec![9],
+        },
+    ];
+    // The keys of projected fields can't be named, so the key is opaque but still orders like `Ord::cmp`.
+    for a in &players {
+        for b in &players {
+            assert_eq!(a.cmp(b), a.cmp_key().cmp(&b.cmp_key()));
+        }
+    }
+    assert!(players[1].cmp_key() < players[0].cmp_key());
+}
+
+#[test]
+fn test_impl_sort_key() {
+    use std::cmp::Reverse;
+
+    trait SortKey {
+        type Key<'a>: Ord
+        where
+            Self: 'a;
+
+        fn sort_key(&self) -> Self::Key<'_>;
+    }
+
+    #[derive(CmpBy, Debug)]
+    #[cmp_by(impl_sort_key)]
+    struct Task<'n, T> {
+        #[cmp_by(reverse)]
+        priority: u8,
+        #[cmp_by(none_last)]
+        deadline: Option<u32>,
+        #[cmp_by]
+        name: &'n str,
+        #[cmp_by]
+        payload: T,
+    }
+
+    #[derive(CmpBy)]
+    #[cmp_by(impl_sort_key, descending)]
+    struct Score(#[cmp_by] u32);
+
+    fn sorted<T: SortKey>(mut items: Vec<T>) -> Vec<T> {
+        items.sort_by(|a, b| a.sort_key().cmp(&b.sort_key()));
+        items
+    }
+
+    fn greatest<T: SortKey>(items: &[T]) -> Option<&T> {
+        items.iter().max_by(|a, b| a.sort_key().cmp(&b.sort_key()))
+    }
+
+    let tasks = sorted(vec![
+        Task {
+            priority: 1,
+            deadline: Some(3),
+            name: "a",
+            payload: (),
+        },
+        Task {
+            priority: 2,
+            deadline: None,
+            name: "b",
+            payload: (),
+        },
+        Task {
+            priority: 2,
+            deadline: Some(1),
+            name: "c",
+            payload: (),
+        },
+        Task {
+            priority: 2,
+            deadline: Some(1),
+            name: "a",
+            payload: (),
+        },
+    ]);
+    assert_eq!(
+        tasks.iter().map(|task| task.name).collect::<Vec<_>>(),
+        ["a", "c", "b", "a"]
+    );
+    // The key orders like `Ord::cmp`.
+    assert!(tasks.windows(2).all(|pair| pair[0] <= pair[1]));
+    let key: (Reverse<&u8>, (bool, &Option<u32>), &&str, &()) = tasks[0].sort_key();
+    assert_eq!(key, (Reverse(&2), (false, &Some(1)), &"a", &()));
+
+    let scores = [Score(3), Score(1), Score(2)];
+    assert_eq!(greatest(&scores).map(|score| score.0), Some(1));
+}
+
+#[test]
+fn test_tuple_cmp() {
+    #[derive(CmpBy, Debug)]
+    #[cmp_by(tuple_cmp, name.len(), _fields)]
+    struct Task {
+        name: String,
+        #[cmp_by(reverse)]
+        priority: u8,
+        #[cmp_by(none_last)]
+        due: Option<u32>,
+    }
+
+    assert_eq!(
+        Task {
+            name: "ab".into(),
+            priority: 0,
+            due: None
+        }
+        .cmp(&Task {
+            name: "a".into(),
+            priority: 9,
+            due: Some(1)
+        }),
+        Ordering::Greater
+    );
+    assert_eq!(
+        Task {
+            name: "a".into(),
+            priority: 2,
+            due: None
+        }
+        .cmp(&Task {
+            name: "b".into(),
+            priority: 1,
+            due: None
+        }),
+        Ordering::Less
+    );
+    assert_eq!(
+        Task {
+            name: "a".into(),
+            priority: 1,
+            due: None
+        }
+        .cmp(&Task {
+            name: "b".into(),
+            priority: 1,
+            due: Some(5)
+        }),
+        Ordering::Greater
+    );
+    assert_eq!(
+        Task {
+            name: "a".into(),
+            priority: 1,
+            due: Some(5)
+        }
+        .cmp(&Task {
+            name: "b".into(),
+            priority: 1,
+            due: Some(5)
+        }),
+        Ordering::Equal
+    );
+}
+
+#[test]
+fn test_tuple_cmp_reversed_fields() {
+    #[derive(CmpBy, Debug)]
+    #[cmp_by(gen_key, Reverse(_fields), label.len())]
+    struct Chained {
+        label: &'static str,
+        #[cmp_by]
+        priority: u8,
+        #[cmp_by(reverse)]
+        age: u8,
+        #[cmp_by(none_last)]
+        due: Option<u8>,
+    }
+
+    #[derive(CmpBy, Debug)]
+    #[cmp_by(tuple_cmp, Reverse(_fields), label.len())]
+    struct Tupled {
+        label: &'static str,
+        #[cmp_by]
+        priority: u8,
+        #[cmp_by(reverse)]
+        age: u8,
+        #[cmp_by(none_last)]
+        due: Option<u8>,
+    }
+
+    let values = [0, 1]
+        .into_iter()
+        .flat_map(|priority| [0, 1].map(|age| (priority, age)))
+        .flat_map(|(priority, age)| [None, Some(0), Some(1)].map(|due| (priority, age, due)))
+        .flat_map(|(priority, age, due)| ["a", "ab"].map(|label| (label, priority, age, due)))
+        .collect::<Vec<_>>();
+    for &(label, priority, age, due) in &values {
+        for &(other_label, other_priority, other_age, other_due) in &values {
+            let (a, b) = (
+                Chained {
+                    label,
+                    priority,
+                    age,
+                    due,
+                },
+                Chained {
+                    label: other_label,
+                    priority: other_priority,
+                    age: other_age,
+                    due: other_due,
+                },
+            );
+            let tupled = Tupled {
+                label,
+                priority,
+                age,
+                due,
+            }
+            .cmp(&Tupled {
+                label: other_label,
+                priority: other_priority,
+                age: other_age,
+                due: other_due,
+            });
+            assert_eq!(a.cmp(&b), tupled);
+            assert_eq!(a.cmp(&b), a.cmp_key().cmp(&b.cmp_key()));
+        }
+    }
+}
+
+#[test]
+fn test_const_fn() {
+    #[derive(CmpBy, Clone, Copy, Debug)]
+    #[cmp_by(const_fn)]
+    struct Entry {
+        #[cmp_by(reverse)]
+        priority: u8,
+        #[cmp_by]
+        code: char,
+        #[cmp_by(abs)]
+        offset: i16,
+    }
+
+    const fn sorted<const N: usize>(mut entries: [Entry; N]) -> [Entry; N] {
+        let mut i = 1;
+        while i < N {
+            let mut j = i;
+            while j > 0 && entries[j].const_cmp(&entries[j - 1]).is_lt() {
+                let entry = entries[j];
+                entries[j] = entries[j - 1];
+                entries[j - 1] = entry;
+                j -= 1;
+            }
+            i += 1;
+        }
+        entries
+    }
+
+    const TABLE: [Entry; 4] = sorted([
+        Entry {
+            priority: 1,
+            code: 'b',
+            offset: 0,
+        },
+        Entry {
+            priority: 2,
+            code: 'z',
+            offset: 0,
+        },
+        Entry {
+            priority: 1,
+            code: 'a',
+            offset: -3,
+        },
+        Entry {
+            priority: 1,
+            code: 'a',
+            offset: 2,
+        },
+    ]);
+    assert_eq!(
+        TABLE.map(|entry| (entry.priority, entry.code, entry.offset)),
+        [(2, 'z', 0), (1, 'a', 2), (1, 'a', -3), (1, 'b', 0)]
+    );
+    for pair in TABLE.windows(2) {
+        assert_eq!(pair[0].const_cmp(&pair[1]), pair[0].cmp(&pair[1]));
+    }
+}
+
+#[test]
+fn test_gen_min_heap_key() {
+    use std::collections::BinaryHeap;
+
+    #[derive(CmpBy, Debug)]
+    #[cmp_by(gen_min_heap_key)]
+    struct Event {
+        #[cmp_by]
+        time: u64,
+        #[cmp_by(reverse)]
+        priority: u8,
+    }
+
+    let events = [
+        Event {
+            time: 2,
+            priority: 0,
+        },
+        Event {
+            time: 1,
+            priority: 0,
+        },
+        Event {
+            time: 2,
+            priority: 5,
+        },
+    ];
+    let mut heap: BinaryHeap<_> = events
+        .iter()
+        .map(|event| (event.min_heap_key(), event))
+        .collect();
+    let mut popped = Vec::new();
+    while let Some((_, event)) = heap.pop() {
+        popped.push((event.time, event.priority));
+    }
+    assert_eq!(popped, [(1, 0), (2, 5), (2, 0)]);
+}
+
+#[test]
+fn test_call_evaluated_once() {
+    use std::cell::Cell;
+
+    #[derive(CmpBy)]
+    #[cmp_by(NoneLast(channel()))]
+    struct Note {
+        channel: Option<u8>,
+        calls: Cell<u32>,
+    }
+
+    impl Note {
+        fn channel(&self) -> Option<&u8> {
+            self.calls.set(self.calls.get() + 1);
+            self.channel.as_ref()
+        }
+    }
+
+    let a = Note {
+        channel: Some(1),
+        calls: Cell::new(0),
+    };
+    let b = Note {
+        channel: None,
+        calls: Cell::new(0),
+    };
+    assert_eq!(a.cmp(&b), Ordering::Less);
+    assert_eq!((a.calls.get(), b.calls.get()), (1, 1));
+    assert_eq!(
+        b.cmp(&Note {
+            channel: None,
+            calls: Cell::new(0)
+        }),
+        Ordering::Equal
+    );
+    assert_eq!(b.calls.get(), 2);
+}
+
+#[test]
+fn test_method_chain() {
+    use std::cell::Cell;
+
+    struct Payload<'a> {
+        bytes: &'a [u8],
+        calls: &'a Cell<u32>,
+    }
+
+    impl Payload<'_> {
+        fn checksum(&self) -> u32 {
+            self.calls.set(self.calls.get() + 1);
+            self.bytes.iter().map(|&byte| u32::from(byte)).sum()
+        }
+    }
+
+    #[derive(CmpBy)]
+    #[cmp_by(payload().checksum())]
+    struct Packet {
+        bytes: Vec<u8>,
+        calls: Cell<u32>,
+    }
+
+    impl Packet {
+        fn payload(&self) -> Payload<'_> {
+            Payload {
+                bytes: &self.bytes,
+                calls: &self.calls,
+            }
+        }
+    }
+
+    let a = Packet {
+        bytes: vec![1, 2],
+        calls: Cell::new(0),
+    };
+    let b = Packet {
+        bytes: vec![4],
+        calls: Cell::new(0),
+    };
+    assert_eq!(a.cmp(&b), Ordering::Less);
+    assert_eq!((a.calls.get(), b.calls.get()), (1, 1));
+    assert!(
+        Packet {
+            bytes: vec![3],
+            calls: Cell::new(0)
+        } == Packet {
+            bytes: vec![1, 2],
+            calls: Cell::new(0)
+        }
+    );
+}
+
+#[test]
+fn test_explicit_self() {
+    use cmp_by_derive::HashBy;
+    use std::collections::HashSet;
+
+    struct Meta {
+        rank: u8,
+    }
+
+    #[derive(CmpBy, HashBy)]
+    #[cmp_by(self.meta.rank, Reverse(self.name()), id)]
+    #[hash_by(self.meta.rank, name(), self.id)]
+    struct Entry {
+        id: u32,
+        name: &'static str,
+        meta: Meta,
+    }
+
+    impl Entry {
+        fn name(&self) -> &str {
+            self.name
+        }
+    }
+
+    assert_eq!(
+        Entry {
+            id: 0,
+            name: "a",
+            meta: Meta { rank: 1 }
+        }
+        .cmp(&Entry {
+            id: 0,
+            name: "a",
+            meta: Meta { rank: 2 }
+        }),
+        Ordering::Less
+    );
+    assert_eq!(
+        Entry {
+            id: 0,
+            name: "a",
+            meta: Meta { rank: 1 }
+        }
+        .cmp(&Entry {
+            id: 0,
+            name: "b",
+            meta: Meta { rank: 1 }
+        }),
+        Ordering::Greater
+    );
+    assert_eq!(
+        Entry {
+            id: 0,
+            name: "a",
+            meta: Meta { rank: 1 }
+        }
+        .cmp(&Entry {
+            id: 1,
+            name: "a",
+            meta: Meta { rank: 1 }
+        }),
+        Ordering::Less
+    );
+    assert!(HashSet::from([Entry {
+        id: 0,
+        name: "a",
+        meta: Meta { rank: 1 }
+    }])
+    .contains(&Entry {
+        id: 0,
+        name: "a",
+        meta: Meta { rank: 1 }
+    }));
+}
+
+#[test]
+fn test_wrapper_types() {
+    use cmp_by_derive::HashBy;
+    use std::borrow::Cow;
+    use std::collections::HashSet;
+    use std::sync::Arc;
+
+    #[derive(CmpBy, HashBy, Debug)]
+    struct Entry<'a> {
+        #[cmp_by]
+        #[hash_by]
+        name: Cow<'a, str>,
+        #[cmp_by]
+        #[hash_by]
+        tag: Arc<str>,
+        #[cmp_by]
+        #[hash_by]
+        payload: Box<[u8]>,
+    }
+
+    let borrowed = String::from("a");
+    assert_eq!(
+        Entry {
+            name: Cow::Borrowed(&borrowed),
+            tag: Arc::from("t"),
+            payload: Box::new([1])
+        },
+        Entry {
+            name: Cow::Owned("a".into()),
+            tag: Arc::from("t"),
+            payload: Box::new([1])
+        }
+    );
+    assert!(
+        Entry {
+            name: Cow::Borrowed(&borrowed),
+            tag: Arc::from("t"),
+            payload: Box::new([2])
+        } < Entry {
+            name: Cow::Borrowed("b"),
+            tag: Arc::from("t"),
+            payload: Box::new([1])
+        }
+    );
+    assert!(
+        Entry {
+            name: Cow::Borrowed("a"),
+            tag: Arc::from("t"),
+            payload: Box::new([1, 2])
+        } > Entry {
+            name: Cow::Borrowed("a"),
+            tag: Arc::from("t"),
+            payload: Box::new([1])
+        }
+    );
+    let set: HashSet<_> = [
+        Entry {
+            name: Cow::Borrowed(&borrowed),
+            tag: Arc::from("t"),
+            payload: Box::new([1]),
+        },
+        Entry {
+            name: Cow::Owned("a".into()),
+            tag: Arc::from("t"),
+            payload: Box::new([1]),
+        },
+    ]
+    .into();
+    assert_eq!(set.len(), 1);
+
+    // The bound is on the `Cow`, which carries the `ToOwned` bound of the parameter.
+    #[derive(CmpBy)]
+    struct Generic<'a, T: ?Sized + ToOwned> {
+        #[cmp_by]
+        value: Cow<'a, T>,
+    }
+
+    let (a, b) = (vec![1, 2], vec![1, 3]);
+    assert!(
+        Generic {
+            value: Cow::Borrowed(a.as_slice())
+        } < Generic {
+            value: Cow::Owned(b)
+        }
+    );
+}
+
+#[test]
+fn test_opaque_keys() {
+    #[derive(CmpBy, Debug)]
+    #[cmp_by(Reverse(peak()), NoneLast(first_word()))]
+    struct Series {
+        values: Vec<i32>,
+        #[cmp_by(key = "longest")]
+        labels: Vec<String>,
+    }
+
+    fn longest(labels: &[String]) -> impl Ord + '_ {
+        labels
+            .iter()
+            .map(String::as_str)
+            .max_by_key(|label| label.len())
+    }
+
+    impl Series {
+        fn peak(&self) -> impl Ord + '_ {
+            self.values.iter().map(|value| value.abs()).max()
+        }
+
+        fn first_word(&self) -> Option<impl Ord + '_> {
+            self.labels
+                .first()
+                .and_then(|label| label.split(' ').next())
+        }
+    }
+
+    assert_eq!(
+        Series {
+            values: vec![-5, 1],
+            labels: vec![]
+        }
+        .cmp(&Series {
+            values: vec![4],
+            labels: vec![]
+        }),
+        Ordering::Less
+    );
+    assert_eq!(
+        Series {
+            values: vec![1],
+            labels: vec!["b x".into()]
+        }
+        .cmp(&Series {
+            values: vec![1],
+            labels: vec![]
+        }),
+        Ordering::Less
+    );
+    assert_eq!(
+        Series {
+            values: vec![1],
+            labels: vec!["a".into(), "ccc".into()]
+        }
+        .cmp(&Series {
+            values: vec![1],
+            labels: vec!["a".into(), "bb".into()]
+        }),
+        Ordering::Greater
+    );
+}
+
+#[test]
+fn test_accessor_return_types() {
+    use cmp_by_derive::HashBy;
+    use std::collections::HashSet;
+
+    #[derive(CmpBy, HashBy)]
+    #[cmp_by(len(), name(), Reverse(id()), first_tag(), label())]
+    #[hash_by(len(), name(), id(), first_tag(), label())]
+    struct Item {
+        name: String,
+        id: u32,
+        tags: Vec<u8>,
+    }
+
+    impl Item {
+        fn len(&self) -> usize {
+            self.name.len()
+        }
+
+        fn name(&self) -> &str {
+            &self.name
+        }
+
+        fn id(&self) -> &u32 {
+            &self.id
+        }
+
+        fn first_tag(&self) -> Option<&u8> {
+            self.tags.first()
+        }
+
+        fn label(&self) -> String {
+            format!("{}#{}", self.name, self.id)
+        }
+    }
+
+    assert_eq!(
+        Item {
+            name: "bb".into(),
+            id: 0,
+            tags: vec![]
+        }
+        .cmp(&Item {
+            name: "a".into(),
+            id: 0,
+            tags: vec![]
+        }),
+        Ordering::Greater
+    );
+    assert_eq!(
+        Item {
+            name: "a".into(),
+            id: 0,
+            tags: vec![]
+        }
+        .cmp(&Item {
+            name: "b".into(),
+            id: 0,
+            tags: vec![]
+        }),
+        Ordering::Less
+    );
+    assert_eq!(
+        Item {
+            name: "a".into(),
+            id: 1,
+            tags: vec![]
+        }
+        .cmp(&Item {
+            name: "a".into(),
+            id: 2,
+            tags: vec![]
+        }),
+        Ordering::Greater
+    );
+    assert_eq!(
+        Item {
+            name: "a".into(),
+            id: 1,
+            tags: vec![2]
+        }
+        .cmp(&Item {
+            name: "a".into(),
+            id: 1,
+            tags: vec![1]
+        }),
+        Ordering::Greater
+    );
+    assert_eq!(
+        Item {
+            name: "a".into(),
+            id: 1,
+            tags: vec![1, 2]
+        }
+        .cmp(&Item {
+            name: "a".into(),
+            id: 1,
+            tags: vec![1, 3]
+        }),
+        Ordering::Equal
+    );
+    assert!(HashSet::from([Item {
+        name: "a".into(),
+        id: 1,
+        tags: vec![1]
+    }])
+    .contains(&Item {
+        name: "a".into(),
+        id: 1,
+        tags: vec![1, 2]
+    }));
+}
+
+#[test]
+fn test_eq_only() {
+    #[derive(CmpBy, Debug)]
+    struct Blob {
+        #[cmp_by]
+        name: &'static str,
+        #[cmp_by(eq)]
+        digest: u64,
+    }
+
+    assert_eq!(
+        Blob {
+            name: "a",
+            digest: 2
+        }
+        .cmp(&Blob {
+            name: "b",
+            digest: 1
+        }),
+        Ordering::Less
+    );
+    assert_eq!(
+        Blob {
+            name: "a",
+            digest: 2
+        }
+        .cmp(&Blob {
+            name: "a",
+            digest: 1
+        }),
+        Ordering::Equal
+    );
+    assert!(
+        Blob {
+            name: "a",
+            digest: 1
+        } == Blob {
+            name: "a",
+            digest: 1
+        }
+    );
+    assert!(
+        Blob {
+            name: "a",
+            digest: 1
+        } != Blob {
+            name: "a",
+            digest: 2
+        }
+    );
+}
+
+#[test]
+fn test_prefix() {
+    use cmp_by_derive::HashBy;
+    use std::collections::HashSet;
+
+    #[derive(CmpBy, HashBy, Debug)]
+    #[cmp_by(prefix = "k_")]
+    #[hash_by(prefix = "k_")]
+    struct Row {
+        k_table: &'static str,
+        #[cmp_by(reverse)]
+        k_id: u32,
+        #[allow(dead_code)]
+        payload: f32,
+    }
+
+    assert_eq!(
+        Row {
+            k_table: "a",
+            k_id: 1,
+            payload: 0.0
+        }
+        .cmp(&Row {
+            k_table: "b",
+            k_id: 0,
+            payload: 0.0
+        }),
+        Ordering::Less
+    );
+    assert_eq!(
+        Row {
+            k_table: "a",
+            k_id: 1,
+            payload: 0.0
+        }
+        .cmp(&Row {
+            k_table: "a",
+            k_id: 2,
+            payload: 0.0
+        }),
+        Ordering::Greater
+    );
+    assert!(
+        Row {
+            k_table: "a",
+            k_id: 1,
+            payload: 0.0
+        } == Row {
+            k_table: "a",
+            k_id: 1,
+            payload: 1.0
+        }
+    );
+    assert!(HashSet::from([Row {
+        k_table: "a",
+        k_id: 1,
+        payload: 0.0
+    }])
+    .contains(&Row {
+        k_table: "a",
+        k_id: 1,
+        payload: 1.0
+    }));
+}
+
+#[test]
+fn test_cfg() {
+    use cmp_by_derive::HashBy;
+    use std::collections::HashSet;
+
+    // `any()` never holds, so the marked field is removed before the derives run, whatever the features.
+    #[derive(CmpBy, HashBy, Debug)]
+    struct Entry {
+        #[cmp_by]
+        #[hash_by]
+        id: u32,
+        #[cfg(any())]
+        #[cmp_by]
+        #[hash_by]
+        tag: &'static str,
+        #[cfg(not(any()))]
+        #[allow(dead_code)]
+        tag: &'static str,
+    }
+
+    assert_eq!(
+        Entry { id: 1, tag: "b" }.cmp(&Entry { id: 2, tag: "a" }),
+        Ordering::Less
+    );
+    assert_eq!(
+        Entry { id: 1, tag: "a" }.cmp(&Entry { id: 1, tag: "b" }),
+        Ordering::Equal
+    );
+    assert!(HashSet::from([Entry { id: 1, tag: "a" }]).contains(&Entry { id: 1, tag: "b" }));
+}
+
+#[test]
+fn test_both_some_only() {
+    #[derive(CmpBy, Debug)]
+    struct Record {
+        #[cmp_by(both_some_only, reverse)]
+        version: Option<u32>,
+        #[cmp_by]
+        id: u32,
+    }
+
+    assert_eq!(
+        Record {
+            version: Some(1),
+            id: 0
+        }
+        .cmp(&Record {
+            version: Some(2),
+            id: 0
+        }),
+        Ordering::Greater
+    );
+    assert_eq!(
+        Record {
+            version: None,
+            id: 0
+        }
+        .cmp(&Record {
+            version: Some(2),
+            id: 1
+        }),
+        Ordering::Less
+    );
+    assert_eq!(
+        Record {
+            version: Some(1),
+            id: 1
+        }
+        .cmp(&Record {
+            version: None,
+            id: 0
+        }),
+        Ordering::Greater
+    );
+    assert!(
+        Record {
+            version: Some(1),
+            id: 0
+        } == Record {
+            version: None,
+            id: 0
+        }
+    );
+    assert!(
+        Record {
+            version: Some(1),
+            id: 0
+        } != Record {
+            version: Some(2),
+            id: 0
+        }
+    );
+}
+
+#[test]
+fn test_ord_only() {
+    #[derive(CmpBy, Debug)]
+    #[cmp_by(ord_only)]
+    struct Version {
+        #[cmp_by]
+        major: u16,
+        #[cmp_by]
+        minor: u16,
+    }
+
+    // Equality is implemented by hand, consistently with the derived ordering.
+    impl PartialEq for Version {
+        fn eq(&self, other: &Self) -> bool {
+            self.cmp(other) == Ordering::Equal
+        }
+    }
+    impl Eq for Version {}
+
+    assert_eq!(
+        Version { major: 1, minor: 2 }.cmp(&Version { major: 2, minor: 0 }),
+        Ordering::Less
+    );
+    assert_eq!(
+        Version { major: 1, minor: 2 }.cmp(&Version { major: 1, minor: 1 }),
+        Ordering::Greater
+    );
+    assert_eq!(
+        Version { major: 1, minor: 2 },
+        Version { major: 1, minor: 2 }
+    );
+    assert_ne!(
+        Version { major: 1, minor: 2 },
+        Version { major: 1, minor: 3 }
+    );
+}
+
+#[test]
+fn test_eq_only_flag() {
+    use cmp_by_derive::HashBy;
+    use std::collections::HashSet;
+
+    #[derive(CmpBy, HashBy)]
+    #[cmp_by(eq_only)]
+    struct Tag {
+        #[cmp_by]
+        #[hash_by]
+        name: &'static str,
+    }
+
+    impl PartialOrd for Tag {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            self.name.partial_cmp(other.name)
+        }
+    }
+
+    assert!(Tag { name: "a" } == Tag { name: "a" });
+    assert!(Tag { name: "a" } != Tag { name: "b" });
+    assert!(Tag { name: "a" } < Tag { name: "b" });
+    assert!(HashSet::from([Tag { name: "a" }]).contains(&Tag { name: "a" }));
+}
+
+#[test]
+fn test_unrelated_attributes() {
+    /// A user.
+    #[derive(CmpBy, Debug)]
+    #[allow(dead_code)]
+    #[cmp_by(name)]
+    struct User {
+        /// The id, compared after the name.
+        #[allow(unused)]
+        #[cmp_by]
+        #[doc = "Unique."]
+        id: u32,
+        /// Not compared.
+        #[doc(hidden)]
+        email: &'static str,
+        name: &'static str,
+    }
+
+    assert_eq!(
+        User {
+            id: 2,
+            email: "",
+            name: "a"
+        }
+        .cmp(&User {
+            id: 1,
+            email: "",
+            name: "b"
+        }),
+        Ordering::Less
+    );
+    assert_eq!(
+        User {
+            id: 2,
+            email: "",
+            name: "a"
+        }
+        .cmp(&User {
+            id: 1,
+            email: "",
+            name: "a"
+        }),
+        Ordering::Greater
+    );
+}
+
+#[test]
+fn test_floats_total() {
+    use cmp_by_derive::PartialOrdBy;
+
+    #[derive(CmpBy, Debug)]
+    #[cmp_by(floats = "total")]
+    struct Measure {
+        #[cmp_by]
+        value: f64,
+        #[cmp_by(reverse)]
+        weight: f32,
+    }
+
+    assert_eq!(
+        Measure {
+            value: 1.5,
+            weight: 0.0
+        }
+        .cmp(&Measure {
+            value: 2.5,
+            weight: 0.0
+        }),
+        Ordering::Less
+    );
+    assert_eq!(
+        Measure {
+            value: 1.5,
+            weight: 2.0
+        }
+        .cmp(&Measure {
+            value: 1.5,
+            weight: 1.0
+        }),
+        Ordering::Less
+    );
+    assert_eq!(
+        Measure {
+            value: -0.0,
+            weight: 0.0
+        }
+        .cmp(&Measure {
+            value: 0.0,
+            weight: 0.0
+        }),
+        Ordering::Less
+    );
+    assert_eq!(
+        Measure {
+            value: f64::NAN,
+            weight: 0.0
+        },
+        Measure {
+            value: f64::NAN,
+            weight: 0.0
+        }
+    );
+    let mut measures = [
+        Measure {
+            value: f64::NAN,
+            weight: 0.0,
+        },
+        Measure {
+            value: 1.0,
+            weight: 0.0,
+        },
+        Measure {
+            value: -1.0,
+            weight: 0.0,
+        },
+    ];
+    measures.sort();
+    assert_eq!(
+        measures.map(|m| m.value.to_bits())[..2],
+        [(-1.0f64).to_bits(), 1.0f64.to_bits()]
+    );
+
+    #[derive(PartialOrdBy, PartialEq)]
+    #[cmp_by(floats = "total")]
+    struct Reading {
+        #[cmp_by]
+        celsius: f32,
+    }
+
+    assert_eq!(
+        Reading { celsius: -0.0 }.partial_cmp(&Reading { celsius: 0.0 }),
+        Some(Ordering::Less)
+    );
+}
+
+#[cfg(feature = "ordered-float")]
+#[test]
+fn test_ordered_float() {
+    use cmp_by_derive::HashBy;
+    use std::collections::HashSet;
+
+    #[derive(CmpBy, HashBy, Debug)]
+    #[hash_by(verify_eq)]
+    struct Point {
+        #[cmp_by(ordered_float)]
+        #[hash_by(ordered_float)]
+        x: f64,
+        #[cmp_by(ordered_float, reverse)]
+        #[hash_by(ordered_float)]
+        y: f32,
+    }
+
+    #[derive(CmpBy, HashBy, Debug)]
+    enum Shape {
+        Circle(
+            #[cmp_by(ordered_float)]
+            #[hash_by(ordered_float)]
+            f64,
+        ),
+    }
+
+    assert_eq!(
+        Point { x: 1.0, y: 0.0 }.cmp(&Point { x: 2.0, y: 0.0 }),
+        Ordering::Less
+    );
+    assert_eq!(
+        Point { x: 1.0, y: 1.0 }.cmp(&Point { x: 1.0, y: 2.0 }),
+        Ordering::Greater
+    );
+    // `OrderedFloat` puts NaN above every other value, and considers every NaN equal.
+    assert_eq!(
+        Point {
+            x: f64::NAN,
+            y: 0.0
+        }
+        .cmp(&Point {
+            x: f64::INFINITY,
+            y: 0.0
+        }),
+        Ordering::Greater
+    );
+    assert!(
+        Point {
+            x: f64::NAN,
+            y: 0.0
+        } == Point {
+            x: -f64::NAN,
+            y: 0.0
+        }
+    );
+    assert!(Point { x: 0.0, y: 0.0 } == Point { x: -0.0, y: -0.0 });
+    let points = HashSet::from([
+        Point {
+            x: f64::NAN,
+            y: 0.0,
+        },
+        Point { x: 0.0, y: 1.0 },
+    ]);
+    assert!(points.contains(&Point {
+        x: -f64::NAN,
+        y: 0.0
+    }));
+    assert!(points.contains(&Point { x: -0.0, y: 1.0 }));
+    assert!(!points.contains(&Point { x: 0.0, y: 2.0 }));
+
+    assert_eq!(
+        Shape::Circle(1.5).cmp(&Shape::Circle(f64::NAN)),
+        Ordering::Less
+    );
+    assert!(HashSet::from([Shape::Circle(0.0)]).contains(&Shape::Circle(-0.0)));
+}
+
+#[test]
+fn test_as_sorted() {
+    use cmp_by_derive::HashBy;
+    use std::collections::{HashMap, HashSet};
+
+    #[derive(CmpBy, HashBy, Debug)]
+    #[hash_by(verify_eq)]
+    struct Inventory {
+        #[cmp_by(as_sorted)]
+        #[hash_by(as_sorted)]
+        stock: HashMap<&'static str, u32>,
+        #[cmp_by(as_sorted, reverse)]
+        #[hash_by(as_sorted)]
+        tags: HashSet<u8>,
+    }
+
+    assert_eq!(
+        Inventory {
+            stock: HashMap::from([("apple", 1), ("pear", 2)]),
+            tags: HashSet::new()
+        }
+        .cmp(&Inventory {
+            stock: HashMap::from([("apple", 2)]),
+            tags: HashSet::new()
+        }),
+        Ordering::Less
+    );
+    assert_eq!(
+        Inventory {
+            stock: HashMap::from([("pear", 2), ("apple", 1)]),
+            tags: HashSet::new()
+        }
+        .cmp(&Inventory {
+            stock: HashMap::from([("apple", 1)]),
+            tags: HashSet::new()
+        }),
+        Ordering::Greater
+    );
+    assert_eq!(
+        Inventory {
+            stock: HashMap::new(),
+            tags: HashSet::from([1, 2])
+        }
+        .cmp(&Inventory {
+            stock: HashMap::new(),
+            tags: HashSet::from([1, 3])
+        }),
+        Ordering::Greater
+    );
+    assert_eq!(
+        Inventory {
+            stock: HashMap::new(),
+            tags: (0..100).collect()
+        },
+        Inventory {
+            stock: HashMap::new(),
+            tags: (0..100).rev().collect()
+        }
+    );
+    assert!(HashSet::from([Inventory {
+        stock: HashMap::from([("a", 1), ("b", 2)]),
+        tags: (0..100).collect()
+    }])
+    .contains(&Inventory {
+        stock: HashMap::from([("b", 2), ("a", 1)]),
+        tags: (0..100).rev().collect()
+    }));
+}
+
+#[cfg(feature = "indexmap")]
+#[test]
+fn test_as_pairs() {
+    use cmp_by_derive::HashBy;
+    use indexmap::IndexMap;
+    use std::collections::HashSet;
+
+    #[derive(CmpBy, HashBy, Debug)]
+    #[hash_by(verify_eq)]
+    struct Pipeline {
+        #[cmp_by(as_pairs)]
+        #[hash_by(as_pairs)]
+        stages: IndexMap<&'static str, u32>,
+    }
+
+    #[derive(CmpBy, Debug)]
+    enum Config {
+        Stages(#[cmp_by(as_pairs, reverse)] IndexMap<&'static str, u32>),
+    }
+
+    let build_test = Pipeline {
+        stages: IndexMap::from([("build", 1), ("test", 2)]),
+    };
+    let test_build = Pipeline {
+        stages: IndexMap::from([("test", 2), ("build", 1)]),
+    };
+    // `IndexMap` itself ignores the order, the entries are compared in insertion order instead.
+    assert_eq!(build_test.stages, test_build.stages);
+    assert!(build_test != test_build);
+    assert_eq!(build_test.cmp(&test_build), Ordering::Less);
+    assert_eq!(
+        Pipeline {
+            stages: IndexMap::from([("build", 1)]),
+        }
+        .cmp(&build_test),
+        Ordering::Less
+    );
+    let pipelines = HashSet::from([build_test]);
+    assert!(pipelines.contains(&Pipeline {
+        stages: IndexMap::from([("build", 1), ("test", 2)]),
+    }));
+    assert!(!pipelines.contains(&test_build));
+
+    assert_eq!(
+        Config::Stages(IndexMap::from([("a", 1)])).cmp(&Config::Stages(IndexMap::from([("b", 1)]))),
+        Ordering::Greater
+    );
+}
+
+#[test]
+fn test_on_err() {
+    #[derive(CmpBy, Debug)]
+    #[cmp_by(ErrGreater(parsed_version()))]
+    struct Package {
+        version: &'static str,
+        #[cmp_by(on_err = "less")]
+        size: Result<u32, &'static str>,
+    }
+
+    impl Package {
+        fn parsed_version(&self) -> Result<u32, core::num::ParseIntError> {
+            self.version.parse()
+        }
+    }
+
+    assert_eq!(
+        Package {
+            version: "1",
+            size: Ok(0)
+        }
+        .cmp(&Package {
+            version: "2",
+            size: Ok(0)
+        }),
+        Ordering::Less
+    );
+    assert_eq!(
+        Package {
+            version: "x",
+            size: Ok(0)
+        }
+        .cmp(&Package {
+            version: "2",
+            size: Ok(0)
+        }),
+        Ordering::Greater
+    );
+    assert_eq!(
+        Package {
+            version: "x",
+            size: Ok(0)
+        }
+        .cmp(&Package {
+            version: "y",
+            size: Ok(0)
+        }),
+        Ordering::Equal
+    );
+    assert_eq!(
+        Package {
+            version: "1",
+            size: Err("unknown")
+        }
+        .cmp(&Package {
+            version: "1",
+            size: Ok(0)
+        }),
+        Ordering::Less
+    );
+    assert_eq!(
+        Package {
+            version: "1",
+            size: Ok(2)
+        }
+        .cmp(&Package {
+            version: "1",
+            size: Ok(1)
+        }),
+        Ordering::Greater
+    );
+    assert_eq!(
+        Package {
+            version: "x",
+            size: Err("a")
+        },
+        Package {
+            version: "y",
+            size: Err("b")
+        }
+    );
+    assert_ne!(
+        Package {
+            version: "x",
+            size: Err("a")
+        },
+        Package {
+            version: "1",
+            size: Err("a")
+        }
+    );
+}
+
+#[test]
+fn test_trait_methods() {
+    trait Ranked {
+        fn rank(&self) -> u32;
+    }
+
+    trait Seeded {
+        fn rank(&self) -> u32;
+    }
+
+    #[derive(CmpBy, Debug)]
+    #[cmp_by(<Self as Ranked>::rank(), Seeded::rank())]
+    struct Player {
+        score: u32,
+        seed: u32,
+    }
+
+    impl Ranked for Player {
+        fn rank(&self) -> u32 {
+            self.score
+        }
+    }
+
+    impl Seeded for Player {
+        fn rank(&self) -> u32 {
+            self.seed
+        }
+    }
+
+    assert_eq!(
+        Player { score: 1, seed: 9 }.cmp(&Player { score: 2, seed: 0 }),
+        Ordering::Less
+    );
+    assert_eq!(
+        Player { score: 1, seed: 9 }.cmp(&Player { score: 1, seed: 0 }),
+        Ordering::Greater
+    );
+    assert_eq!(Player { score: 1, seed: 9 }, Player { score: 1, seed: 9 });
+}
+
+#[test]
+fn test_gen_reverse() {
+    use std::collections::BinaryHeap;
+
+    #[derive(CmpBy, Debug)]
+    #[cmp_by(gen_reverse = "RevEvent")]
+    struct Event {
+        #[cmp_by]
+        time: u64,
+        name: &'static str,
+    }
+
+    let mut heap = BinaryHeap::from([
+        RevEvent(Event { time: 3, name: "c" }),
+        RevEvent(Event { time: 1, name: "a" }),
+        RevEvent(Event { time: 2, name: "b" }),
+    ]);
+    assert_eq!(heap.pop().map(|RevEvent(event)| event.name), Some("a"));
+    assert_eq!(heap.pop().map(|RevEvent(event)| event.name), Some("b"));
+    heap.push(RevEvent(Event { time: 0, name: "z" }));
+    assert_eq!(heap.peek().map(|event| event.0.time), Some(0));
+    assert!(RevEvent(Event { time: 1, name: "a" }) == RevEvent(Event { time: 1, name: "b" }));
+}
+
+#[test]
+fn test_many_fields() {
+    use cmp_by_derive::PartialOrdBy;
+
+    #[derive(CmpBy, Debug, Default)]
+    #[cmp_by(all)]
+    struct Wide(
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+    );
+
+    #[derive(CmpBy, Debug, Default)]
+    #[cmp_by(all, descending)]
+    struct Descending(
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+        u8,
+    );
+
+    #[derive(PartialOrdBy, PartialEq, Default)]
+    #[cmp_by(all)]
+    struct Floats(
+        f32,
+        f32,
+        f32,
+        f32,
+        f32,
+        f32,
+        f32,
+        f32,
+        f32,
+        f32,
+        f32,
+        f32,
+        f32,
+        f32,
+        f32,
+        f32,
+        f32,
+        f32,
+        f32,
+        f32,
+        f32,
+        f32,
+        f32,
+        f32,
+        f32,
+        f32,
+        f32,
+        f32,
+        f32,
+        f32,
+    );
+
+    let (mut a, mut b) = (Wide::default(), Wide::default());
+    assert_eq!(a, b);
+    a.29 = 1;
+    assert_eq!(a.cmp(&b), Ordering::Greater);
+    b.0 = 1;
+    assert_eq!(a.cmp(&b), Ordering::Less);
+
+    let (mut a, b) = (Descending::default(), Descending::default());
+    a.29 = 1;
+    assert_eq!(a.cmp(&b), Ordering::Less);
+
+    let (mut a, b) = (Floats::default(), Floats::default());
+    a.29 = 1.;
+    assert_eq!(a.partial_cmp(&b), Some(Ordering::Greater));
+    a.3 = f32::NAN;
+    assert_eq!(a.partial_cmp(&b), None);
+}
+
+#[test]
+fn test_hash_tag() {
+    use cmp_by_derive::HashBy;
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{BuildHasher, BuildHasherDefault};
+
+    #[derive(HashBy)]
+    #[hash_by(tag = 1)]
+    struct Meters(#[hash_by] u32);
+
+    #[derive(HashBy)]
+    #[hash_by(tag = 2)]
+    struct Feet(#[hash_by] u32);
+
+    #[derive(HashBy)]
+    #[hash_by(tag = "Seconds")]
+    struct Seconds(#[hash_by] u32);
+
+    let hasher = BuildHasherDefault::<DefaultHasher>::default();
+    assert_eq!(hasher.hash_one(Meters(3)), hasher.hash_one(Meters(3)));
+    assert_ne!(hasher.hash_one(Meters(3)), hasher.hash_one(Feet(3)));
+    assert_ne!(hasher.hash_one(Meters(3)), hasher.hash_one(3u32));
+    assert_ne!(hasher.hash_one(Seconds(3)), hasher.hash_one(3u32));
+}
+
+#[cfg(feature = "bytemuck")]
+#[test]
+fn test_hash_bytes() {
+    use bytemuck::{Pod, Zeroable};
+    use cmp_by_derive::HashBy;
+    use std::collections::HashSet;
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Pod, Zeroable)]
+    #[repr(C)]
+    struct Pixel {
+        r: u8,
+        g: u8,
+        b: u8,
+        a: u8,
+    }
+
+    #[derive(CmpBy, HashBy, Debug)]
+    #[hash_by(verify_eq)]
+    struct Frame<T> {
+        #[cmp_by]
+        #[hash_by(bytes)]
+        pixels: [Pixel; 4],
+        #[cmp_by]
+        #[hash_by(bytes)]
+        depth: T,
+        #[cmp_by]
+        #[hash_by]
+        id: u32,
+    }
+
+    let frames = HashSet::from([
+        Frame {
+            pixels: [Pixel {
+                r: 1,
+                g: 0,
+                b: 0,
+                a: 255,
+            }; 4],
+            depth: [5u16; 2],
+            id: 1,
+        },
+        Frame {
+            pixels: [Pixel {
+                r: 2,
+                g: 0,
+                b: 0,
+                a: 255,
+            }; 4],
+            depth: [5u16; 2],
+            id: 1,
+        },
+    ]);
+    assert_eq!(frames.len(), 2);
+    assert!(frames.contains(&Frame {
+        pixels: [Pixel {
+            r: 1,
+            g: 0,
+            b: 0,
+            a: 255
+        }; 4],
+        depth: [5u16; 2],
+        id: 1,
+    }));
+    assert!(!frames.contains(&Frame {
+        pixels: [Pixel {
+            r: 1,
+            g: 0,
+            b: 0,
+            a: 255
+        }; 4],
+        depth: [4u16; 2],
+        id: 1,
+    }));
+    assert!(!frames.contains(&Frame {
+        pixels: [Pixel {
+            r: 1,
+            g: 0,
+            b: 0,
+            a: 255
+        }; 4],
+        depth: [5u16; 2],
+        id: 2,
+    }));
+}
+
+#[test]
+fn test_hash_raw() {
+    use cmp_by_derive::HashBy;
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{BuildHasher, BuildHasherDefault};
+
+    #[derive(HashBy)]
+    struct Raw {
+        #[hash_by(raw)]
+        x: u16,
+        #[hash_by(raw)]
+        y: i128,
+        #[hash_by(raw)]
+        name: &'static str,
+    }
+
+    #[derive(Hash)]
+    struct Derived {
+        x: u16,
+        y: i128,
+        name: &'static str,
+    }
+
+    // Integers are written the same way `Hash` does, other types fall back to it.
+    let hasher = BuildHasherDefault::<DefaultHasher>::default();
+    assert_eq!(
+        hasher.hash_one(Raw {
+            x: 1,
+            y: -2,
+            name: "a"
+        }),
+        hasher.hash_one(Derived {
+            x: 1,
+            y: -2,
+            name: "a"
+        })
+    );
+}
+
+#[test]
+fn test_transparent() {
+    use cmp_by_derive::HashBy;
+    use std::collections::HashSet;
+
+    #[derive(CmpBy, HashBy, Debug)]
+    #[cmp_by(transparent)]
+    #[hash_by(transparent)]
+    struct Id(u32);
+
+    #[derive(CmpBy, Debug)]
+    #[cmp_by(transparent)]
+    struct Meters(#[cmp_by(total)] f64);
+
+    assert_eq!(Id(1).cmp(&Id(2)), Ordering::Less);
+    assert!(HashSet::from([Id(1)]).contains(&Id(1)));
+    assert_eq!(Meters(2.5).cmp(&Meters(-1.0)), Ordering::Greater);
+    assert_eq!(Meters(f64::NAN), Meters(f64::NAN));
+}
+
+#[test]
+fn test_index_items() {
+    use cmp_by_derive::HashBy;
+    use std::collections::{BTreeMap, HashSet};
+
+    #[derive(CmpBy, HashBy, Debug)]
+    #[cmp_by(config["timeout"])]
+    #[hash_by(config["timeout"])]
+    struct Service {
+        config: BTreeMap<String, u32>,
+    }
+
+    assert_eq!(
+        Service {
+            config: BTreeMap::from([("timeout".into(), 10), ("retries".into(), 1)])
+        }
+        .cmp(&Service {
+            config: BTreeMap::from([("timeout".into(), 20), ("retries".into(), 0)])
+        }),
+        Ordering::Less
+    );
+    assert_eq!(
+        Service {
+            config: BTreeMap::from([("timeout".into(), 10), ("retries".into(), 1)])
+        },
+        Service {
+            config: BTreeMap::from([("timeout".into(), 10), ("retries".into(), 5)])
+        }
+    );
+    assert!(HashSet::from([Service {
+        config: BTreeMap::from([("timeout".into(), 10), ("retries".into(), 1)])
+    }])
+    .contains(&Service {
+        config: BTreeMap::from([("timeout".into(), 10), ("retries".into(), 5)])
+    }));
+}
+
+#[test]
+fn test_partial_cmp_agrees_with_cmp() {
+    use cmp_by_derive::PartialOrdBy;
+
+    #[derive(CmpBy, Debug)]
+    #[cmp_by(Reverse(name.len()))]
+    struct Total {
+        #[cmp_by(reverse)]
+        priority: u8,
+        #[cmp_by]
+        name: &'static str,
+        #[cmp_by(total, reverse)]
+        weight: f64,
+    }
+
+    #[derive(PartialOrdBy, PartialEq)]
+    #[cmp_by(Reverse(name.len()))]
+    struct Partial {
+        #[cmp_by(reverse)]
+        priority: u8,
+        #[cmp_by]
+        name: &'static str,
+        #[cmp_by(total, reverse)]
+        weight: f64,
+    }
+
+    #[derive(CmpBy, Debug)]
+    #[cmp_by(descending)]
+    struct Descending {
+        #[cmp_by(total)]
+        weight: f64,
+        #[cmp_by]
+        name: &'static str,
+    }
+
+    let values = [1, 2]
+        .into_iter()
+        .flat_map(|priority| ["a", "b", "ab"].map(|name| (priority, name)))
+        .flat_map(|(priority, name)| [-0.0, 0.0, 1.5].map(|weight| (priority, name, weight)))
+        .collect::<Vec<_>>();
+    for &(priority, name, weight) in &values {
+        for &(other_priority, other_name, other_weight) in &values {
+            let (this, other) = (
+                Total {
+                    priority,
+                    name,
+                    weight,
+                },
+                Total {
+                    priority: other_priority,
+                    name: other_name,
+                    weight: other_weight,
+                },
+            );
+            assert_eq!(this.partial_cmp(&other), Some(this.cmp(&other)));
+            assert_eq!(
+                Partial {
+                    priority,
+                    name,
+                    weight
+                }
+                .partial_cmp(&Partial {
+                    priority: other_priority,
+                    name: other_name,
+                    weight: other_weight,
+                }),
+                Some(this.cmp(&other))
+            );
+            let (this, other) = (
+                Descending { weight, name },
+                Descending {
+                    weight: other_weight,
+                    name: other_name,
+                },
+            );
+            assert_eq!(this.partial_cmp(&other), Some(this.cmp(&other)));
+        }
+    }
+}
+
+#[test]
+fn test_epsilon() {
+    use cmp_by_derive::PartialOrdBy;
+
+    #[derive(CmpBy, Debug)]
+    struct Particle {
+        #[cmp_by(epsilon = 0.001)]
+        x: f64,
+        #[cmp_by(epsilon = 0.5, reverse)]
+        mass: f32,
+    }
+
+    #[derive(CmpBy, Debug)]
+    enum Body {
+        Point(#[cmp_by(epsilon = 0.1)] f64),
+    }
+
+    #[derive(PartialOrdBy, PartialEq)]
+    struct Reading {
+        #[cmp_by(epsilon = 0.01)]
+        value: f64,
+    }
+
+    assert_eq!(
+        Particle { x: 1.0, mass: 0.0 },
+        Particle {
+            x: 1.0005,
+            mass: 0.0
+        }
+    );
+    assert_eq!(
+        Particle { x: 1.0, mass: 0.0 }.cmp(&Particle { x: 1.01, mass: 0.0 }),
+        Ordering::Less
+    );
+    assert_eq!(
+        Particle { x: 1.0, mass: 1.0 }.cmp(&Particle { x: 1.0, mass: 1.2 }),
+        Ordering::Equal
+    );
+    assert_eq!(
+        Particle { x: 1.0, mass: 1.0 }.cmp(&Particle { x: 1.0, mass: 2.0 }),
+        Ordering::Greater
+    );
+    assert_eq!(
+        Particle {
+            x: f64::NAN,
+            mass: 0.0
+        },
+        Particle {
+            x: f64::NAN,
+            mass: 0.0
+        }
+    );
+    assert_eq!(Body::Point(0.0).cmp(&Body::Point(0.05)), Ordering::Equal);
+    assert_eq!(Body::Point(0.0).cmp(&Body::Point(-0.5)), Ordering::Greater);
+    assert_eq!(
+        Reading { value: 0.5 }.partial_cmp(&Reading { value: 0.505 }),
+        Some(Ordering::Equal)
+    );
+}
+
+#[test]
+fn test_order_by() {
+    use cmp_by_derive::PartialOrdBy;
+
+    #[derive(CmpBy, Debug)]
+    #[order_by(len())]
+    struct Word {
+        #[order_by(reverse)]
+        text: &'static str,
+    }
+
+    impl Word {
+        fn len(&self) -> usize {
+            self.text.len()
+        }
+    }
+
+    #[derive(PartialOrdBy, PartialEq)]
+    struct Reading {
+        #[order_by]
+        value: f32,
+    }
+
+    assert_eq!(
+        Word { text: "ab" }.cmp(&Word { text: "abc" }),
+        Ordering::Less
+    );
+    assert_eq!(
+        Word { text: "ab" }.cmp(&Word { text: "cd" }),
+        Ordering::Greater
+    );
+    assert_eq!(
+        Reading { value: 1.0 }.partial_cmp(&Reading { value: 2.0 }),
+        Some(Ordering::Less)
+    );
+}
+
+#[test]
+fn test_use_comparator() {
+    type Comparator = Box<dyn Fn(&u32, &u32) -> Ordering>;
+
+    #[derive(CmpBy)]
+    #[cmp_by(use_comparator = "cmp_fn", on = "data")]
+    struct Sorter {
+        cmp_fn: Comparator,
+        data: u32,
+    }
+
+    fn descending(a: &u32, b: &u32) -> Ordering {
+        b.cmp(a)
+    }
+
+    assert_eq!(
+        Sorter {
+            cmp_fn: Box::new(descending),
+            data: 1
+        }
+        .cmp(&Sorter {
+            cmp_fn: Box::new(descending),
+            data: 2
+        }),
+        Ordering::Greater
+    );
+    assert_eq!(
+        Sorter {
+            cmp_fn: Box::new(u32::cmp),
+            data: 1
+        }
+        .cmp(&Sorter {
+            cmp_fn: Box::new(u32::cmp),
+            data: 2
+        }),
+        Ordering::Less
+    );
+    assert!(
+        Sorter {
+            cmp_fn: Box::new(descending),
+            data: 3
+        } == Sorter {
+            cmp_fn: Box::new(u32::cmp),
+            data: 3
+        }
+    );
+    let mut sorters = [
+        Sorter {
+            cmp_fn: Box::new(descending),
+            data: 3,
+        },
+        Sorter {
+            cmp_fn: Box::new(descending),
+            data: 1,
+        },
+        Sorter {
+            cmp_fn: Box::new(descending),
+            data: 2,
+        },
+    ];
+    sorters.sort();
+    assert_eq!(sorters.map(|sorter| sorter.data), [3, 2, 1]);
+}
+
+#[test]
+fn test_cmp_hash_by() {
+    use cmp_by_derive::CmpHashBy;
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    #[derive(CmpHashBy, Debug)]
+    #[cmp_by(initial())]
+    enum Entry {
+        Word {
+            #[cmp_by(case_insensitive, reverse)]
+            text: &'static str,
+            #[cmp_by(total)]
+            weight: f64,
+            #[allow(dead_code)]
+            note: u8,
+        },
+        Number(#[cmp_by] u8, #[allow(dead_code)] u8),
+    }
+
+    impl Entry {
+        fn initial(&self) -> Option<u8> {
+            match self {
+                Entry::Word { text, .. } => {
+                    text.bytes().next().map(|byte| byte.to_ascii_lowercase())
+                }
+                Entry::Number(..) => None,
+            }
+        }
+    }
+
+    fn hash(entry: &Entry) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        entry.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    let mut entries = Vec::new();
+    for text in ["a", "A", "ab", "b"] {
+        for weight in [0.0, -0.0, 1.0] {
+            for note in [0, 1] {
+                entries.push(Entry::Word { text, weight, note });
+            }
+        }
+    }
+    for value in [0, 1] {
+        for ignored in [0, 1] {
+            entries.push(Entry::Number(value, ignored));
+        }
+    }
+    // Exactly the values that compare equal hash the same.
+    for a in &entries {
+        for b in &entries {
+            assert_eq!(a == b, hash(a) == hash(b), "{a:?} and {b:?}");
+            assert_eq!(a == b, a.cmp(b) == Ordering::Equal);
+        }
+    }
+}
@@ -0,0 +1,69 @@
+#![no_std]
+
+use cmp_by_derive::{CmpBy, HashBy};
+use core::cmp::Ordering;
+use core::hash::{Hash, Hasher};
+
+/// Minimal FNV-1a hasher, since `no_std` has no default hasher.
+struct Fnv(u64);
+
+impl Hasher for Fnv {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.0 = (self.0 ^ u64::from(*byte)).wrapping_mul(0x100000001b3);
+        }
+    }
+}
+
+fn hash<T: Hash>(value: &T) -> u64 {
+    let mut hasher = Fnv(0xcbf29ce484222325);
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[derive(CmpBy, HashBy)]
+#[cmp_by(channel())]
+#[hash_by(channel())]
+enum Message {
+    Note { channel: u8 },
+    Clock,
+}
+
+impl Message {
+    fn channel(&self) -> Option<u8> {
+        match self {
+            Message::Note { channel, .. } => Some(*channel),
+            Message::Clock => None,
+        }
+    }
+}
+
+#[derive(CmpBy, HashBy)]
+struct Sample {
+    #[cmp_by(total)]
+    value: f32,
+    #[cmp_by]
+    #[hash_by]
+    id: u32,
+}
+
+#[test]
+fn test_no_std() {
+    assert_eq!(
+        Message::Clock.cmp(&Message::Note { channel: 0 }),
+        Ordering::Less
+    );
+    assert_eq!(
+        Sample { value: 1.0, id: 0 }.cmp(&Sample { value: 2.0, id: 0 }),
+        Ordering::Less
+    );
+    assert_eq!(
+        hash(&Sample { value: 1.0, id: 3 }),
+        hash(&Sample { value: 2.0, id: 3 })
+    );
+    assert_eq!(hash(&Message::Clock), hash(&Message::Clock));
+}
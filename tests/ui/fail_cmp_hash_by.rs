@@ -0,0 +1,28 @@
+use cmp_by_derive::CmpHashBy;
+use std::cmp::Ordering;
+
+#[derive(CmpHashBy)]
+struct Position {
+    #[cmp_by(epsilon = 0.001)]
+    x: f64,
+}
+
+#[derive(CmpHashBy)]
+#[cmp_by(Reverse(name()))]
+struct User {
+    #[cmp_by(with = "cmp_ids")]
+    id: u64,
+    name: String,
+}
+
+impl User {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+fn cmp_ids(a: &u64, b: &u64) -> Ordering {
+    (a % 100).cmp(&(b % 100))
+}
+
+fn main() {}
@@ -0,0 +1,17 @@
+use cmp_by_derive::CmpBy;
+
+#[derive(CmpBy)]
+#[cmp_by(_fields, len())]
+#[cmp_by(_fields)]
+struct Thing {
+    #[cmp_by]
+    a: u32,
+}
+
+impl Thing {
+    fn len(&self) -> usize {
+        0
+    }
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use cmp_by_derive::CmpBy;
+
+#[derive(CmpBy)]
+#[cmp_by()]
+struct Thing {
+    a: u32,
+    b: u64,
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use cmp_by_derive::EqBy;
+
+#[derive(EqBy)]
+struct Measure {
+    #[eq_by]
+    id: u32,
+    #[eq_by]
+    value: f64,
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use cmp_by_derive::CmpBy;
+
+#[derive(CmpBy)]
+#[cmp_by(eq_only, gen_key)]
+struct Thing {
+    #[cmp_by]
+    a: u32,
+    #[cmp_by(reverse)]
+    b: u64,
+}
+
+fn main() {}
@@ -0,0 +1,17 @@
+use cmp_by_derive::HashBy;
+
+#[derive(PartialEq, Eq, HashBy)]
+#[derive(Hash)]
+struct Point {
+    #[hash_by]
+    x: u32,
+}
+
+#[derive(PartialEq, Eq, HashBy)]
+#[derive(Clone, std::hash::Hash)]
+struct Size {
+    #[hash_by]
+    width: u32,
+}
+
+fn main() {}
@@ -0,0 +1,18 @@
+use cmp_by_derive::{CmpBy, HashBy};
+
+#[derive(CmpBy, HashBy)]
+#[hash_by(verify_eq)]
+struct Position {
+    #[cmp_by(epsilon = 0.001)]
+    #[hash_by]
+    x: f64,
+}
+
+#[derive(CmpBy, HashBy)]
+struct Speed {
+    #[cmp_by]
+    #[hash_by(epsilon = 0.001)]
+    value: u64,
+}
+
+fn main() {}
@@ -0,0 +1,30 @@
+use cmp_by_derive::CmpBy;
+
+trait SortKey {
+    type Key<'a>: Ord
+    where
+        Self: 'a;
+
+    fn sort_key(&self) -> Self::Key<'_>;
+}
+
+#[derive(CmpBy)]
+#[cmp_by(impl_sort_key, total())]
+struct Order {
+    #[cmp_by(by_len)]
+    items: Vec<u32>,
+}
+
+impl Order {
+    fn total(&self) -> u32 {
+        self.items.iter().sum()
+    }
+}
+
+#[derive(CmpBy)]
+#[cmp_by(impl_sort_key)]
+enum Shape {
+    Circle(#[cmp_by] u32),
+}
+
+fn main() {}
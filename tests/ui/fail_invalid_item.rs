@@ -0,0 +1,24 @@
+use cmp_by_derive::CmpBy;
+
+#[derive(CmpBy)]
+#[cmp_by(a, 3 + , b)]
+struct Thing {
+    #[cmp_by]
+    a: u32,
+}
+
+#[derive(CmpBy)]
+#[cmp_by(a, [1, 2], b)]
+struct Other {
+    #[cmp_by]
+    a: u32,
+}
+
+#[derive(CmpBy)]
+#[cmp_by(a,, b)]
+struct Empty {
+    #[cmp_by]
+    a: u32,
+}
+
+fn main() {}
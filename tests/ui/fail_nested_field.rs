@@ -0,0 +1,15 @@
+use cmp_by_derive::CmpBy;
+
+struct Metadata {
+    timestamp: u64,
+}
+
+#[derive(CmpBy)]
+struct Post {
+    #[cmp_by = "metadata.timestamp"]
+    meta: Metadata,
+    #[cmp_by = 3]
+    id: u32,
+}
+
+fn main() {}
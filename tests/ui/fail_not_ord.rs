@@ -0,0 +1,11 @@
+use cmp_by_derive::CmpBy;
+
+#[derive(CmpBy)]
+struct Sample {
+    #[cmp_by]
+    id: u32,
+    #[cmp_by]
+    weight: f32,
+}
+
+fn main() {}
@@ -0,0 +1,17 @@
+use cmp_by_derive::{CmpBy, HashBy};
+
+#[derive(CmpBy)]
+union Number {
+    #[cmp_by]
+    int: u32,
+    float: f32,
+}
+
+#[derive(HashBy)]
+union Bits {
+    #[hash_by]
+    int: u32,
+    float: f32,
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use cmp_by_derive::CmpBy;
+
+#[derive(CmpBy)]
+#[cmp_by(borrow_key = "str")]
+struct Event {
+    #[cmp_by]
+    name: String,
+    #[cmp_by]
+    at: u64,
+}
+
+fn main() {}
@@ -0,0 +1,25 @@
+use cmp_by_derive::CmpBy;
+
+#[derive(CmpBy)]
+#[cmp_by(const_fn)]
+struct Named {
+    #[cmp_by]
+    id: u32,
+    #[cmp_by]
+    name: String,
+}
+
+#[derive(CmpBy)]
+#[cmp_by(const_fn, len())]
+struct Sized {
+    #[cmp_by]
+    id: u32,
+}
+
+impl Sized {
+    fn len(&self) -> usize {
+        0
+    }
+}
+
+fn main() {}
@@ -0,0 +1,13 @@
+use cmp_by_derive::CmpBy;
+
+#[derive(CmpBy)]
+struct Thing {
+    #[cmp_by(order = 1)]
+    a: u32,
+    #[cmp_by(order = 0)]
+    b: u64,
+    #[cmp_by(order = 1)]
+    c: u64,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use cmp_by_derive::CmpBy;
+
+#[derive(CmpBy)]
+#[cmp_by(0)]
+enum Shape {
+    Circle(u32),
+    Square(u32),
+}
+
+fn main() {}
@@ -0,0 +1,16 @@
+use cmp_by_derive::{CmpBy, HashBy};
+
+#[derive(CmpBy, HashBy)]
+#[hash_by(verify_eq)]
+struct Thing {
+    #[cmp_by]
+    #[hash_by]
+    a: u32,
+    #[hash_by]
+    b: u64,
+    #[cmp_by(key = "str::len")]
+    #[hash_by]
+    c: String,
+}
+
+fn main() {}
@@ -0,0 +1,17 @@
+use cmp_by_derive::CmpBy;
+
+#[derive(CmpBy)]
+struct Unparsable {
+    #[cmp_by(with = "cmp::by len")]
+    a: Vec<u32>,
+}
+
+#[derive(CmpBy)]
+struct Unresolved {
+    #[cmp_by(with = "cmp_lenght")]
+    a: Vec<u32>,
+    #[cmp_by(key = "str::lenght")]
+    b: String,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use cmp_by_derive::CmpBy;
+
+#[derive(CmpBy)]
+struct Tag {
+    #[cmp_by(map = |name| name.len() + self.offset)]
+    name: String,
+    offset: usize,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use cmp_by_derive::CmpBy;
+
+#[derive(CmpBy)]
+#[cmp_by(naem, id)]
+struct User {
+    name: String,
+    id: u32,
+}
+
+fn main() {}
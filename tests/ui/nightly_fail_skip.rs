@@ -0,0 +1,22 @@
+use cmp_by_derive::CmpBy;
+
+#[derive(CmpBy)]
+#[cmp_by(all)]
+struct Cached {
+    a: u32,
+    #[cmp_by]
+    #[cmp_by(skip)]
+    b: u64,
+    #[cmp_by(skip, reverse)]
+    c: u64,
+}
+
+#[derive(CmpBy)]
+struct NotAll {
+    #[cmp_by]
+    a: u32,
+    #[cmp_by(skip)]
+    b: u64,
+}
+
+fn main() {}
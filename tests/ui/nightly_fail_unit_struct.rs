@@ -0,0 +1,6 @@
+use cmp_by_derive::CmpBy;
+
+#[derive(CmpBy)]
+struct Marker;
+
+fn main() {}